@@ -0,0 +1,265 @@
+//! Per-connection algorithm preferences, for the two ends of the spectrum:
+//! ancient appliances that only speak aes128-cbc / ssh-rsa and hardened hosts
+//! limited to chacha20 / ed25519. Names are the OpenSSH ones (`ssh -Q`).
+
+use russh::keys::Algorithm;
+use russh::{cipher, compression, kex, mac, Names, Preferred};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Preferred lists (most preferred first). An empty list keeps the built-in
+/// order for that kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AlgorithmPrefs {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher: Vec<String>,
+    pub mac: Vec<String>,
+}
+
+/// What the last key exchange of a connection agreed on
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NegotiatedAlgorithms {
+    pub kex: String,
+    pub host_key: String,
+    pub cipher: String,
+    pub mac: String,
+    pub compression: String,
+}
+
+impl From<&Names> for NegotiatedAlgorithms {
+    fn from(names: &Names) -> Self {
+        NegotiatedAlgorithms {
+            kex: names.kex.as_ref().to_string(),
+            host_key: names.key.as_str().to_string(),
+            cipher: names.cipher.as_ref().to_string(),
+            mac: names.client_mac.as_ref().to_string(),
+            compression: compression_name(&names.client_compression).to_string(),
+        }
+    }
+}
+
+fn compression_name(compression: &compression::Compression) -> &'static str {
+    match compression {
+        compression::Compression::None => "none",
+        compression::Compression::Zlib => "zlib",
+        compression::Compression::ZlibOpenSSH => "zlib@openssh.com",
+    }
+}
+
+// "none" ciphers/MACs/kex are never offered, even if the user asks
+fn is_real(name: &str) -> bool {
+    name != "none" && name != "clear"
+}
+
+fn supported_kex() -> Vec<&'static kex::Name> {
+    kex::ALL_KEX_ALGORITHMS
+        .iter()
+        .copied()
+        .filter(|n| is_real(n.as_ref()))
+        .collect()
+}
+
+fn supported_host_keys() -> Vec<&'static Algorithm> {
+    // No DSA (the dsa feature is off) and no security-key types: those are
+    // user keys, never host keys
+    russh::keys::key::ALL_KEY_TYPES
+        .iter()
+        .filter(|a| {
+            !matches!(
+                a,
+                Algorithm::Dsa | Algorithm::SkEcdsaSha2NistP256 | Algorithm::SkEd25519
+            )
+        })
+        .collect()
+}
+
+fn supported_ciphers() -> Vec<&'static cipher::Name> {
+    cipher::ALL_CIPHERS
+        .iter()
+        .copied()
+        .filter(|n| is_real(n.as_ref()))
+        .collect()
+}
+
+fn supported_macs() -> Vec<&'static mac::Name> {
+    mac::ALL_MAC_ALGORITHMS
+        .iter()
+        .copied()
+        .filter(|n| is_real(n.as_ref()))
+        .collect()
+}
+
+/// Resolve every name of `wanted` against `supported`, or explain which one
+/// is unknown and what the valid values are
+fn resolve<T: Clone>(
+    kind: &str,
+    wanted: &[String],
+    supported: &[T],
+    name_of: impl Fn(&T) -> String,
+) -> Result<Vec<T>, String> {
+    wanted
+        .iter()
+        .map(|w| {
+            let w = w.trim();
+            supported
+                .iter()
+                .find(|s| name_of(s).eq_ignore_ascii_case(w))
+                .cloned()
+                .ok_or_else(|| {
+                    let valid: Vec<String> = supported.iter().map(&name_of).collect();
+                    format!(
+                        "unsupported {} algorithm \"{}\" (supported: {})",
+                        kind,
+                        w,
+                        valid.join(", ")
+                    )
+                })
+        })
+        .collect()
+}
+
+impl AlgorithmPrefs {
+    /// Every value accepted by `apply`, in the default preference order
+    pub fn supported() -> Self {
+        AlgorithmPrefs {
+            kex: supported_kex()
+                .iter()
+                .map(|n| n.as_ref().to_string())
+                .collect(),
+            host_key: supported_host_keys()
+                .iter()
+                .map(|a| a.as_str().to_string())
+                .collect(),
+            cipher: supported_ciphers()
+                .iter()
+                .map(|n| n.as_ref().to_string())
+                .collect(),
+            mac: supported_macs()
+                .iter()
+                .map(|n| n.as_ref().to_string())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kex.is_empty()
+            && self.host_key.is_empty()
+            && self.cipher.is_empty()
+            && self.mac.is_empty()
+    }
+
+    /// Replace the lists of `preferred` the user chose. Nothing is changed
+    /// when any name is invalid.
+    pub fn apply(&self, preferred: &mut Preferred) -> Result<(), String> {
+        let kex = resolve("kex", &self.kex, &supported_kex(), |n| {
+            n.as_ref().to_string()
+        })?;
+        let host_key = resolve("host key", &self.host_key, &supported_host_keys(), |a| {
+            a.as_str().to_string()
+        })?;
+        let cipher = resolve("cipher", &self.cipher, &supported_ciphers(), |n| {
+            n.as_ref().to_string()
+        })?;
+        let mac = resolve("MAC", &self.mac, &supported_macs(), |n| {
+            n.as_ref().to_string()
+        })?;
+
+        if !kex.is_empty() {
+            let mut names: Vec<kex::Name> = kex.into_iter().copied().collect();
+            // The OpenSSH extensions travel in the kex list: keep them so
+            // rsa-sha2 user auth and strict kex still work
+            names.push(kex::EXTENSION_SUPPORT_AS_CLIENT);
+            names.push(kex::EXTENSION_OPENSSH_STRICT_KEX_AS_CLIENT);
+            preferred.kex = Cow::Owned(names);
+        }
+        if !host_key.is_empty() {
+            preferred.key = Cow::Owned(host_key.into_iter().cloned().collect());
+        }
+        if !cipher.is_empty() {
+            preferred.cipher = Cow::Owned(cipher.into_iter().copied().collect());
+        }
+        if !mac.is_empty() {
+            preferred.mac = Cow::Owned(mac.into_iter().copied().collect());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names<T: AsRef<str>>(list: &[T]) -> Vec<&str> {
+        list.iter().map(|n| n.as_ref()).collect()
+    }
+
+    #[test]
+    fn apply_replaces_only_the_lists_given() {
+        let prefs = AlgorithmPrefs {
+            kex: vec!["diffie-hellman-group14-sha1".into()],
+            host_key: vec!["ssh-rsa".into()],
+            cipher: vec!["AES128-CBC".into()],
+            mac: Vec::new(),
+        };
+        let mut preferred = Preferred::DEFAULT;
+        prefs.apply(&mut preferred).unwrap();
+
+        assert_eq!(
+            names(&preferred.kex),
+            [
+                "diffie-hellman-group14-sha1",
+                "ext-info-c",
+                "kex-strict-c-v00@openssh.com"
+            ]
+        );
+        assert_eq!(
+            preferred
+                .key
+                .iter()
+                .map(Algorithm::as_str)
+                .collect::<Vec<_>>(),
+            ["ssh-rsa"]
+        );
+        assert_eq!(names(&preferred.cipher), ["aes128-cbc"]);
+        assert_eq!(preferred.mac, Preferred::DEFAULT.mac);
+    }
+
+    #[test]
+    fn unknown_names_list_the_supported_values() {
+        let prefs = AlgorithmPrefs {
+            cipher: vec![
+                "chacha20-poly1305@openssh.com".into(),
+                "blowfish-cbc".into(),
+            ],
+            ..Default::default()
+        };
+        let mut preferred = Preferred::DEFAULT;
+        let err = prefs.apply(&mut preferred).unwrap_err();
+        assert!(err.contains("unsupported cipher algorithm \"blowfish-cbc\""));
+        assert!(err.contains("aes128-ctr"));
+        assert_eq!(preferred.cipher, Preferred::DEFAULT.cipher);
+
+        // "none" exists in russh but is never accepted
+        let prefs = AlgorithmPrefs {
+            mac: vec!["none".into()],
+            ..Default::default()
+        };
+        assert!(prefs.apply(&mut Preferred::default()).is_err());
+    }
+
+    #[test]
+    fn supported_lists_exclude_none_and_user_only_key_types() {
+        let supported = AlgorithmPrefs::supported();
+        assert!(supported.kex.contains(&"curve25519-sha256".to_string()));
+        assert!(supported.host_key.contains(&"ssh-ed25519".to_string()));
+        assert!(supported.host_key.contains(&"rsa-sha2-512".to_string()));
+        assert!(!supported.host_key.iter().any(|k| k.starts_with("sk-")));
+        assert!(!supported.cipher.iter().any(|c| c == "none" || c == "clear"));
+        assert!(!supported.mac.contains(&"none".to_string()));
+        // Every advertised value is accepted back
+        supported.apply(&mut Preferred::default()).unwrap();
+    }
+}
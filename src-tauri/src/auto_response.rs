@@ -0,0 +1,215 @@
+//! Expect-style answers typed by the backend: per-session rules (pattern →
+//! response) matched against the recent output of the channel, after
+//! `PlainText` (colors and cursor movements don't get in the way of a
+//! pattern). Full-screen programs are one line there, so nothing inside
+//! vim or htop is ever answered.
+//!
+//! Matching happens at every flush (never per byte) on the last `WINDOW`
+//! bytes of text. After a match the text seen so far is dropped: the echo
+//! of the answer ("[y/N] y") doesn't fire the same rule again.
+
+use crate::db::AutoResponseRule;
+use crate::plain_text::PlainText;
+use regex::Regex;
+
+/// How much recent output a pattern can span
+const WINDOW: usize = 2048;
+
+/// A rule matched: `response` is to be typed
+#[derive(Debug, PartialEq)]
+pub struct Fired {
+    pub pattern: String,
+    pub response: String,
+}
+
+struct Rule {
+    regex: Regex,
+    response: String,
+    once: bool,
+}
+
+pub struct AutoResponder {
+    screen: PlainText,
+    rules: Vec<Rule>,
+    /// Completed lines, at most `WINDOW` bytes
+    window: String,
+}
+
+impl AutoResponder {
+    /// None without usable rules. A rule whose pattern doesn't compile (or
+    /// is blank) is skipped with a warning, the others still work.
+    pub fn new(name: &str, rules: &[AutoResponseRule]) -> Option<Self> {
+        let rules: Vec<Rule> = rules
+            .iter()
+            .filter(|rule| !rule.pattern.trim().is_empty())
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(regex) => Some(Rule {
+                    regex,
+                    response: unescape(&rule.response),
+                    once: rule.once,
+                }),
+                Err(e) => {
+                    log::warn!("{}: invalid auto-response pattern: {}", name, e);
+                    None
+                }
+            })
+            .collect();
+        (!rules.is_empty()).then(|| AutoResponder {
+            screen: PlainText::default(),
+            rules,
+            window: String::new(),
+        })
+    }
+
+    /// Output shown by the terminal (a whole flush). At most one rule fires
+    /// per call, the first one in order that matches.
+    pub fn output(&mut self, text: &str) -> Option<Fired> {
+        self.window.push_str(&self.screen.feed(text));
+        trim_front(&mut self.window, WINDOW);
+        // Only the end of the open line: a progress bar or a binary without
+        // newlines must not be copied whole at every flush
+        let mut haystack = self.window.clone();
+        haystack.push_str(&self.screen.partial_tail(WINDOW));
+        trim_front(&mut haystack, WINDOW);
+
+        let index = self
+            .rules
+            .iter()
+            .position(|rule| rule.regex.is_match(&haystack))?;
+        let fired = Fired {
+            pattern: self.rules[index].regex.as_str().to_string(),
+            response: self.rules[index].response.clone(),
+        };
+        if self.rules[index].once {
+            self.rules.remove(index);
+        }
+        self.window.clear();
+        self.screen.take_partial();
+        Some(fired)
+    }
+
+    /// Every rule was a `once` one and fired
+    pub fn exhausted(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Keep the last `max` bytes of `text`, cut on a char boundary
+fn trim_front(text: &mut String, max: usize) {
+    if text.len() <= max {
+        return;
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    text.drain(..start);
+}
+
+/// `\r`, `\n`, `\t` and `\\` in a response; any other backslash stays
+fn unescape(response: &str) -> String {
+    let mut out = String::with_capacity(response.len());
+    let mut chars = response.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => out.push('\r'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, response: &str, once: bool) -> AutoResponseRule {
+        AutoResponseRule {
+            pattern: pattern.into(),
+            response: response.into(),
+            once,
+        }
+    }
+
+    #[test]
+    fn pager_prompt_is_answered_every_time() {
+        let mut responder = AutoResponder::new("t", &[rule(r"--More--\s*$", " ", false)]).unwrap();
+        assert_eq!(responder.output("line 1\r\nline 2\r\n"), None);
+        // Colored and split between two flushes
+        assert_eq!(responder.output("\x1b[7m--Mo"), None);
+        let fired = responder.output("re--\x1b[0m").unwrap();
+        assert_eq!(fired.response, " ");
+        // The pager erases its prompt and prints the next page
+        assert_eq!(responder.output("\r\x1b[Kline 3\r\n"), None);
+        assert!(responder.output("\x1b[7m--More--\x1b[0m").is_some());
+        assert!(!responder.exhausted());
+    }
+
+    #[test]
+    fn long_open_lines_are_matched_on_their_end() {
+        let mut responder = AutoResponder::new("t", &[rule(r"\[y/N\]$", "y\\r", false)]).unwrap();
+        for _ in 0..100 {
+            assert_eq!(responder.output(&"#".repeat(30)), None);
+        }
+        assert!(responder.output(" Continue? [y/N] ").is_some());
+    }
+
+    #[test]
+    fn once_rules_fire_once_and_the_echo_doesnt_refire() {
+        let mut responder = AutoResponder::new(
+            "t",
+            &[
+                rule(r"\[y/N\]", "y\\r", false),
+                rule("Press any key", "\\r", true),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            responder.output("Press any key to continue"),
+            Some(Fired {
+                pattern: "Press any key".into(),
+                response: "\r".into(),
+            })
+        );
+        assert_eq!(responder.output("\r\nPress any key to continue"), None);
+
+        let fired = responder.output("\r\nProceed? [y/N] ").unwrap();
+        assert_eq!(fired.response, "y\r");
+        // The echo completes the line that matched: not a new prompt
+        assert_eq!(responder.output("y\r\nDone.\r\n$ "), None);
+    }
+
+    #[test]
+    fn bad_patterns_are_skipped_and_windows_stay_bounded() {
+        assert!(AutoResponder::new("t", &[rule("(unclosed", "x", false)]).is_none());
+        assert!(AutoResponder::new("t", &[rule("  ", "x", false)]).is_none());
+        let mut responder = AutoResponder::new(
+            "t",
+            &[rule("(unclosed", "x", false), rule("^START", "x", false)],
+        )
+        .unwrap();
+        // Far more than the window, with multi-byte chars on the cut
+        let long = format!("START{}\r\n", "ñ".repeat(WINDOW));
+        assert_eq!(responder.output(&long), None);
+        assert!(responder.window.len() <= WINDOW);
+    }
+
+    #[test]
+    fn escapes_in_responses() {
+        assert_eq!(unescape(r"y\r"), "y\r");
+        assert_eq!(unescape(r"a\tb\nc"), "a\tb\nc");
+        assert_eq!(unescape(r"C:\\dir\x"), "C:\\dir\\x");
+        assert_eq!(unescape("trail\\"), "trail\\");
+    }
+}
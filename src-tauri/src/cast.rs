@@ -0,0 +1,133 @@
+//! Terminal recordings in asciinema cast v2 format (`asciinema play`): a
+//! JSON header line, then one `[seconds, code, data]` line per event, "o"
+//! for output, "i" for input and "r" for resizes ("COLSxROWS").
+
+use serde_json::json;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub struct CastRecorder {
+    file: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    /// Keystrokes too: off unless asked for (passwords typed are in them)
+    record_input: bool,
+}
+
+impl CastRecorder {
+    /// Create (or truncate) `path` and write the header for a cols×rows
+    /// terminal
+    pub fn create(
+        path: &Path,
+        cols: u32,
+        rows: u32,
+        term: Option<&str>,
+        record_input: bool,
+    ) -> io::Result<Self> {
+        let mut header = json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        });
+        if let Some(term) = term {
+            header["env"] = json!({ "TERM": term });
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", header)?;
+        Ok(CastRecorder {
+            file,
+            path: path.to_path_buf(),
+            started: Instant::now(),
+            record_input,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn output(&mut self, text: &str) -> io::Result<()> {
+        self.event("o", text)
+    }
+
+    pub fn input(&mut self, text: &str) -> io::Result<()> {
+        if !self.record_input {
+            return Ok(());
+        }
+        self.event("i", text)
+    }
+
+    pub fn resize(&mut self, cols: u32, rows: u32) -> io::Result<()> {
+        self.event("r", &format!("{}x{}", cols, rows))
+    }
+
+    /// Flush and close: the file is complete for `asciinema play`
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        // Microsecond precision, like asciinema itself
+        let seconds = (self.started.elapsed().as_micros() as f64) / 1_000_000.0;
+        writeln!(self.file, "{}", json!([seconds, code, data]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cast_file_is_a_header_then_events() {
+        let path = std::env::temp_dir().join(format!("ori-cast-{}.cast", uuid::Uuid::new_v4()));
+        let mut cast = CastRecorder::create(&path, 80, 24, Some("xterm-256color"), false).unwrap();
+        cast.output("$ ls\r\n").unwrap();
+        cast.input("ls\r").unwrap();
+        cast.resize(120, 40).unwrap();
+        cast.output("\x1b[01;34mbin\x1b[0m\r\n").unwrap();
+        cast.finish().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[0]["height"], 24);
+        assert_eq!(lines[0]["env"]["TERM"], "xterm-256color");
+        // Input is not recorded unless asked for
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "$ ls\r\n");
+        assert_eq!(lines[2][1], "r");
+        assert_eq!(lines[2][2], "120x40");
+        assert_eq!(lines[3][2], "\x1b[01;34mbin\x1b[0m\r\n");
+        assert!(lines[1][0].as_f64().unwrap() <= lines[3][0].as_f64().unwrap());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn input_is_recorded_when_enabled() {
+        let path = std::env::temp_dir().join(format!("ori-cast-{}.cast", uuid::Uuid::new_v4()));
+        let mut cast = CastRecorder::create(&path, 80, 24, None, true).unwrap();
+        cast.input("ls\r").unwrap();
+        cast.finish().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let event: serde_json::Value = serde_json::from_str(text.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(event[1], "i");
+        assert_eq!(event[2], "ls\r");
+        std::fs::remove_file(&path).ok();
+    }
+}
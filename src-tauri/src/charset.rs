@@ -0,0 +1,140 @@
+//! Terminal charset of legacy servers (AIX/Solaris boxes, network gear) that
+//! don't speak UTF-8: their output is decoded to UTF-8 before `pty_output`
+//! and keystrokes are encoded back before reaching the channel.
+
+use encoding_rs::{Decoder, EncoderResult, Encoding, UTF_8};
+use thiserror::Error;
+
+/// Session charset when none is set
+pub const DEFAULT_ENCODING: &str = "utf-8";
+
+#[derive(Error, Debug)]
+pub enum CharsetError {
+    #[error("Unsupported terminal encoding: {0}")]
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Charset(&'static Encoding);
+
+impl Charset {
+    /// WHATWG labels ("utf-8", "latin1", "gbk", "shift_jis", "euc-kr"…),
+    /// also spelled "latin-1" / "shift-jis". Empty means UTF-8.
+    ///
+    /// As in browsers, "latin1" / "iso-8859-1" is windows-1252 (what `name`
+    /// says): the same but for 0x80–0x9F, C1 controls in ISO-8859-1 and
+    /// printable (€, “, ”, …) in windows-1252. Those bytes are shown as the
+    /// windows-1252 characters.
+    pub fn from_label(label: &str) -> Result<Self, CharsetError> {
+        let label = label.trim();
+        if label.is_empty() {
+            return Ok(Charset(UTF_8));
+        }
+        let encoding = Encoding::for_label(label.as_bytes())
+            .or_else(|| Encoding::for_label(label.replace('-', "_").as_bytes()))
+            .or_else(|| Encoding::for_label(label.replace(['-', '_'], "").as_bytes()))
+            .ok_or_else(|| CharsetError::Unsupported(label.to_string()))?;
+        // UTF-16 and "replacement" can't be written back as themselves
+        if encoding.output_encoding() != encoding {
+            return Err(CharsetError::Unsupported(label.to_string()));
+        }
+        Ok(Charset(encoding))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    /// Streaming decoder for one shell's output. None for UTF-8: that path
+    /// keeps split sequences itself.
+    pub fn decoder(&self) -> Option<Decoder> {
+        (self.0 != UTF_8).then(|| self.0.new_decoder_without_bom_handling())
+    }
+
+    /// Keystrokes / pasted text in the server's charset; characters it
+    /// can't represent are sent as '?'
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        if self.0 == UTF_8 {
+            return text.as_bytes().to_vec();
+        }
+        let mut encoder = self.0.new_encoder();
+        let mut out = Vec::with_capacity(text.len() + 8);
+        let mut rest = text;
+        loop {
+            let (result, read) =
+                encoder.encode_from_utf8_to_vec_without_replacement(rest, &mut out, true);
+            rest = &rest[read..];
+            match result {
+                EncoderResult::InputEmpty => return out,
+                EncoderResult::OutputFull => out.reserve(rest.len() + 8),
+                EncoderResult::Unmappable(_) => {
+                    out.reserve(1);
+                    out.push(b'?');
+                }
+            }
+        }
+    }
+}
+
+/// Decode the bytes read so far. An incomplete multi-byte sequence at the
+/// end (or an ISO-2022 shift state) stays inside `decoder` for the next call.
+pub fn decode(decoder: &mut Decoder, bytes: &[u8]) -> String {
+    let capacity = decoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len() * 3 + 16);
+    let mut text = String::with_capacity(capacity);
+    let _ = decoder.decode_to_string(bytes, &mut text, false);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_accept_common_spellings() {
+        assert_eq!(Charset::from_label("").unwrap().name(), "UTF-8");
+        assert_eq!(
+            Charset::from_label("latin-1").unwrap().name(),
+            "windows-1252"
+        );
+        assert_eq!(Charset::from_label(" GBK ").unwrap().name(), "GBK");
+        assert_eq!(
+            Charset::from_label("shift-jis").unwrap().name(),
+            "Shift_JIS"
+        );
+        assert!(Charset::from_label("utf-16").is_err());
+        assert!(Charset::from_label("klingon").is_err());
+    }
+
+    #[test]
+    fn latin1_output_and_input_roundtrip() {
+        let charset = Charset::from_label("latin1").unwrap();
+        let mut decoder = charset.decoder().unwrap();
+        assert_eq!(decode(&mut decoder, b"Espa\xf1a"), "España");
+        assert_eq!(charset.encode("año"), b"a\xf1o");
+        // Really windows-1252: 0x80–0x9F are printable, not C1 controls (€
+        // isn't in ISO-8859-1); → is in neither
+        assert_eq!(charset.name(), "windows-1252");
+        assert_eq!(charset.encode("a€→b"), b"a\x80?b");
+        assert_eq!(decode(&mut decoder, b"\x93ok\x94"), "“ok”");
+    }
+
+    #[test]
+    fn gbk_sequence_split_across_reads_is_kept() {
+        let charset = Charset::from_label("gbk").unwrap();
+        let bytes = charset.encode("中文");
+        assert_eq!(bytes, [0xd6, 0xd0, 0xce, 0xc4]);
+
+        let mut decoder = charset.decoder().unwrap();
+        assert_eq!(decode(&mut decoder, &bytes[..3]), "中");
+        assert_eq!(decode(&mut decoder, &bytes[3..]), "文");
+    }
+
+    #[test]
+    fn utf8_needs_no_decoder() {
+        let charset = Charset::from_label(DEFAULT_ENCODING).unwrap();
+        assert!(charset.decoder().is_none());
+        assert_eq!(charset.encode("ñ"), "ñ".as_bytes());
+    }
+}
@@ -0,0 +1,317 @@
+//! What a failed command sends the frontend: `{ kind, message, details? }`.
+//! The UI decides on `kind` (wrong password, unreachable host, changed host
+//! key...) instead of matching the message, which stays the English text of
+//! the error for logs and toasts. `details` carries what a kind needs to be
+//! acted on: both fingerprints of a changed host key, the fingerprint to
+//! trust of an unknown one, the phases a connection test got through.
+
+use crate::local::LocalError;
+use crate::proxy::ProxyError;
+use crate::ssh::SshError;
+use crate::wol::WolError;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorKind {
+    /// The server (or a jump host) rejected the credentials
+    Auth,
+    /// The key is encrypted and its passphrase is missing or wrong
+    KeyPassphrase,
+    /// Not in known_hosts: `details` has host, port, keyType, fingerprint
+    HostKeyUnknown,
+    /// Different from known_hosts: `details` is the HostKeyChange
+    HostKeyChanged,
+    /// known_hosts unreadable, bad signature...
+    HostKey,
+    /// Name resolution, refused or reset connections, protocol failures
+    Network,
+    Timeout,
+    Proxy,
+    ProxyAuth,
+    /// Saved session, open channel, terminal or remote path that isn't there
+    NotFound,
+    PermissionDenied,
+    /// `ssh_cancel_connect` / `ssh_cancel_queue`: not a failure to report
+    Cancelled,
+    /// Settings or arguments that can't work (MAC, algorithms, TERM...)
+    InvalidInput,
+    Database,
+    Io,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl std::fmt::Display) -> Self {
+        AppError {
+            kind,
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
+    /// Whatever has no kind of its own (a panicked blocking task...)
+    pub fn failed(message: impl std::fmt::Display) -> Self {
+        AppError::new(ErrorKind::Failed, message)
+    }
+
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+fn io_kind(e: &std::io::Error) -> ErrorKind {
+    match e.kind() {
+        std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+        std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+        std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+        std::io::ErrorKind::InvalidInput => ErrorKind::InvalidInput,
+        _ => ErrorKind::Io,
+    }
+}
+
+fn ssh_kind(e: &SshError) -> ErrorKind {
+    match e {
+        SshError::ConnectTimeout(_) => ErrorKind::Timeout,
+        // Refused, unreachable, unresolved
+        SshError::ConnectionFailed(_) | SshError::ChannelError(_) => ErrorKind::Network,
+        SshError::AuthFailed(_) => ErrorKind::Auth,
+        // The key file is the problem, not the credentials
+        SshError::KeyUnusable { cause, .. } => match cause {
+            std::io::ErrorKind::InvalidData => ErrorKind::InvalidInput,
+            cause => io_kind(&std::io::Error::from(*cause)),
+        },
+        // A key given that doesn't parse (`ssh_install_public_key`...)
+        SshError::KeyError(_) => ErrorKind::InvalidInput,
+        SshError::KeyPassphrase(_) => ErrorKind::KeyPassphrase,
+        SshError::IoError(e) => match io_kind(e) {
+            ErrorKind::Io => ErrorKind::Network,
+            kind => kind,
+        },
+        SshError::Protocol(russh::Error::IO(e)) => match io_kind(e) {
+            ErrorKind::Io => ErrorKind::Network,
+            kind => kind,
+        },
+        SshError::Protocol(
+            russh::Error::ConnectionTimeout
+            | russh::Error::KeepaliveTimeout
+            | russh::Error::InactivityTimeout
+            | russh::Error::Elapsed(_),
+        ) => ErrorKind::Timeout,
+        SshError::Protocol(russh::Error::NotAuthenticated | russh::Error::NoAuthMethod) => {
+            ErrorKind::Auth
+        }
+        SshError::Protocol(_) => ErrorKind::Network,
+        SshError::SessionNotFound(_) => ErrorKind::NotFound,
+        SshError::Cancelled => ErrorKind::Cancelled,
+        SshError::Proxy(ProxyError::Auth(_)) => ErrorKind::ProxyAuth,
+        SshError::Proxy(ProxyError::Config(_)) => ErrorKind::InvalidInput,
+        SshError::Proxy(_) => ErrorKind::Proxy,
+        SshError::Wol(e) => wol_kind(e),
+        SshError::Charset(_)
+        | SshError::InvalidAlgorithms(_)
+        | SshError::InvalidTerm(_)
+        | SshError::PasteInProgress => ErrorKind::InvalidInput,
+        SshError::ExecTimedOut(_) => ErrorKind::Timeout,
+        SshError::Forward(_) => ErrorKind::Failed,
+        SshError::ForwardNotFound(_) => ErrorKind::NotFound,
+        SshError::HostKeyError(_) => ErrorKind::HostKey,
+        SshError::HostKeyMismatch(_) => ErrorKind::HostKeyChanged,
+        SshError::HostKeyUnknown { .. } => ErrorKind::HostKeyUnknown,
+    }
+}
+
+fn wol_kind(e: &WolError) -> ErrorKind {
+    match e {
+        WolError::InvalidMac(_) | WolError::InvalidBroadcast(_) => ErrorKind::InvalidInput,
+        WolError::Io(_) => ErrorKind::Network,
+    }
+}
+
+impl From<SshError> for AppError {
+    fn from(e: SshError) -> Self {
+        let error = AppError::new(ssh_kind(&e), &e);
+        match &e {
+            SshError::HostKeyMismatch(change) => {
+                error.with_details(serde_json::to_value(change).unwrap_or_default())
+            }
+            SshError::HostKeyUnknown {
+                host,
+                port,
+                key_type,
+                fingerprint,
+            } => error.with_details(serde_json::json!({
+                "host": host,
+                "port": port,
+                "keyType": key_type,
+                "fingerprint": fingerprint,
+            })),
+            _ => error,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        let kind = match e {
+            rusqlite::Error::QueryReturnedNoRows => ErrorKind::NotFound,
+            _ => ErrorKind::Database,
+        };
+        AppError::new(kind, e)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::new(io_kind(&e), e)
+    }
+}
+
+impl From<LocalError> for AppError {
+    fn from(e: LocalError) -> Self {
+        let kind = match e {
+            LocalError::NotFound(_) => ErrorKind::NotFound,
+            LocalError::Spawn { .. } | LocalError::Io(_) => ErrorKind::Io,
+        };
+        AppError::new(kind, e)
+    }
+}
+
+impl From<WolError> for AppError {
+    fn from(e: WolError) -> Self {
+        AppError::new(wol_kind(&e), e)
+    }
+}
+
+/// Validation messages (`ForwardRule::validate`, keygen arguments...)
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::new(ErrorKind::InvalidInput, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ssh_errors_keep_their_text_and_get_a_kind() {
+        let error = AppError::from(SshError::AuthFailed("all methods rejected".into()));
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "auth",
+                "message": "Authentication failed: all methods rejected",
+            })
+        );
+
+        let kind = |e: SshError| AppError::from(e).kind;
+        assert_eq!(
+            kind(SshError::ConnectTimeout(
+                "10.0.0.1:22: connect timeout".into()
+            )),
+            ErrorKind::Timeout
+        );
+        // The kind comes from the variant, not from the wording
+        assert_eq!(
+            kind(SshError::ConnectionFailed(
+                "srv.timeout.example:22: cannot resolve".into()
+            )),
+            ErrorKind::Network
+        );
+        assert_eq!(
+            kind(SshError::ConnectionFailed(
+                "10.0.0.1:22: Connection refused (os error 111)".into()
+            )),
+            ErrorKind::Network
+        );
+        assert_eq!(
+            kind(SshError::Proxy(ProxyError::Auth("407".into()))),
+            ErrorKind::ProxyAuth
+        );
+        assert_eq!(
+            kind(SshError::IoError(std::io::ErrorKind::TimedOut.into())),
+            ErrorKind::Timeout
+        );
+        assert_eq!(
+            kind(SshError::SessionNotFound("abc".into())),
+            ErrorKind::NotFound
+        );
+        assert_eq!(kind(SshError::Cancelled), ErrorKind::Cancelled);
+        // The channel is fine: the paste has to wait for the other one
+        assert_eq!(kind(SshError::PasteInProgress), ErrorKind::InvalidInput);
+        // A key that can't be used isn't a rejected credential
+        let key = |cause| SshError::KeyUnusable {
+            message: "~/.ssh/id_ed25519".into(),
+            cause,
+        };
+        assert_eq!(kind(key(std::io::ErrorKind::NotFound)), ErrorKind::NotFound);
+        assert_eq!(
+            kind(key(std::io::ErrorKind::PermissionDenied)),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            kind(key(std::io::ErrorKind::InvalidData)),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            kind(SshError::ForwardNotFound("f1".into())),
+            ErrorKind::NotFound
+        );
+        let bad_key = russh::keys::PublicKey::from_openssh("ssh-ed25519 not-base64").unwrap_err();
+        assert_eq!(
+            kind(SshError::KeyError(russh::keys::Error::SshKey(bad_key))),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn host_key_errors_carry_what_the_dialog_shows() {
+        let error = AppError::from(SshError::HostKeyUnknown {
+            host: "srv".into(),
+            port: 2222,
+            key_type: "ssh-ed25519".into(),
+            fingerprint: "SHA256:abc".into(),
+        });
+        let payload = serde_json::to_value(&error).unwrap();
+        assert_eq!(payload["kind"], "hostKeyUnknown");
+        assert_eq!(payload["details"]["port"], 2222);
+        assert_eq!(payload["details"]["fingerprint"], "SHA256:abc");
+
+        let error = AppError::from(SshError::HostKeyMismatch(Box::new(
+            crate::ssh::HostKeyChange {
+                host: "srv".into(),
+                port: 22,
+                key_type: "ssh-ed25519".into(),
+                old_fingerprint: "SHA256:old".into(),
+                new_fingerprint: "SHA256:new".into(),
+                known_hosts: "/home/ana/.ssh/known_hosts".into(),
+                line: 3,
+            },
+        )));
+        let payload = serde_json::to_value(&error).unwrap();
+        assert_eq!(payload["kind"], "hostKeyChanged");
+        assert_eq!(payload["details"]["newFingerprint"], "SHA256:new");
+        assert!(payload["message"].as_str().unwrap().contains("CHANGED"));
+
+        assert_eq!(
+            AppError::from(rusqlite::Error::QueryReturnedNoRows).kind,
+            ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,309 @@
+//! Port forwarding over a channel's connection. Local (`ssh -L`): a
+//! listener here, each connection a "direct-tcpip" channel to the target.
+//! Remote (`ssh -R`): the server listens and opens a "forwarded-tcpip"
+//! channel per connection it accepts; the handler looks the port up in the
+//! connection's routes and pipes the channel to the local target. Dynamic
+//! (`ssh -D`): a SOCKS5 server on localhost, each CONNECT a "direct-tcpip"
+//! channel of its own.
+//!
+//! A forward belongs to the channel that started it: closing the channel
+//! cancels it. After a reconnect remote forwards are asked for again on the
+//! new connection; local listeners and SOCKS servers open their next
+//! channels there.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// A local target that doesn't accept by then is not there
+const TARGET_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// SOCKS5 (RFC 1928): no-auth only, CONNECT only
+const SOCKS_VERSION: u8 = 5;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xFF;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+pub const REPLY_SUCCEEDED: u8 = 0x00;
+pub const REPLY_FAILURE: u8 = 0x01;
+pub const REPLY_HOST_UNREACHABLE: u8 = 0x04;
+pub const REPLY_REFUSED: u8 = 0x05;
+const REPLY_COMMAND_UNSUPPORTED: u8 = 0x07;
+const REPLY_ADDRESS_UNSUPPORTED: u8 = 0x08;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardKind {
+    /// `ssh -L`: listens here, connects from the server
+    Local,
+    /// `ssh -R`: listens on the server, connects from here
+    Remote,
+    /// `ssh -D`: SOCKS5 proxy here
+    Dynamic,
+}
+
+impl ForwardKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ForwardKind::Local => "local",
+            ForwardKind::Remote => "remote",
+            ForwardKind::Dynamic => "dynamic",
+        }
+    }
+
+    pub(crate) fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "local" => Some(ForwardKind::Local),
+            "remote" => Some(ForwardKind::Remote),
+            "dynamic" => Some(ForwardKind::Dynamic),
+            _ => None,
+        }
+    }
+}
+
+/// `ssh_forward_*` / `ssh_start_socks` result, `ssh_list_forwards` entry
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardInfo {
+    pub forward_id: String,
+    pub channel_id: String,
+    pub kind: ForwardKind,
+    pub bind_host: String,
+    /// What listens: the server's choice when 0 was asked for
+    pub bind_port: u16,
+    /// None for dynamic forwards: each SOCKS client says where
+    pub target_host: Option<String>,
+    pub target_port: Option<u16>,
+}
+
+/// Where the channels of one remote listener go
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Target {
+    pub fn new(host: &str, port: u16) -> Result<Self, String> {
+        let host = host.trim();
+        if host.is_empty() {
+            return Err("the target host is empty".to_string());
+        }
+        if port == 0 {
+            return Err("the target port can't be 0".to_string());
+        }
+        Ok(Target {
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+/// Remote listeners of a connection by port (the one the server reports in
+/// each forwarded-tcpip open)
+pub type RemoteRoutes = Arc<Mutex<HashMap<u32, Target>>>;
+
+/// The bind address of a remote forward: "" or "localhost" keep it on the
+/// server's loopback (unless GatewayPorts says otherwise)
+pub fn bind_host(host: &str) -> String {
+    match host.trim() {
+        "" => "localhost".to_string(),
+        host => host.to_string(),
+    }
+}
+
+/// Pipe a forwarded channel to `target` until either side closes
+pub async fn relay<S: AsyncRead + AsyncWrite + Unpin>(
+    mut remote: S,
+    target: &Target,
+) -> std::io::Result<()> {
+    let connect = tokio::net::TcpStream::connect((target.host.as_str(), target.port));
+    let mut local = tokio::time::timeout(TARGET_CONNECT_TIMEOUT, connect)
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "connect timed out"))??;
+    let _ = local.set_nodelay(true);
+    tokio::io::copy_bidirectional(&mut remote, &mut local).await?;
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum SocksError {
+    #[error("not a SOCKS5 client (version {0})")]
+    Version(u8),
+    #[error("the client offers no method without authentication")]
+    NoAcceptableMethod,
+    #[error("unsupported SOCKS command {0}")]
+    Command(u8),
+    #[error("unsupported address type {0}")]
+    AddressType(u8),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Greeting and request of a SOCKS5 client: where it wants to go. Refusals
+/// are answered here; success is for the caller, once the channel opened.
+pub async fn socks_request<S: AsyncRead + AsyncWrite + Unpin>(
+    client: &mut S,
+) -> Result<Target, SocksError> {
+    let mut header = [0u8; 2];
+    client.read_exact(&mut header).await?;
+    if header[0] != SOCKS_VERSION {
+        return Err(SocksError::Version(header[0]));
+    }
+    let mut methods = vec![0u8; header[1].into()];
+    client.read_exact(&mut methods).await?;
+    if !methods.contains(&METHOD_NO_AUTH) {
+        client
+            .write_all(&[SOCKS_VERSION, METHOD_NONE_ACCEPTABLE])
+            .await?;
+        return Err(SocksError::NoAcceptableMethod);
+    }
+    client.write_all(&[SOCKS_VERSION, METHOD_NO_AUTH]).await?;
+
+    // VER CMD RSV ATYP
+    let mut request = [0u8; 4];
+    client.read_exact(&mut request).await?;
+    if request[0] != SOCKS_VERSION {
+        return Err(SocksError::Version(request[0]));
+    }
+    if request[1] != CMD_CONNECT {
+        socks_reply(client, REPLY_COMMAND_UNSUPPORTED).await?;
+        return Err(SocksError::Command(request[1]));
+    }
+    let host = match request[3] {
+        ATYP_IPV4 => {
+            let mut ip = [0u8; 4];
+            client.read_exact(&mut ip).await?;
+            Ipv4Addr::from(ip).to_string()
+        }
+        ATYP_IPV6 => {
+            let mut ip = [0u8; 16];
+            client.read_exact(&mut ip).await?;
+            Ipv6Addr::from(ip).to_string()
+        }
+        ATYP_DOMAIN => {
+            let len = client.read_u8().await?;
+            let mut name = vec![0u8; len.into()];
+            client.read_exact(&mut name).await?;
+            String::from_utf8_lossy(&name).into_owned()
+        }
+        other => {
+            socks_reply(client, REPLY_ADDRESS_UNSUPPORTED).await?;
+            return Err(SocksError::AddressType(other));
+        }
+    };
+    let port = client.read_u16().await?;
+    Ok(Target { host, port })
+}
+
+/// The reply to a request. The bound address is left at 0.0.0.0:0: the
+/// remote end of a channel has none we could give.
+pub async fn socks_reply<S: AsyncWrite + Unpin>(client: &mut S, code: u8) -> std::io::Result<()> {
+    client
+        .write_all(&[SOCKS_VERSION, code, 0, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_and_bind_hosts() {
+        assert_eq!(
+            Target::new(" 127.0.0.1 ", 8080),
+            Ok(Target {
+                host: "127.0.0.1".into(),
+                port: 8080
+            })
+        );
+        assert!(Target::new("", 8080).is_err());
+        assert!(Target::new("localhost", 0).is_err());
+        assert_eq!(bind_host(" "), "localhost");
+        assert_eq!(bind_host("0.0.0.0"), "0.0.0.0");
+    }
+
+    #[tokio::test]
+    async fn relays_both_ways_to_the_local_target() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let target = Target::new("127.0.0.1", listener.local_addr().unwrap().port()).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 4];
+            socket.read_exact(&mut request).await.unwrap();
+            socket.write_all(b"pong").await.unwrap();
+            request
+        });
+
+        // The channel side of the relay: one end of an in-memory pipe
+        let (channel, mut remote) = tokio::io::duplex(1024);
+        let relay = tokio::spawn(async move { relay(channel, &target).await });
+        remote.write_all(b"ping").await.unwrap();
+        let mut reply = [0u8; 4];
+        remote.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"pong");
+        assert_eq!(&server.await.unwrap(), b"ping");
+        drop(remote);
+        relay.await.unwrap().unwrap();
+    }
+
+    /// What `socks_request` makes of `sent`, and what it answered
+    async fn socks(sent: &[u8]) -> (Result<Target, SocksError>, Vec<u8>) {
+        let (mut server, mut client) = tokio::io::duplex(1024);
+        client.write_all(sent).await.unwrap();
+        let result = socks_request(&mut server).await;
+        drop(server);
+        let mut answered = Vec::new();
+        client.read_to_end(&mut answered).await.unwrap();
+        (result, answered)
+    }
+
+    #[tokio::test]
+    async fn socks_connect_requests() {
+        let greeting = [5, 2, 0x02, 0x00];
+        let target = |host: &str, port: u16| Target {
+            host: host.into(),
+            port,
+        };
+
+        let mut ipv4 = greeting.to_vec();
+        ipv4.extend_from_slice(&[5, 1, 0, 1, 10, 0, 0, 7, 0x1F, 0x90]);
+        let (result, answered) = socks(&ipv4).await;
+        assert_eq!(result.unwrap(), target("10.0.0.7", 8080));
+        assert_eq!(answered, [5, 0]);
+
+        let mut domain = greeting.to_vec();
+        domain.extend_from_slice(&[5, 1, 0, 3, 11]);
+        domain.extend_from_slice(b"db.internal");
+        domain.extend_from_slice(&5432u16.to_be_bytes());
+        assert_eq!(socks(&domain).await.0.unwrap(), target("db.internal", 5432));
+
+        let mut ipv6 = greeting.to_vec();
+        ipv6.extend_from_slice(&[5, 1, 0, 4]);
+        ipv6.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        ipv6.extend_from_slice(&443u16.to_be_bytes());
+        assert_eq!(socks(&ipv6).await.0.unwrap(), target("::1", 443));
+    }
+
+    #[tokio::test]
+    async fn socks_refusals_are_answered() {
+        // Username/password only
+        let (result, answered) = socks(&[5, 1, 0x02]).await;
+        assert!(matches!(result, Err(SocksError::NoAcceptableMethod)));
+        assert_eq!(answered, [5, 0xFF]);
+
+        // BIND
+        let (result, answered) = socks(&[5, 1, 0, 5, 2, 0, 1, 127, 0, 0, 1, 0, 80]).await;
+        assert!(matches!(result, Err(SocksError::Command(2))));
+        assert_eq!(answered[2..4], [5, REPLY_COMMAND_UNSUPPORTED]);
+
+        let (result, _) = socks(&[4, 1, 0, 80]).await;
+        assert!(matches!(result, Err(SocksError::Version(4))));
+    }
+}
@@ -0,0 +1,130 @@
+//! What the debug log says about terminal input: how many bytes were typed,
+//! never what. Not even that while the channel sits at a password prompt (a
+//! password's length is a hint too). `verboseInputLog` (Settings, off by
+//! default) logs the text itself, for debugging only.
+//!
+//! The prompt is only watched with trace logging on: otherwise the output
+//! path doesn't pay for it. The last output is kept, as in `transcript`: a
+//! prompt can arrive split over two reads.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Output kept to tell whether the channel sits at a password prompt
+const OUTPUT_TAIL_LIMIT: usize = 256;
+
+/// Same heuristic as the frontend's password-prompt guard: the output ends
+/// in "...password...:" (English / Spanish)
+pub fn looks_like_password_prompt(tail: &str) -> bool {
+    let last_line = tail.trim_end().rsplit('\n').next().unwrap_or_default();
+    let lower = last_line.to_lowercase();
+    let Some(colon) = lower.rfind(':') else {
+        return false;
+    };
+    colon + 1 == lower.len()
+        && ["password", "passphrase", "contraseña", "clave"]
+            .iter()
+            .any(|word| lower[..colon].contains(word))
+}
+
+#[derive(Debug)]
+pub struct InputLog {
+    /// Whether anything below trace is logged: without it the output isn't
+    /// watched at all
+    trace: bool,
+    verbose: AtomicBool,
+    output_tail: Mutex<String>,
+    /// The last output ended in a password prompt: what is typed now is
+    /// the answer (echo off, nothing printed until Enter)
+    at_prompt: AtomicBool,
+}
+
+/// Watching the output only when the app logs at trace level
+impl Default for InputLog {
+    fn default() -> Self {
+        InputLog::new(log::log_enabled!(log::Level::Trace))
+    }
+}
+
+impl InputLog {
+    pub fn new(trace: bool) -> Self {
+        InputLog {
+            trace,
+            verbose: AtomicBool::new(false),
+            output_tail: Mutex::new(String::new()),
+            at_prompt: AtomicBool::new(false),
+        }
+    }
+
+    pub fn set_verbose(&self, verbose: bool) {
+        self.verbose.store(verbose, Ordering::Relaxed);
+    }
+
+    /// Terminal output as it arrives. A bare "\r\n" (the Enter after the
+    /// password) doesn't end the prompt: the next real output does.
+    pub fn output(&self, text: &str) {
+        if !self.trace || text.trim().is_empty() {
+            return;
+        }
+        let mut tail = self.output_tail.lock().unwrap();
+        tail.push_str(text);
+        if tail.len() > OUTPUT_TAIL_LIMIT {
+            let mut cut = tail.len() - OUTPUT_TAIL_LIMIT;
+            while !tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            tail.drain(..cut);
+        }
+        self.at_prompt
+            .store(looks_like_password_prompt(&tail), Ordering::Relaxed);
+    }
+
+    /// `data` as the log may show it
+    pub fn describe(&self, data: &[u8]) -> String {
+        if self.verbose.load(Ordering::Relaxed) {
+            format!("{:?}", String::from_utf8_lossy(data))
+        } else if self.at_prompt.load(Ordering::Relaxed) {
+            "at a password prompt".to_string()
+        } else {
+            format!("{} bytes", data.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompts_hide_even_the_length() {
+        let input = InputLog::new(true);
+        assert_eq!(input.describe(b"ls\r"), "3 bytes");
+        input.output("\r\n[sudo] password for ana: ");
+        assert_eq!(input.describe(b"hunter2"), "at a password prompt");
+        input.output("\r\n");
+        assert_eq!(input.describe(b"\r"), "at a password prompt");
+        input.output("ana@host:~$ ");
+        assert_eq!(input.describe(b"ls\r"), "3 bytes");
+
+        input.set_verbose(true);
+        assert_eq!(input.describe(b"ls\r"), r#""ls\r""#);
+    }
+
+    #[test]
+    fn a_prompt_split_between_reads_is_still_one() {
+        let input = InputLog::new(true);
+        input.output("ana@host:~$ sudo ls\r\n[sudo] pass");
+        assert_eq!(input.describe(b"x"), "1 bytes");
+        input.output("word for ana: ");
+        assert_eq!(input.describe(b"hunter2"), "at a password prompt");
+        input.output("\r\nbin  etc\r\nana@host:~$ ");
+        assert_eq!(input.describe(b"ls\r"), "3 bytes");
+    }
+
+    #[test]
+    fn without_trace_the_output_is_not_watched() {
+        let input = InputLog::new(false);
+        input.output("[sudo] password for ana: ");
+        assert_eq!(input.describe(b"hunter2"), "7 bytes");
+    }
+}
@@ -0,0 +1,176 @@
+//! Key pair generation for users without SSH keys, in pure Rust (ssh-key) so
+//! it works the same on Windows: no ssh-keygen binary involved.
+
+use russh::keys::ssh_key::private::{Ed25519Keypair, KeypairData, RsaKeypair};
+use russh::keys::ssh_key::LineEnding;
+use russh::keys::{HashAlg, PrivateKey};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_RSA_BITS: usize = 4096;
+const RSA_BITS: [usize; 3] = [2048, 3072, 4096];
+
+/// What `generate_keypair` wrote
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedKey {
+    pub private_key_path: String,
+    pub public_key_path: String,
+    /// authorized_keys line ("type base64 comment")
+    pub public_key: String,
+    pub fingerprint: String,
+}
+
+fn key_err(e: russh::keys::ssh_key::Error) -> String {
+    format!("Key generation failed: {}", e)
+}
+
+/// New random (unencrypted) key: "ed25519" or "rsa" (`bits`
+/// 2048/3072/4096, 4096 by default)
+pub fn generate(algorithm: &str, bits: Option<u32>, comment: &str) -> Result<PrivateKey, String> {
+    let mut rng = rand::rng();
+    let key_data = match algorithm.to_ascii_lowercase().as_str() {
+        "ed25519" => KeypairData::from(Ed25519Keypair::random(&mut rng)),
+        "rsa" => {
+            let bits = bits.map_or(DEFAULT_RSA_BITS, |b| b as usize);
+            if !RSA_BITS.contains(&bits) {
+                return Err(format!(
+                    "Unsupported RSA key size {} (use 2048, 3072 or 4096)",
+                    bits
+                ));
+            }
+            KeypairData::from(RsaKeypair::random(&mut rng, bits).map_err(key_err)?)
+        }
+        other => {
+            return Err(format!(
+                "Unsupported key algorithm: {} (use ed25519 or rsa)",
+                other
+            ))
+        }
+    };
+    PrivateKey::new(key_data, comment).map_err(key_err)
+}
+
+/// Write `path` with `mode` (unix); never overwrites an existing key
+fn write_new_file(path: &Path, contents: &[u8], mode: u32) -> Result<(), String> {
+    use std::io::Write;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => format!("{} already exists", path.display()),
+        _ => format!("Cannot create {}: {}", path.display(), e),
+    })?;
+    file.write_all(contents)
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+/// Generate a key pair and save it as `<directory>/<file_name>` (OpenSSH
+/// private key, 600) and `<file_name>.pub` (644). `file_name` defaults to
+/// id_ed25519 / id_rsa like ssh-keygen. A non-empty `passphrase` encrypts
+/// the private key (bcrypt-pbkdf + aes256-ctr, as ssh-keygen does).
+pub fn generate_to_dir(
+    algorithm: &str,
+    bits: Option<u32>,
+    passphrase: Option<&str>,
+    comment: &str,
+    directory: &Path,
+    file_name: Option<&str>,
+) -> Result<GeneratedKey, String> {
+    let key = generate(algorithm, bits, comment)?;
+    let default_name = format!("id_{}", algorithm.to_ascii_lowercase());
+    let file_name = file_name
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .unwrap_or(&default_name);
+    // A bare name: the key must land in the chosen directory
+    if file_name.contains(['/', '\\']) || file_name == ".." {
+        return Err(format!("Invalid key file name: {}", file_name));
+    }
+
+    std::fs::create_dir_all(directory)
+        .map_err(|e| format!("Cannot create {}: {}", directory.display(), e))?;
+    let private_path: PathBuf = directory.join(file_name);
+    let public_path: PathBuf = directory.join(format!("{}.pub", file_name));
+    if public_path.exists() {
+        return Err(format!("{} already exists", public_path.display()));
+    }
+
+    // Public half first: the encrypted key no longer exposes the comment
+    let public_line = key.public_key().to_openssh().map_err(key_err)?;
+    let fingerprint = key.public_key().fingerprint(HashAlg::Sha256).to_string();
+    let key = match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => key.encrypt(&mut rand::rng(), passphrase).map_err(key_err)?,
+        None => key,
+    };
+    let private_pem = key.to_openssh(LineEnding::LF).map_err(key_err)?;
+    write_new_file(&private_path, private_pem.as_bytes(), 0o600)?;
+    write_new_file(&public_path, format!("{}\n", public_line).as_bytes(), 0o644)?;
+
+    Ok(GeneratedKey {
+        private_key_path: private_path.display().to_string(),
+        public_key_path: public_path.display().to_string(),
+        public_key: public_line,
+        fingerprint,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ori-keygen-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn generated_ed25519_key_loads_like_a_user_key_file() {
+        let dir = temp_dir();
+        let generated =
+            generate_to_dir("ed25519", None, Some("s3cret"), "me@laptop", &dir, None).unwrap();
+        assert!(generated.private_key_path.ends_with("id_ed25519"));
+        assert!(generated.public_key.starts_with("ssh-ed25519 "));
+        assert!(generated.public_key.ends_with(" me@laptop"));
+
+        // Same loaders the key auth uses: the .pub matches the decrypted key
+        let public = russh::keys::load_public_key(&generated.public_key_path).unwrap();
+        let private =
+            russh::keys::load_secret_key(&generated.private_key_path, Some("s3cret")).unwrap();
+        assert_eq!(private.public_key().key_data(), public.key_data());
+        assert_eq!(
+            public.fingerprint(HashAlg::Sha256).to_string(),
+            generated.fingerprint
+        );
+        assert!(russh::keys::load_secret_key(&generated.private_key_path, None).is_err());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &str| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode(&generated.private_key_path), 0o600);
+            assert_eq!(mode(&generated.public_key_path), 0o644);
+        }
+
+        // Never overwrites an existing key
+        let err = generate_to_dir("ed25519", None, None, "", &dir, None).unwrap_err();
+        assert!(err.contains("already exists"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn rejects_unknown_algorithms_sizes_and_paths() {
+        assert!(generate("dsa", None, "").is_err());
+        let err = generate("rsa", Some(1024), "").unwrap_err();
+        assert!(err.contains("Unsupported RSA key size 1024"));
+        let err =
+            generate_to_dir("ed25519", None, None, "", &temp_dir(), Some("../x")).unwrap_err();
+        assert!(err.contains("Invalid key file name"));
+    }
+}
@@ -0,0 +1,106 @@
+//! Port knocking (knockd style) before the real connection: a TCP SYN or an
+//! empty UDP datagram to every port of the sequence, in order. Knocks are
+//! fire-and-forget: nobody answers them, so a knock that can't be sent is
+//! logged and the sequence goes on.
+
+use crate::db::{KnockProtocol, KnockStep};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+// Only the SYN matters: don't wait for a handshake a closed port never does
+const TCP_KNOCK_TIMEOUT: Duration = Duration::from_millis(300);
+// A mistyped delay must not freeze the connect
+const MAX_KNOCK_DELAY: Duration = Duration::from_secs(10);
+
+/// Send `sequence` to `addr`, waiting each step's delay after its knock.
+/// Returns how many knocks went out.
+pub async fn send_sequence(addr: IpAddr, sequence: &[KnockStep]) -> usize {
+    let mut sent = 0;
+    for step in sequence {
+        let target = SocketAddr::new(addr, step.port);
+        match knock(target, step.protocol).await {
+            Ok(()) => sent += 1,
+            Err(e) => log::warn!("Knock {:?} {} not sent: {}", step.protocol, target, e),
+        }
+        let delay = Duration::from_millis(step.delay_ms.into()).min(MAX_KNOCK_DELAY);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+    sent
+}
+
+async fn knock(target: SocketAddr, protocol: KnockProtocol) -> std::io::Result<()> {
+    match protocol {
+        KnockProtocol::Tcp => {
+            // Refused or unanswered is the expected outcome of a knock: the
+            // SYN already left. Only local failures (no route…) count.
+            match tokio::time::timeout(TCP_KNOCK_TIMEOUT, tokio::net::TcpStream::connect(target))
+                .await
+            {
+                Ok(Err(e)) if e.kind() != std::io::ErrorKind::ConnectionRefused => Err(e),
+                _ => Ok(()),
+            }
+        }
+        KnockProtocol::Udp => {
+            let local: SocketAddr = if target.is_ipv4() {
+                (Ipv4Addr::UNSPECIFIED, 0).into()
+            } else {
+                (Ipv6Addr::UNSPECIFIED, 0).into()
+            };
+            let socket = tokio::net::UdpSocket::bind(local).await?;
+            socket.send_to(&[], target).await.map(|_| ())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(port: u16, protocol: KnockProtocol) -> KnockStep {
+        KnockStep {
+            port,
+            protocol,
+            delay_ms: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn udp_knocks_arrive_in_order() {
+        let first = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let second = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let sequence = [
+            step(first.local_addr().unwrap().port(), KnockProtocol::Udp),
+            step(second.local_addr().unwrap().port(), KnockProtocol::Udp),
+        ];
+
+        let sent = send_sequence(Ipv4Addr::LOCALHOST.into(), &sequence).await;
+
+        assert_eq!(sent, 2);
+        let mut buf = [0u8; 8];
+        assert_eq!(first.recv(&mut buf).await.unwrap(), 0);
+        assert_eq!(second.recv(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn tcp_knocks_count_refused_and_open_ports() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        // A port that was just free: the knock is refused, as on a knockd host
+        let closed = {
+            let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().port()
+        };
+        let sequence = [
+            step(closed, KnockProtocol::Tcp),
+            step(open, KnockProtocol::Tcp),
+        ];
+
+        assert_eq!(
+            send_sequence(Ipv4Addr::LOCALHOST.into(), &sequence).await,
+            2
+        );
+        assert!(listener.accept().await.is_ok());
+    }
+}
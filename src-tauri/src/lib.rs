@@ -1,12 +1,37 @@
 //! ORI-SSHManager - Tauri Application Entry Point
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
+use tauri::Manager;
 
+mod algorithms;
+mod auto_response;
+mod cast;
+mod charset;
 mod db;
+mod error;
+mod forward;
+mod input_log;
+mod keygen;
+mod knock;
+mod local;
+mod osc52;
+mod plain_text;
+mod proxy;
+mod scrollback;
+mod shell_ready;
 mod ssh;
+mod ssh_config;
+mod sudo_helper;
+mod totp;
+mod transcript;
+mod wol;
+mod x11;
 
-use db::{Database, SavedCommand, Session, SessionGroup, SessionLog};
+use db::{Database, ForwardRule, SavedCommand, Session, SessionGroup, SessionLog};
+use error::{AppError, ErrorKind};
+use local::LocalTerminals;
 use ssh::SshManager;
 
 // ==================== GLOBAL STATE ====================
@@ -14,26 +39,32 @@ use ssh::SshManager;
 struct AppState {
     db: Database,
     ssh: SshManager,
+    // Local shell tabs: channel ids with local::CHANNEL_PREFIX, routed here
+    // by the ssh_* terminal commands
+    local: LocalTerminals,
 }
 
 // ==================== TAURI COMMANDS: DATABASE ====================
 
 #[tauri::command]
-async fn get_sessions(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<Session>, String> {
-    state.db.get_sessions().map_err(|e| e.to_string())
+async fn get_sessions(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<Session>, AppError> {
+    state.db.get_sessions().map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn save_session(
     state: tauri::State<'_, Arc<AppState>>,
     session: Session,
-) -> Result<(), String> {
-    state.db.save_session(&session).map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    state.db.save_session(&session).map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn delete_session(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
-    state.db.delete_session(&id).map_err(|e| e.to_string())
+async fn delete_session(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<(), AppError> {
+    state.db.delete_session(&id).map_err(AppError::from)
 }
 
 /// Export every session (WITH decrypted secrets, by explicit user choice) as a
@@ -43,52 +74,85 @@ async fn delete_session(state: tauri::State<'_, Arc<AppState>>, id: String) -> R
 async fn export_sessions_to_path(
     state: tauri::State<'_, Arc<AppState>>,
     path: String,
-) -> Result<usize, String> {
-    let (json, count) = state.db.export_sessions_json().map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("No se pudo escribir el archivo: {e}"))?;
+) -> Result<usize, AppError> {
+    let (json, count) = state.db.export_sessions_json().map_err(AppError::from)?;
+    std::fs::write(&path, json).map_err(|e| {
+        AppError::new(
+            ErrorKind::Io,
+            format!("No se pudo escribir el archivo: {e}"),
+        )
+    })?;
     Ok(count)
 }
 
 #[tauri::command]
-async fn get_groups(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<SessionGroup>, String> {
-    state.db.get_groups().map_err(|e| e.to_string())
+async fn get_groups(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<SessionGroup>, AppError> {
+    state.db.get_groups().map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn save_group(
     state: tauri::State<'_, Arc<AppState>>,
     group: SessionGroup,
-) -> Result<(), String> {
-    state.db.save_group(&group).map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    state.db.save_group(&group).map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn delete_group(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
-    state.db.delete_group(&id).map_err(|e| e.to_string())
+async fn delete_group(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), AppError> {
+    state.db.delete_group(&id).map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn get_commands(
     state: tauri::State<'_, Arc<AppState>>,
     session_id: Option<String>,
-) -> Result<Vec<SavedCommand>, String> {
+) -> Result<Vec<SavedCommand>, AppError> {
     state
         .db
         .get_commands(session_id.as_deref())
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn save_command(
     state: tauri::State<'_, Arc<AppState>>,
     command: SavedCommand,
-) -> Result<(), String> {
-    state.db.save_command(&command).map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    state.db.save_command(&command).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_command(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<(), AppError> {
+    state.db.delete_command(&id).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn get_forwards(
+    state: tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<Vec<ForwardRule>, AppError> {
+    state.db.get_forwards(&session_id).map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn delete_command(state: tauri::State<'_, Arc<AppState>>, id: String) -> Result<(), String> {
-    state.db.delete_command(&id).map_err(|e| e.to_string())
+async fn save_forward(
+    state: tauri::State<'_, Arc<AppState>>,
+    forward: ForwardRule,
+) -> Result<(), AppError> {
+    forward.validate()?;
+    state.db.save_forward(&forward).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn delete_forward(
+    state: tauri::State<'_, Arc<AppState>>,
+    id: String,
+) -> Result<(), AppError> {
+    state.db.delete_forward(&id).map_err(AppError::from)
 }
 
 /// Number of OTHER sessions that reference this session as a jump host
@@ -97,11 +161,11 @@ async fn delete_command(state: tauri::State<'_, Arc<AppState>>, id: String) -> R
 async fn count_session_jump_refs(
     state: tauri::State<'_, Arc<AppState>>,
     id: String,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     state
         .db
         .count_session_jump_refs(&id)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 // ==================== TAURI COMMANDS: SESSION LOGS (AUDIT) ====================
@@ -110,8 +174,8 @@ async fn count_session_jump_refs(
 async fn add_session_log(
     state: tauri::State<'_, Arc<AppState>>,
     log: SessionLog,
-) -> Result<(), String> {
-    state.db.add_session_log(&log).map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    state.db.add_session_log(&log).map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -119,22 +183,22 @@ async fn get_session_logs(
     state: tauri::State<'_, Arc<AppState>>,
     session_id: String,
     limit: Option<i64>,
-) -> Result<Vec<SessionLog>, String> {
+) -> Result<Vec<SessionLog>, AppError> {
     state
         .db
         .get_session_logs(&session_id, limit)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn clear_session_logs(
     state: tauri::State<'_, Arc<AppState>>,
     session_id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     state
         .db
         .clear_session_logs(&session_id)
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 /// Export a session's audit log as a JSON file at `path` (written by the
@@ -144,12 +208,17 @@ async fn export_session_logs_to_path(
     state: tauri::State<'_, Arc<AppState>>,
     session_id: String,
     path: String,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     let (json, count) = state
         .db
         .export_session_logs_json(&session_id)
-        .map_err(|e| e.to_string())?;
-    std::fs::write(&path, json).map_err(|e| format!("No se pudo escribir el archivo: {e}"))?;
+        .map_err(AppError::from)?;
+    std::fs::write(&path, json).map_err(|e| {
+        AppError::new(
+            ErrorKind::Io,
+            format!("No se pudo escribir el archivo: {e}"),
+        )
+    })?;
     Ok(count)
 }
 
@@ -167,6 +236,165 @@ pub struct ConnectParams {
     pub rows: Option<u16>,
     #[serde(default)]
     pub progress_id: Option<String>,
+    /// Resolve the session host against ~/.ssh/config, filling the fields
+    /// the session leaves empty (user, port, key, ProxyJump)
+    #[serde(default)]
+    pub use_ssh_config: bool,
+    /// Forward the local agent for this connection even if the session
+    /// doesn't have it enabled
+    #[serde(default)]
+    pub agent_forwarding: bool,
+    /// Forward X11 to the local display for this connection even if the
+    /// session doesn't have it enabled
+    #[serde(default)]
+    pub x11_forwarding: bool,
+    /// Ask for zlib compression on this connection (slow links)
+    #[serde(default)]
+    pub compression: bool,
+    /// Preferred kex / host key / cipher / MAC lists (OpenSSH names)
+    #[serde(default)]
+    pub algorithms: Option<algorithms::AlgorithmPrefs>,
+    /// TCP connect deadline per hop (default 10 s)
+    #[serde(default)]
+    pub connect_timeout_ms: Option<u64>,
+    /// Deadline for each handshake/auth reply from the server (default 15 s)
+    #[serde(default)]
+    pub io_timeout_ms: Option<u64>,
+    /// Keepalive interval in seconds for this connection (0 = off),
+    /// overriding the session's
+    #[serde(default)]
+    pub keepalive_interval: Option<u32>,
+    /// Idle timeout in seconds for this connection (0 = off), overriding
+    /// the session's; `idle_warning` comes a minute before the disconnect
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u32>,
+    /// Seconds between `channel_latency` RTT samples (default 10, 0 = off)
+    #[serde(default)]
+    pub latency_interval_secs: Option<u32>,
+    /// Seconds without output nor keepalive answer before the connection
+    /// is probed and, unanswered, given up as "network_lost" (default 60,
+    /// at least 10, 0 = off)
+    #[serde(default)]
+    pub network_watchdog_secs: Option<u32>,
+    /// Open the shell as one more channel of an open connection to the same
+    /// host/port/user (other tab), skipping the handshake and auth
+    #[serde(default)]
+    pub reuse_connection: bool,
+    /// Reconnect in the backend when the connection drops (same channel id,
+    /// `pty_reconnecting` / `pty_reconnected` events)
+    #[serde(default)]
+    pub auto_reconnect: bool,
+    /// Reconnect attempts before `pty_closed` "reconnect_failed" (default 5)
+    #[serde(default)]
+    pub reconnect_max_attempts: Option<u32>,
+    /// Client-chosen id (the tab id) for `ssh_cancel_connect` and the
+    /// `connect_progress` stage events
+    #[serde(default)]
+    pub connect_id: Option<String>,
+    /// "ipv4" / "ipv6": family tried first when a host resolves to both
+    #[serde(default)]
+    pub ip_preference: ssh::IpPreference,
+    /// "http" (CONNECT) or "socks5": reach the first host through this proxy
+    /// instead of the session's own proxy settings
+    #[serde(default)]
+    pub proxy_type: Option<db::ProxyType>,
+    #[serde(default)]
+    pub proxy_host: Option<String>,
+    #[serde(default)]
+    pub proxy_port: Option<u16>,
+    #[serde(default)]
+    pub proxy_username: Option<String>,
+    #[serde(default)]
+    pub proxy_password: Option<String>,
+    /// Connect without the session's port knocking sequence
+    #[serde(default)]
+    pub skip_knock: bool,
+    /// Send the session's Wake-on-LAN packet, then wait up to
+    /// wake_timeout_secs (default 90) for the host before connecting
+    #[serde(default)]
+    pub wake_on_lan: bool,
+    #[serde(default)]
+    pub wake_timeout_secs: Option<u32>,
+    /// Terminal type of the PTY (remote $TERM) for this connection instead
+    /// of the session's (default xterm-256color)
+    #[serde(default)]
+    pub term: Option<String>,
+    /// Transcript (`logToFile` sessions) size before rotating, in MB
+    /// (default 10)
+    #[serde(default)]
+    pub log_max_mb: Option<u32>,
+    /// Also write the typed lines to the transcript (never the answers to
+    /// password prompts)
+    #[serde(default)]
+    pub log_input: bool,
+    /// Transcript without escape sequences (colors, cursor moves): the text
+    /// as seen, in a .txt file
+    #[serde(default)]
+    pub log_plain_text: bool,
+    /// `shell_ready`: output quiet this long with a prompt-like line open
+    /// (default 800) and the longest wait for it (default 15000)
+    #[serde(default)]
+    pub shell_ready_quiet_ms: Option<u64>,
+    #[serde(default)]
+    pub shell_ready_timeout_ms: Option<u64>,
+    /// Connect without typing the session's startup command
+    #[serde(default)]
+    pub skip_startup_command: bool,
+    /// Output the backend keeps for `ssh_get_buffer`, in KiB (default 512,
+    /// 0 = none)
+    #[serde(default)]
+    pub output_buffer_kb: Option<u32>,
+    /// Trace log the typed text itself instead of its length (debugging)
+    #[serde(default)]
+    pub verbose_input_log: bool,
+}
+
+impl ConnectParams {
+    fn connect_options(&self, cancel: Option<&ssh::ConnectCancel>) -> ssh::ConnectOptions {
+        ssh::ConnectOptions {
+            algorithms: self.algorithms.clone(),
+            timeouts: ssh::Timeouts::from_millis(self.connect_timeout_ms, self.io_timeout_ms),
+            auto_reconnect: self.auto_reconnect,
+            reconnect_max_attempts: self.reconnect_max_attempts,
+            cancel: cancel.map(ssh::ConnectCancel::signal),
+            connect_id: self.connect_id.clone(),
+            ip_preference: self.ip_preference,
+            skip_knock: self.skip_knock,
+            wake_on_lan: self.wake_on_lan,
+            wake_timeout_secs: self.wake_timeout_secs,
+            latency_interval_secs: self.latency_interval_secs,
+            network_watchdog_secs: self.network_watchdog_secs,
+            reuse_connection: self.reuse_connection,
+            transcript: transcript::TranscriptOptions::new(
+                self.log_max_mb,
+                self.log_input,
+                self.log_plain_text,
+            ),
+            shell_ready: shell_ready::Timings::from_millis(
+                self.shell_ready_quiet_ms,
+                self.shell_ready_timeout_ms,
+            ),
+            skip_startup_command: self.skip_startup_command,
+            output_buffer_kb: self.output_buffer_kb,
+            verbose_input_log: self.verbose_input_log,
+        }
+    }
+
+    /// Proxy given for this connection only (default ports 8080 / 1080)
+    fn proxy(&self) -> Option<db::ProxyConfig> {
+        let proxy_type = self.proxy_type?;
+        let default_port = match proxy_type {
+            db::ProxyType::Http => 8080,
+            db::ProxyType::Socks5 => 1080,
+        };
+        Some(db::ProxyConfig {
+            proxy_type,
+            host: self.proxy_host.clone().unwrap_or_default(),
+            port: self.proxy_port.unwrap_or(default_port).into(),
+            username: self.proxy_username.clone(),
+            password: self.proxy_password.clone(),
+        })
+    }
 }
 
 #[tauri::command]
@@ -174,19 +402,15 @@ async fn ssh_connect(
     app: tauri::AppHandle,
     state: tauri::State<'_, Arc<AppState>>,
     params: ConnectParams,
-) -> Result<String, String> {
-    // Only the DB read is blocking (rusqlite); the SSH stack is async (russh)
-    let db_state = state.inner().clone();
-    let session_id = params.session_id.clone();
-    let session = tauri::async_runtime::spawn_blocking(move || {
-        db_state
-            .db
-            .get_session_secrets(&session_id)
-            .map_err(|e| ssh::SshError::SessionNotFound(format!("{}: {}", session_id, e)))
-    })
-    .await
-    .map_err(|e| e.to_string())?
-    .map_err(|e| e.to_string())?;
+) -> Result<ssh::ConnectInfo, AppError> {
+    let app = ssh::Frontend::from(app);
+    // Registered first: a cancel arriving during the DB read still counts
+    let cancel = params
+        .connect_id
+        .as_deref()
+        .map(|id| state.ssh.register_connect(id));
+
+    let session = load_connect_session(&state, &params).await?;
 
     log::info!(
         "SSH Connect attempt: {}@{}:{} ({} hops, {}x{})",
@@ -206,33 +430,200 @@ async fn ssh_connect(
             params.progress_id.as_deref(),
             params.cols,
             params.rows,
+            &params.connect_options(cancel.as_ref()),
         )
         .await
     {
-        Ok(channel_id) => {
-            log::info!("SSH Connected successfully: {}", channel_id);
-            Ok(channel_id)
+        Ok(info) => {
+            log::info!("SSH Connected successfully: {}", info.channel_id);
+            // The saved forwards, after the shell: the terminal doesn't wait
+            // for them nor fails with them (`forwards_started` says how)
+            match state.db.get_forwards(&session.id) {
+                Ok(rules) if rules.iter().any(|r| r.enabled) => {
+                    tauri::async_runtime::spawn(state.ssh.clone().start_session_forwards(
+                        app.clone(),
+                        info.channel_id.clone(),
+                        rules,
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Forwards of {} not loaded: {}", session.id, e),
+            }
+            Ok(info)
+        }
+        Err(ssh::SshError::Cancelled) => {
+            log::info!("SSH Connect cancelled");
+            Err(ssh::SshError::Cancelled.into())
         }
         Err(e) => {
             log::error!("SSH Connection failed: {:?}", e);
-            Err(e.to_string())
+            Err(e.into())
         }
     }
 }
 
+/// Connect and authenticate with the same params as `ssh_connect`, then
+/// disconnect: timings per phase, server version and algorithms. Errors are
+/// those of `ssh_connect`, with the phase reached and the timings up to it
+/// in `details.phase` / `details.phases`.
+#[tauri::command]
+async fn ssh_test_connection(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    params: ConnectParams,
+) -> Result<ssh::TestReport, AppError> {
+    let app = ssh::Frontend::from(app);
+    let cancel = params
+        .connect_id
+        .as_deref()
+        .map(|id| state.ssh.register_connect(id));
+    let session = load_connect_session(&state, &params).await?;
+
+    state
+        .ssh
+        .test_connection(
+            &app,
+            &session,
+            params.progress_id.as_deref(),
+            &params.connect_options(cancel.as_ref()),
+        )
+        .await
+        .map_err(|failure| {
+            log::info!("SSH test of {} failed: {}", session.host, failure.error);
+            let mut error = AppError::from(failure.error);
+            let details = error.details.get_or_insert_with(|| serde_json::json!({}));
+            details["phase"] = failure.phase.into();
+            details["phases"] = serde_json::to_value(&failure.phases).unwrap_or_default();
+            error
+        })
+}
+
+/// The session of `params` with its secrets, the ~/.ssh/config fill-in and
+/// the per-connection overrides applied
+async fn load_connect_session(
+    state: &tauri::State<'_, Arc<AppState>>,
+    params: &ConnectParams,
+) -> Result<db::Session, AppError> {
+    // Only the DB read is blocking (rusqlite); the SSH stack is async (russh)
+    let db_state = state.inner().clone();
+    let session_id = params.session_id.clone();
+    let use_ssh_config = params.use_ssh_config;
+    let agent_forwarding = params.agent_forwarding;
+    let x11_forwarding = params.x11_forwarding;
+    let compression = params.compression;
+    let keepalive_interval = params.keepalive_interval;
+    let idle_timeout_secs = params.idle_timeout_secs;
+    let proxy = params.proxy();
+    let term = params.term.clone().filter(|t| !t.trim().is_empty());
+    tauri::async_runtime::spawn_blocking(move || {
+        db_state
+            .db
+            .get_session_secrets(&session_id)
+            .map(|mut session| {
+                if use_ssh_config {
+                    ssh_config::SshConfig::load().apply(&mut session);
+                }
+                session.agent_forwarding |= agent_forwarding;
+                session.x11_forwarding |= x11_forwarding;
+                session.compression |= compression;
+                if let Some(secs) = keepalive_interval {
+                    session.keepalive_interval = secs;
+                }
+                if let Some(secs) = idle_timeout_secs {
+                    session.idle_timeout_secs = secs;
+                }
+                if proxy.is_some() {
+                    session.proxy = proxy;
+                }
+                if let Some(term) = term {
+                    session.term = term;
+                }
+                session
+            })
+            .map_err(|e| ssh::SshError::SessionNotFound(format!("{}: {}", session_id, e)))
+    })
+    .await
+    .map_err(AppError::failed)?
+    .map_err(AppError::from)
+}
+
+/// Abort the in-flight `ssh_connect` with this `connect_id`: it fails
+/// promptly with the "cancelled" error. Ok(false) if it already finished.
+#[tauri::command]
+async fn ssh_cancel_connect(
+    state: tauri::State<'_, Arc<AppState>>,
+    connect_id: String,
+) -> Result<bool, AppError> {
+    Ok(state.ssh.cancel_connect(&connect_id))
+}
+
+/// Send a Wake-on-LAN magic packet for `mac` (broadcast default
+/// 255.255.255.255:9); returns the address it was sent to
+#[tauri::command]
+async fn send_wol(mac: String, broadcast: Option<String>) -> Result<String, AppError> {
+    wol::send_magic_packet(&mac, broadcast.as_deref())
+        .await
+        .map(|addr| addr.to_string())
+        .map_err(AppError::from)
+}
+
 #[tauri::command]
 async fn ssh_send(
     state: tauri::State<'_, Arc<AppState>>,
     channel_id: String,
     data: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     // Never log the data itself: it includes everything typed in the terminal
-    log::trace!("ssh_send: channel={}, {} bytes", channel_id, data.len());
+    // Nor its length here: the write path logs what it may (`input_log`)
+    if LocalTerminals::is_local(&channel_id) {
+        return state.local.send(&channel_id, &data).map_err(AppError::from);
+    }
     state
         .ssh
         .send_command(&channel_id, &data)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// `ssh_send` for bytes a string can't carry (NUL, lone DEL, non-UTF-8
+/// binary input of xterm): written as they come
+#[tauri::command]
+async fn ssh_send_bytes(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    data: Vec<u8>,
+) -> Result<(), AppError> {
+    if LocalTerminals::is_local(&channel_id) {
+        return state
+            .local
+            .send_bytes(&channel_id, &data)
+            .map_err(AppError::from);
+    }
+    state
+        .ssh
+        .send_bytes(&channel_id, data)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Cluster typing: the same input to several terminals. Per-channel
+/// outcome (null = sent, else the error) instead of failing as a whole
+#[tauri::command]
+async fn ssh_send_broadcast(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_ids: Vec<String>,
+    data: String,
+) -> Result<HashMap<String, Option<String>>, AppError> {
+    log::trace!("ssh_send_broadcast: {} channels", channel_ids.len());
+    let (local_ids, ssh_ids): (Vec<String>, Vec<String>) = channel_ids
+        .into_iter()
+        .partition(|id| LocalTerminals::is_local(id));
+    let mut sent = state.ssh.send_broadcast(&ssh_ids, &data).await;
+    for id in local_ids {
+        let outcome = state.local.send(&id, &data).err().map(|e| e.to_string());
+        sent.insert(id, outcome);
+    }
+    Ok(sent)
 }
 
 #[tauri::command]
@@ -241,43 +632,639 @@ async fn ssh_resize(
     channel_id: String,
     cols: u16,
     rows: u16,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    if LocalTerminals::is_local(&channel_id) {
+        return state
+            .local
+            .resize(&channel_id, cols, rows)
+            .map_err(AppError::from);
+    }
     state
         .ssh
         .resize(&channel_id, cols, rows)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn ssh_disconnect(
     state: tauri::State<'_, Arc<AppState>>,
     channel_id: String,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    if LocalTerminals::is_local(&channel_id) {
+        return state.local.disconnect(&channel_id).map_err(AppError::from);
+    }
     state
         .ssh
         .disconnect(&channel_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// Close every open connection ("Desconectar todas"), local shells
+/// included; returns how many
+#[tauri::command]
+async fn ssh_disconnect_all(state: tauri::State<'_, Arc<AppState>>) -> Result<usize, AppError> {
+    Ok(state.ssh.disconnect_all().await + state.local.disconnect_all())
+}
+
+/// Local shell tab: the platform shell (or `shell`) under a PTY, driven like
+/// an SSH channel with the returned channel id. `verboseInputLog` as in the
+/// connect params
+#[tauri::command]
+fn local_connect(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    shell: Option<String>,
+    verbose_input_log: Option<bool>,
+) -> Result<local::LocalInfo, AppError> {
+    state
+        .local
+        .connect(
+            &app,
+            cols.unwrap_or(80),
+            rows.unwrap_or(24),
+            shell.as_deref(),
+            verbose_input_log.unwrap_or(false),
+        )
+        .map_err(AppError::from)
+}
+
+/// Banner, negotiated algorithms, host key and byte counters of an open
+/// channel, for the tab's connection details
+#[tauri::command]
+async fn ssh_get_connection_info(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+) -> Result<ssh::ConnectionInfo, AppError> {
+    state
+        .ssh
+        .connection_info(&channel_id)
+        .map_err(AppError::from)
+}
+
+/// Start (Some(path) of the transcript) or stop the session log of a
+/// channel
+#[tauri::command]
+async fn ssh_set_logging(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    enabled: bool,
+) -> Result<Option<String>, AppError> {
+    state
+        .ssh
+        .set_logging(&channel_id, enabled)
+        .map_err(AppError::from)
+}
+
+/// Record a channel to an asciinema v2 file at `path` (keystrokes only
+/// with `record_input`) until `ssh_stop_recording` or the channel closes
+#[tauri::command]
+async fn ssh_start_recording(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    path: String,
+    record_input: Option<bool>,
+) -> Result<(), AppError> {
+    state
+        .ssh
+        .start_recording(
+            &channel_id,
+            std::path::Path::new(&path),
+            record_input.unwrap_or(false),
+        )
+        .map_err(AppError::from)
+}
+
+/// Flush and close the recording of a channel; false if none was running
+#[tauri::command]
+async fn ssh_stop_recording(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+) -> Result<bool, AppError> {
+    state
+        .ssh
+        .stop_recording(&channel_id)
+        .map_err(AppError::from)
+}
+
+/// Byte totals and current throughput of a channel (rates come from the
+/// difference between calls: poll it every few seconds)
+#[tauri::command]
+async fn ssh_get_channel_stats(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+) -> Result<ssh::ChannelStats, AppError> {
+    state.ssh.channel_stats(&channel_id).map_err(AppError::from)
+}
+
+/// Flow control: the frontend wrote `bytes` of the channel's pty_output
+/// (the `bytes` of each event). Local terminals don't wait for acks.
+#[tauri::command]
+fn pty_ack(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    bytes: u64,
+) -> Result<(), AppError> {
+    if LocalTerminals::is_local(&channel_id) {
+        return Ok(());
+    }
+    state
+        .ssh
+        .ack_output(&channel_id, bytes)
+        .map_err(AppError::from)
+}
+
+/// "Descartar salida": the output is dropped until it stops, then
+/// `pty_discarded` says how much. SSH channels only: a local shell has no
+/// reader to hold back (the tab hides the button)
+#[tauri::command]
+fn ssh_discard_output(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+) -> Result<(), AppError> {
+    if LocalTerminals::is_local(&channel_id) {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "Local terminals can't discard their output",
+        ));
+    }
+    state
+        .ssh
+        .discard_output(&channel_id)
+        .map_err(AppError::from)
+}
+
+/// `ssh -L`: `local_bind_host:local_port` here ("" = 127.0.0.1, 0 = any free
+/// port) relayed to `remote_host:remote_port` from the server
+#[tauri::command]
+async fn ssh_forward_local(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    local_bind_host: String,
+    local_port: u16,
+    remote_host: String,
+    remote_port: u16,
+) -> Result<forward::ForwardInfo, AppError> {
+    state
+        .ssh
+        .forward_local(
+            &channel_id,
+            &local_bind_host,
+            local_port,
+            &remote_host,
+            remote_port,
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// `ssh -R`: the server's `remote_bind_host:remote_port` (0 = the server
+/// picks, the result says which) relayed to `local_host:local_port`
+#[tauri::command]
+async fn ssh_forward_remote(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    remote_bind_host: String,
+    remote_port: u16,
+    local_host: String,
+    local_port: u16,
+) -> Result<forward::ForwardInfo, AppError> {
+    state
+        .ssh
+        .forward_remote(
+            &channel_id,
+            &remote_bind_host,
+            remote_port,
+            &local_host,
+            local_port,
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// `ssh -D`: SOCKS5 proxy on 127.0.0.1:`local_port` (0 = any free port)
+/// reaching out through the channel's connection
+#[tauri::command]
+async fn ssh_start_socks(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    local_port: u16,
+) -> Result<forward::ForwardInfo, AppError> {
+    state
+        .ssh
+        .start_socks(&channel_id, local_port)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn ssh_stop_socks(
+    state: tauri::State<'_, Arc<AppState>>,
+    forward_id: String,
+) -> Result<(), AppError> {
+    ssh_cancel_forward(state, forward_id).await
+}
+
+#[tauri::command]
+async fn ssh_cancel_forward(
+    state: tauri::State<'_, Arc<AppState>>,
+    forward_id: String,
+) -> Result<(), AppError> {
+    state
+        .ssh
+        .cancel_forward(&forward_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+fn ssh_list_forwards(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: Option<String>,
+) -> Vec<forward::ForwardInfo> {
+    state.ssh.list_forwards(channel_id.as_deref())
+}
+
+/// Recent output of a channel (last `outputBufferKb` KiB): what a reloaded
+/// frontend repaints before the live `pty_output` events
+#[tauri::command]
+fn ssh_get_buffer(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+) -> Result<String, AppError> {
+    state.ssh.output_buffer(&channel_id).map_err(AppError::from)
+}
+
+/// Channels the backend still holds (for a reloaded frontend to rebind)
+#[tauri::command]
+async fn ssh_list_channels(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<ssh::ChannelSummary>, AppError> {
+    Ok(state.ssh.list_channels())
+}
+
+/// After `ssh_list_channels`: the ids given tabs again. The rest are closed
+/// after a grace period; returns how many are left unclaimed.
+#[tauri::command]
+async fn ssh_claim_channels(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_ids: Vec<String>,
+) -> Result<usize, AppError> {
+    Ok(state.ssh.claim_channels(&channel_ids))
 }
 
 /// Release resources of channels whose reader task already detected
 /// EOF/error. The frontend calls this on every `pty_closed` event so dead
 /// sessions don't linger until the next connect.
 #[tauri::command]
-async fn ssh_cleanup_dead(state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+async fn ssh_cleanup_dead(state: tauri::State<'_, Arc<AppState>>) -> Result<(), AppError> {
     state.ssh.cleanup_dead_channels();
     Ok(())
 }
 
+/// Paste `data` in bounded chunks (bracketed when the remote enabled it);
+/// big pastes report `paste_progress` and `ssh_cancel_paste` stops them
+#[tauri::command]
+async fn ssh_paste(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    data: String,
+    bracketed: bool,
+) -> Result<ssh::PasteOutcome, AppError> {
+    let app = ssh::Frontend::from(app);
+    log::trace!("ssh_paste: channel={}, {} bytes", channel_id, data.len());
+    // No SSH window to respect locally: one write
+    if LocalTerminals::is_local(&channel_id) {
+        let text = ssh::prepare_paste(&data, bracketed);
+        state
+            .local
+            .send(&channel_id, &text)
+            .map_err(AppError::from)?;
+        return Ok(ssh::PasteOutcome {
+            bytes_sent: text.len() as u64,
+            cancelled: false,
+        });
+    }
+    state
+        .ssh
+        .paste(&app, &channel_id, &data, bracketed)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Stop the paste in progress: false when there is none
+#[tauri::command]
+async fn ssh_cancel_paste(
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+) -> Result<bool, AppError> {
+    Ok(state.ssh.cancel_paste(&channel_id))
+}
+
+/// Drop the channels left behind by reader tasks that already ended (dead
+/// channels nobody cleaned up). Returns how many were removed.
+#[tauri::command]
+async fn ssh_gc_channels(state: tauri::State<'_, Arc<AppState>>) -> Result<usize, AppError> {
+    Ok(state.ssh.gc_channels())
+}
+
+/// Answer a keyboard-interactive `auth_prompt` (PAM/OTP) raised during
+/// `ssh_connect`. `answers: None` cancels the prompt and aborts the connect.
+#[tauri::command]
+async fn ssh_auth_answer(
+    state: tauri::State<'_, Arc<AppState>>,
+    request_id: String,
+    answers: Option<Vec<String>>,
+) -> Result<(), AppError> {
+    state
+        .ssh
+        .answer_auth_prompt(&request_id, answers)
+        .map_err(AppError::from)
+}
+
+/// Answer a `password_change_required` event of connect `connect_id`:
+/// `old` empty means the stored password. The connect goes on with the
+/// change; it fails with "Password change rejected" if the server refuses.
+#[tauri::command]
+async fn ssh_change_password(
+    state: tauri::State<'_, Arc<AppState>>,
+    connect_id: String,
+    old: String,
+    new: String,
+) -> Result<(), AppError> {
+    state
+        .ssh
+        .change_password(&connect_id, old, new)
+        .map_err(AppError::from)
+}
+
+/// Answer an `auth_passphrase_required` prompt raised when a key's stored
+/// passphrase is missing or wrong. `passphrase: None` aborts the connect.
+#[tauri::command]
+async fn ssh_provide_passphrase(
+    state: tauri::State<'_, Arc<AppState>>,
+    request_id: String,
+    passphrase: Option<String>,
+) -> Result<(), AppError> {
+    state
+        .ssh
+        .provide_passphrase(&request_id, passphrase)
+        .map_err(AppError::from)
+}
+
+/// Where `ssh_install_public_key` / `ssh_exec` run: an open terminal's
+/// connection, or a one-shot connection with a saved session's credentials
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum RemoteTarget {
+    Channel {
+        #[serde(rename = "channelId")]
+        channel_id: String,
+    },
+    Session {
+        #[serde(rename = "sessionId")]
+        session_id: String,
+    },
+}
+
+/// ssh-copy-id: append `public_key` to the server's ~/.ssh/authorized_keys
+/// (creating it with safe permissions) unless it is already there
+#[tauri::command]
+async fn ssh_install_public_key(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    target: RemoteTarget,
+    public_key: String,
+) -> Result<ssh::KeyInstall, AppError> {
+    let app = ssh::Frontend::from(app);
+    let result = match target {
+        RemoteTarget::Channel { channel_id } => {
+            state.ssh.install_public_key(&channel_id, &public_key).await
+        }
+        RemoteTarget::Session { session_id } => {
+            let session = load_session_secrets(&state, session_id).await?;
+            state
+                .ssh
+                .install_public_key_once(&app, &session, &public_key)
+                .await
+        }
+    };
+    result.map_err(AppError::from)
+}
+
+/// Run `command` without a PTY and return stdout / stderr apart. Fails with
+/// "timed out" past `timeout_ms` (default 30 s); each stream keeps at most
+/// `max_output_bytes` (default 1 MiB), with `truncated` set beyond that.
+#[tauri::command]
+async fn ssh_exec(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    target: RemoteTarget,
+    command: String,
+    timeout_ms: Option<u64>,
+    max_output_bytes: Option<usize>,
+) -> Result<ssh::ExecOutput, AppError> {
+    let app = ssh::Frontend::from(app);
+    let limits = ssh::ExecLimits::new(timeout_ms, max_output_bytes);
+    let result = match target {
+        RemoteTarget::Channel { channel_id } => state.ssh.exec(&channel_id, &command, limits).await,
+        RemoteTarget::Session { session_id } => {
+            let session = load_session_secrets(&state, session_id).await?;
+            state.ssh.exec_once(&app, &session, &command, limits).await
+        }
+    };
+    result.map_err(AppError::from)
+}
+
+/// Run `command` on many saved sessions over short-lived connections (or
+/// their open tabs), `parallelism` hosts at a time (default 8, at most 32).
+/// Every host sends an `exec_multi_result` event; the summary comes last,
+/// also as `exec_multi_done`. `batch_id` tags the events (a new id if None).
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn ssh_exec_multi(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    session_ids: Vec<String>,
+    command: String,
+    parallelism: Option<usize>,
+    timeout_ms: Option<u64>,
+    max_output_bytes: Option<usize>,
+    batch_id: Option<String>,
+) -> Result<ssh::MultiExecSummary, AppError> {
+    let app = ssh::Frontend::from(app);
+    let db_state = state.inner().clone();
+    let targets = tauri::async_runtime::spawn_blocking(move || {
+        session_ids
+            .into_iter()
+            .map(|id| {
+                let session = db_state
+                    .db
+                    .get_session_secrets(&id)
+                    .map_err(|e| e.to_string());
+                (id, session)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(AppError::failed)?;
+    let batch_id = batch_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    Ok(state
+        .ssh
+        .exec_multi(
+            &app,
+            &batch_id,
+            targets,
+            &command,
+            ssh::ExecLimits::new(timeout_ms, max_output_bytes),
+            ssh::exec_parallelism(parallelism),
+        )
+        .await)
+}
+
+/// Run `commands` in order on the connection of `channel_id` (exec
+/// channels, not the terminal). Each step has its own `step_timeout_ms`
+/// (default 30 s) and `max_output_bytes`; `queue_progress` follows every
+/// step. With `queue_id` the queue can be stopped by `ssh_cancel_queue`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn ssh_run_queue(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+    channel_id: String,
+    commands: Vec<String>,
+    stop_on_error: bool,
+    step_timeout_ms: Option<u64>,
+    max_output_bytes: Option<usize>,
+    queue_id: Option<String>,
+) -> Result<ssh::QueueOutcome, AppError> {
+    let app = ssh::Frontend::from(app);
+    let limits = ssh::ExecLimits::new(step_timeout_ms, max_output_bytes);
+    state
+        .ssh
+        .run_queue(
+            &app,
+            &channel_id,
+            &commands,
+            stop_on_error,
+            limits,
+            queue_id.as_deref(),
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// Stop a running `ssh_run_queue`; false if it already finished
+#[tauri::command]
+fn ssh_cancel_queue(state: tauri::State<'_, Arc<AppState>>, queue_id: String) -> bool {
+    state.ssh.cancel_queue(&queue_id)
+}
+
+async fn load_session_secrets(
+    state: &tauri::State<'_, Arc<AppState>>,
+    session_id: String,
+) -> Result<db::Session, AppError> {
+    let db_state = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || db_state.db.get_session_secrets(&session_id))
+        .await
+        .map_err(AppError::failed)?
+        .map_err(AppError::from)
+}
+
+/// Create a new key pair (`algorithm` "ed25519" or "rsa") in `directory`
+/// and return its public key line and fingerprint
+#[tauri::command]
+async fn generate_keypair(
+    algorithm: String,
+    bits: Option<u32>,
+    passphrase: Option<String>,
+    comment: Option<String>,
+    directory: String,
+    file_name: Option<String>,
+) -> Result<keygen::GeneratedKey, AppError> {
+    // RSA-4096 takes a while: keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        keygen::generate_to_dir(
+            &algorithm,
+            bits,
+            passphrase.as_deref(),
+            comment.as_deref().unwrap_or(""),
+            &ssh::expand_tilde(&directory),
+            file_name.as_deref(),
+        )
+    })
+    .await
+    .map_err(AppError::failed)?
+    .map_err(AppError::from)
+}
+
+/// Host aliases defined in ~/.ssh/config with their resolved options, for
+/// the import/autocomplete list of the session editor
+#[tauri::command]
+async fn ssh_config_hosts() -> Result<Vec<ssh_config::SshConfigHost>, AppError> {
+    tauri::async_runtime::spawn_blocking(|| ssh_config::SshConfig::load().hosts())
+        .await
+        .map_err(AppError::failed)
+}
+
+/// Algorithm names accepted in ConnectParams.algorithms, per kind
+#[tauri::command]
+fn ssh_supported_algorithms() -> algorithms::AlgorithmPrefs {
+    algorithms::AlgorithmPrefs::supported()
+}
+
+/// Handshake-only probe (no authentication) returning the server banner and
+/// host key fingerprints, shown in the session editor before saving.
+/// `connectTimeoutMs` / `ioTimeoutMs` as in the connect params.
+#[tauri::command]
+async fn ssh_get_host_fingerprint(
+    host: String,
+    port: u16,
+    connect_timeout_ms: Option<u64>,
+    io_timeout_ms: Option<u64>,
+) -> Result<ssh::HostFingerprint, AppError> {
+    let timeouts = ssh::Timeouts::from_millis(connect_timeout_ms, io_timeout_ms);
+    ssh::probe_host_key(&host, port, timeouts)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Trust the host key of an unknown server after the user checked the
+/// fingerprint reported by the failed connect. The frontend then reconnects.
+#[tauri::command]
+async fn ssh_accept_host_key(host: String, port: u16, fingerprint: String) -> Result<(), AppError> {
+    tauri::async_runtime::spawn_blocking(move || ssh::accept_host_key(&host, port, &fingerprint))
+        .await
+        .map_err(AppError::failed)?
+        .map_err(AppError::from)
+}
+
+/// Replace the stored key of a server whose host key changed with the new
+/// key reported by the failed connect (matched by fingerprint).
+#[tauri::command]
+async fn ssh_replace_host_key(
+    host: String,
+    port: u16,
+    fingerprint: String,
+) -> Result<(), AppError> {
+    tauri::async_runtime::spawn_blocking(move || ssh::replace_host_key(&host, port, &fingerprint))
+        .await
+        .map_err(AppError::failed)?
+        .map_err(AppError::from)
+}
+
 /// Remove a stored host key after a HostKeyMismatch (e.g. the server was
 /// legitimately reinstalled). Returns true if an entry was removed.
 #[tauri::command]
-async fn forget_host_key(host: String, port: u16) -> Result<bool, String> {
+async fn forget_host_key(host: String, port: u16) -> Result<bool, AppError> {
     tauri::async_runtime::spawn_blocking(move || ssh::forget_host_key(&host, port))
         .await
-        .map_err(|e| e.to_string())?
-        .map_err(|e| e.to_string())
+        .map_err(AppError::failed)?
+        .map_err(AppError::from)
 }
 
 // ==================== APP ENTRY POINT ====================
@@ -288,7 +1275,11 @@ pub fn run() {
     let db = Database::new().expect("Failed to initialize database");
     let ssh = SshManager::new();
 
-    let state = Arc::new(AppState { db, ssh });
+    let state = Arc::new(AppState {
+        db,
+        ssh,
+        local: LocalTerminals::default(),
+    });
 
     tauri::Builder::default()
         .manage(state)
@@ -296,6 +1287,8 @@ pub fn run() {
         .plugin(tauri_plugin_window_state::Builder::default().build())
         // Native save dialog for exporting sessions
         .plugin(tauri_plugin_dialog::init())
+        // System clipboard for OSC 52 copies (allowRemoteClipboard sessions)
+        .plugin(tauri_plugin_clipboard_manager::init())
         .setup(|app| {
             app.handle().plugin(
                 tauri_plugin_log::Builder::default()
@@ -316,6 +1309,9 @@ pub fn run() {
             get_commands,
             save_command,
             delete_command,
+            get_forwards,
+            save_forward,
+            delete_forward,
             count_session_jump_refs,
             // Session audit logs
             add_session_log,
@@ -325,11 +1321,71 @@ pub fn run() {
             // SSH commands
             ssh_connect,
             ssh_send,
+            ssh_send_bytes,
+            ssh_send_broadcast,
             ssh_resize,
             ssh_disconnect,
+            ssh_disconnect_all,
+            local_connect,
+            ssh_get_connection_info,
+            ssh_get_channel_stats,
+            ssh_get_buffer,
+            pty_ack,
+            ssh_discard_output,
+            ssh_forward_local,
+            ssh_forward_remote,
+            ssh_cancel_forward,
+            ssh_start_socks,
+            ssh_stop_socks,
+            ssh_list_forwards,
+            ssh_set_logging,
+            ssh_start_recording,
+            ssh_stop_recording,
+            ssh_list_channels,
+            ssh_claim_channels,
+            ssh_exec,
+            ssh_run_queue,
+            ssh_exec_multi,
+            ssh_cancel_queue,
             ssh_cleanup_dead,
+            ssh_gc_channels,
+            ssh_paste,
+            ssh_cancel_paste,
+            ssh_cancel_connect,
+            ssh_test_connection,
+            send_wol,
+            ssh_auth_answer,
+            ssh_provide_passphrase,
+            ssh_change_password,
+            ssh_get_host_fingerprint,
+            ssh_config_hosts,
+            ssh_supported_algorithms,
+            ssh_install_public_key,
+            generate_keypair,
+            ssh_accept_host_key,
+            ssh_replace_host_key,
             forget_host_key,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Closing the app ends every SSH session first: dropped sockets
+            // leave them lingering on the servers until TCP timeouts
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                let ssh = app.state::<Arc<AppState>>().ssh.clone();
+                // Readers stop emitting to a webview on its way out
+                ssh.begin_shutdown();
+                // No shell left behind as an orphan of the app
+                app.state::<Arc<AppState>>().local.disconnect_all();
+                if ssh.has_channels() {
+                    api.prevent_exit();
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let closed = ssh.disconnect_all().await;
+                        log::info!("Closed {} SSH connection(s) before exit", closed);
+                        app.exit(0);
+                    });
+                }
+            }
+        });
 }
@@ -0,0 +1,337 @@
+//! Local shell tabs: the platform shell (cmd / PowerShell on Windows, $SHELL
+//! elsewhere) under a PTY from portable-pty, behind the same channel-id
+//! scheme as the SSH tabs: `pty_output` / `pty_closed` out, `ssh_send` /
+//! `ssh_resize` / `ssh_disconnect` in. The terminal view can't tell them
+//! apart.
+//!
+//! Blocking I/O, so each terminal has two threads: the reader (output until
+//! EOF, then `pty_closed`) and the waiter (the shell's exit status; it drops
+//! the PTY so the reader sees EOF on Windows too).
+
+use crate::input_log::InputLog;
+use crate::ssh::take_complete_utf8;
+use portable_pty::{native_pty_system, Child, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Channel ids of local terminals start with it
+pub const CHANNEL_PREFIX: &str = "local-";
+const READ_BUFFER: usize = 16 * 1024;
+/// How long the reader waits for the exit status after EOF
+const EXIT_WAIT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum LocalError {
+    #[error("Cannot start the local shell {shell}: {message}")]
+    Spawn { shell: String, message: String },
+    #[error("Local terminal not found: {0}")]
+    NotFound(String),
+    #[error("Local terminal I/O failed: {0}")]
+    Io(String),
+}
+
+/// `local_connect` result
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalInfo {
+    pub channel_id: String,
+    /// The program started ("/bin/zsh", "C:\\Windows\\system32\\cmd.exe")
+    pub shell: String,
+}
+
+struct LocalPty {
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    control: Arc<Control>,
+}
+
+/// What the reader thread shares with the terminal. Not the PTY itself: the
+/// master must close when the waiter drops the terminal, or a ConPTY read
+/// never ends.
+#[derive(Default)]
+struct Control {
+    /// Closed by `disconnect`: `pty_closed` says "user"
+    closing: AtomicBool,
+    /// What the trace log may say about the keystrokes
+    input_log: InputLog,
+}
+
+/// A started shell, before its threads run
+struct Opened {
+    pty: LocalPty,
+    reader: Box<dyn Read + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    shell: String,
+}
+
+#[derive(Clone, Default)]
+pub struct LocalTerminals {
+    ptys: Arc<Mutex<HashMap<String, Arc<LocalPty>>>>,
+}
+
+/// The shell of a new local tab: `requested`, else $SHELL (/bin/sh), or
+/// %COMSPEC% (cmd.exe) on Windows
+fn default_shell(requested: Option<&str>) -> String {
+    if let Some(shell) = requested.map(str::trim).filter(|s| !s.is_empty()) {
+        return shell.to_string();
+    }
+    let (var, fallback) = if cfg!(windows) {
+        ("COMSPEC", "cmd.exe")
+    } else {
+        ("SHELL", "/bin/sh")
+    };
+    std::env::var(var)
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+fn pty_size(cols: u16, rows: u16) -> PtySize {
+    PtySize {
+        rows: rows.max(1),
+        cols: cols.max(1),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+fn open(shell: &str, args: &[&str], cols: u16, rows: u16) -> Result<Opened, LocalError> {
+    let spawn_error = |e: &dyn std::fmt::Display| LocalError::Spawn {
+        shell: shell.to_string(),
+        message: e.to_string(),
+    };
+    let pair = native_pty_system()
+        .openpty(pty_size(cols, rows))
+        .map_err(|e| spawn_error(&e))?;
+    let mut command = CommandBuilder::new(shell);
+    command.args(args);
+    command.env("TERM", "xterm-256color");
+    if let Some(home) = dirs::home_dir() {
+        command.cwd(home);
+    }
+    let child = pair
+        .slave
+        .spawn_command(command)
+        .map_err(|e| spawn_error(&e))?;
+    // Only the child keeps the slave side: its exit is our EOF
+    drop(pair.slave);
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| spawn_error(&e))?;
+    let writer = pair.master.take_writer().map_err(|e| spawn_error(&e))?;
+    Ok(Opened {
+        pty: LocalPty {
+            master: Mutex::new(pair.master),
+            writer: Mutex::new(writer),
+            killer: Mutex::new(child.clone_killer()),
+            control: Arc::default(),
+        },
+        reader,
+        child,
+        shell: shell.to_string(),
+    })
+}
+
+impl LocalTerminals {
+    pub fn is_local(channel_id: &str) -> bool {
+        channel_id.starts_with(CHANNEL_PREFIX)
+    }
+
+    /// Start a shell in a new local terminal of cols×rows. `verbose_input_log`:
+    /// the typed text goes to the trace log (see `input_log`)
+    pub fn connect(
+        &self,
+        app: &tauri::AppHandle,
+        cols: u16,
+        rows: u16,
+        shell: Option<&str>,
+        verbose_input_log: bool,
+    ) -> Result<LocalInfo, LocalError> {
+        let opened = open(&default_shell(shell), &[], cols, rows)?;
+        let channel_id = format!("{}{}", CHANNEL_PREFIX, Uuid::new_v4());
+        let control = opened.pty.control.clone();
+        control.input_log.set_verbose(verbose_input_log);
+        if verbose_input_log {
+            log::warn!(
+                "{}: verbose input logging on: typed text goes to the log",
+                channel_id
+            );
+        }
+        self.ptys
+            .lock()
+            .unwrap()
+            .insert(channel_id.clone(), Arc::new(opened.pty));
+
+        let (exit_tx, exit_rx) = mpsc::channel();
+        let mut child = opened.child;
+        let terminals = self.clone();
+        let id = channel_id.clone();
+        std::thread::spawn(move || {
+            let status = child.wait();
+            // Out of the map and dropped: the master closes here (the reader
+            // holds no reference), which ends the read on Windows too
+            let pty = terminals.ptys.lock().unwrap().remove(&id);
+            drop(pty);
+            let _ = exit_tx.send(status.ok());
+        });
+
+        let app = app.clone();
+        let id = channel_id.clone();
+        let mut reader = opened.reader;
+        std::thread::spawn(move || {
+            read_output(&app, &id, &mut reader, &control.input_log);
+            let status = exit_rx.recv_timeout(EXIT_WAIT).ok().flatten();
+            let reason = if control.closing.load(Ordering::Relaxed) {
+                "user"
+            } else {
+                "eof"
+            };
+            log::info!("{}: local shell ended ({})", id, reason);
+            let _ = app.emit(
+                "pty_closed",
+                serde_json::json!({
+                    "channelId": id,
+                    "reason": reason,
+                    "exitStatus": status.as_ref().map(|s| s.exit_code()),
+                    "exitSignal": status.as_ref().and_then(|s| s.signal()),
+                }),
+            );
+        });
+
+        log::info!("{}: local shell {} started", channel_id, opened.shell);
+        Ok(LocalInfo {
+            channel_id,
+            shell: opened.shell,
+        })
+    }
+
+    fn get(&self, channel_id: &str) -> Result<Arc<LocalPty>, LocalError> {
+        self.ptys
+            .lock()
+            .unwrap()
+            .get(channel_id)
+            .cloned()
+            .ok_or_else(|| LocalError::NotFound(channel_id.to_string()))
+    }
+
+    pub fn send(&self, channel_id: &str, data: &str) -> Result<(), LocalError> {
+        self.send_bytes(channel_id, data.as_bytes())
+    }
+
+    pub fn send_bytes(&self, channel_id: &str, data: &[u8]) -> Result<(), LocalError> {
+        let pty = self.get(channel_id)?;
+        log::trace!(
+            "{}: input {}",
+            channel_id,
+            pty.control.input_log.describe(data)
+        );
+        let mut writer = pty.writer.lock().unwrap();
+        writer
+            .write_all(data)
+            .and_then(|_| writer.flush())
+            .map_err(|e| LocalError::Io(e.to_string()))
+    }
+
+    pub fn resize(&self, channel_id: &str, cols: u16, rows: u16) -> Result<(), LocalError> {
+        let pty = self.get(channel_id)?;
+        let master = pty.master.lock().unwrap();
+        master
+            .resize(pty_size(cols, rows))
+            .map_err(|e| LocalError::Io(e.to_string()))
+    }
+
+    /// Kill the shell; its reader sends `pty_closed` ("user") once the
+    /// output ends
+    pub fn disconnect(&self, channel_id: &str) -> Result<(), LocalError> {
+        let pty = self.get(channel_id)?;
+        pty.control.closing.store(true, Ordering::Relaxed);
+        let result = pty.killer.lock().unwrap().kill();
+        // Already gone is as good as killed
+        result.or_else(|e| match e.kind() {
+            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::NotFound => Ok(()),
+            _ => Err(LocalError::Io(e.to_string())),
+        })
+    }
+
+    /// On exit and "Desconectar todas": every local shell is killed
+    pub fn disconnect_all(&self) -> usize {
+        let ids: Vec<String> = self.ptys.lock().unwrap().keys().cloned().collect();
+        for id in &ids {
+            if let Err(e) = self.disconnect(id) {
+                log::warn!("{}: cannot kill the local shell: {}", id, e);
+            }
+        }
+        ids.len()
+    }
+}
+
+/// `pty_output` events until the shell closes its side
+fn read_output(
+    app: &tauri::AppHandle,
+    channel_id: &str,
+    reader: &mut Box<dyn Read + Send>,
+    input_log: &InputLog,
+) {
+    let mut buffer = vec![0u8; READ_BUFFER];
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        match reader.read(&mut buffer) {
+            // EOF, or EIO once the slave side is closed (Linux)
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                pending.extend_from_slice(&buffer[..n]);
+                let data = take_complete_utf8(&mut pending);
+                if !data.is_empty() {
+                    input_log.output(&data);
+                    let _ = app.emit(
+                        "pty_output",
+                        serde_json::json!({ "channelId": channel_id, "data": data }),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_choice_and_channel_ids() {
+        assert_eq!(default_shell(Some(" pwsh.exe ")), "pwsh.exe");
+        assert!(!default_shell(None).is_empty());
+        assert!(!default_shell(Some("  ")).is_empty());
+        assert!(LocalTerminals::is_local("local-1234"));
+        assert!(!LocalTerminals::is_local("5f0c6a0e-ssh-channel"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shell_output_goes_through_the_pty() {
+        let mut opened = open("/bin/sh", &["-c", "printf 'hola %s' $TERM"], 80, 24).unwrap();
+        let status = opened.child.wait().unwrap();
+        assert!(status.success());
+        // The output waits in the PTY with the slave side closed
+        drop(opened.pty);
+        let mut out = Vec::new();
+        let mut buffer = [0u8; 1024];
+        while let Ok(n) = opened.reader.read(&mut buffer) {
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buffer[..n]);
+        }
+        let out = String::from_utf8_lossy(&out);
+        assert_eq!(out.trim_end(), "hola xterm-256color");
+    }
+}
@@ -0,0 +1,218 @@
+//! OSC 52 (`ESC ] 52 ; Pc ; Pd BEL` or `... ESC \`): a remote tmux / vim
+//! setting the local clipboard. Only for sessions with
+//! `allowRemoteClipboard`; the sequence never reaches the terminal (nor the
+//! transcript), whatever it asks.
+//!
+//! Streaming: a sequence cut between two flushes is held back until its
+//! terminator arrives. Reading the clipboard (`Pd` = "?") is never answered:
+//! the server would get whatever the user last copied.
+
+use base64::Engine;
+
+const INTRODUCER: &str = "\x1b]52;";
+/// Largest text a server can put in the clipboard
+pub const MAX_BYTES: usize = 100 * 1024;
+/// Base64 of `MAX_BYTES` plus the selection field: past it the rest of the
+/// sequence is skipped, not kept
+const MAX_SEQUENCE: usize = MAX_BYTES / 3 * 4 + 64;
+
+/// What one OSC 52 sequence asked for
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    /// Put this text in the clipboard
+    Copy(String),
+    /// Clipboard read ("?"): ignored
+    Query,
+    /// More than `MAX_BYTES` (or its base64 would be)
+    TooLarge,
+    /// Not base64, or not text
+    Invalid,
+}
+
+impl Request {
+    /// Why nothing was copied (`clipboard_refused` reason)
+    pub fn refusal(&self) -> Option<&'static str> {
+        match self {
+            Request::Copy(_) => None,
+            Request::Query => Some("read_denied"),
+            Request::TooLarge => Some("too_large"),
+            Request::Invalid => Some("invalid"),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Osc52Filter {
+    /// Inside a sequence: its `Pc;Pd` so far (None once past `MAX_SEQUENCE`)
+    sequence: Option<Option<String>>,
+    /// The end of the last flush could be the start of `INTRODUCER`, or the
+    /// ESC of a sequence's `ESC \`
+    held: String,
+}
+
+impl Osc52Filter {
+    /// The text for the terminal, without OSC 52 sequences. The requests
+    /// completed in `text` are added to `requests`.
+    pub fn filter(&mut self, text: &str, requests: &mut Vec<Request>) -> String {
+        let mut input = std::mem::take(&mut self.held);
+        input.push_str(text);
+        let mut out = String::with_capacity(input.len());
+        let mut rest = input.as_str();
+
+        while !rest.is_empty() {
+            if self.sequence.is_some() {
+                rest = self.in_sequence(rest, requests);
+                continue;
+            }
+            match rest.find(INTRODUCER) {
+                Some(start) => {
+                    out.push_str(&rest[..start]);
+                    rest = &rest[start + INTRODUCER.len()..];
+                    self.sequence = Some(Some(String::new()));
+                }
+                None => {
+                    // "…\x1b]5" at the end: wait for the next flush to decide
+                    let keep = partial_introducer(rest);
+                    out.push_str(&rest[..rest.len() - keep]);
+                    self.held = rest[rest.len() - keep..].to_string();
+                    rest = "";
+                }
+            }
+        }
+        out
+    }
+
+    /// Consume sequence bytes up to its terminator; returns what follows
+    fn in_sequence<'a>(&mut self, text: &'a str, requests: &mut Vec<Request>) -> &'a str {
+        let end = text.find(['\x07', '\x1b']);
+        let body = &text[..end.unwrap_or(text.len())];
+        if let Some(Some(payload)) = &mut self.sequence {
+            if payload.len() + body.len() > MAX_SEQUENCE {
+                self.sequence = Some(None);
+            } else {
+                payload.push_str(body);
+            }
+        }
+        match end {
+            None => "",
+            Some(at) if text.as_bytes()[at] == 0x07 => {
+                self.finish(requests);
+                &text[at + 1..]
+            }
+            Some(at) => match text[at + 1..].chars().next() {
+                Some('\\') => {
+                    self.finish(requests);
+                    &text[at + 2..]
+                }
+                // Another escape sequence: this one was cut (xterm drops it)
+                Some(_) => {
+                    self.sequence = None;
+                    &text[at..]
+                }
+                None => {
+                    self.held = "\x1b".to_string();
+                    ""
+                }
+            },
+        }
+    }
+
+    fn finish(&mut self, requests: &mut Vec<Request>) {
+        let request = match self.sequence.take().flatten() {
+            Some(payload) => decode(&payload),
+            None => Request::TooLarge,
+        };
+        requests.push(request);
+    }
+}
+
+/// `Pc;Pd` → what it asks for
+fn decode(payload: &str) -> Request {
+    // No ';': an empty selection list with the data right away
+    let data = payload.split_once(';').map_or(payload, |(_, data)| data);
+    if data == "?" {
+        return Request::Query;
+    }
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let engine = base64::engine::general_purpose::GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        base64::engine::general_purpose::GeneralPurposeConfig::new()
+            .with_decode_padding_mode(base64::engine::DecodePaddingMode::Indifferent),
+    );
+    match engine.decode(data.as_bytes()) {
+        Ok(bytes) if bytes.len() > MAX_BYTES => Request::TooLarge,
+        Ok(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => Request::Copy(text),
+            Err(_) => Request::Invalid,
+        },
+        Err(_) => Request::Invalid,
+    }
+}
+
+/// Length of the longest end of `text` that `INTRODUCER` starts with
+fn partial_introducer(text: &str) -> usize {
+    (1..INTRODUCER.len())
+        .rev()
+        .find(|&n| text.ends_with(&INTRODUCER[..n]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn osc52(data: &str) -> String {
+        format!("\x1b]52;c;{}\x07", data)
+    }
+
+    #[test]
+    fn copies_and_strips_the_sequence() {
+        let mut filter = Osc52Filter::default();
+        let mut requests = Vec::new();
+        let text = format!("before{}after", osc52("aG9sYSBtdW5kbw=="));
+        assert_eq!(filter.filter(&text, &mut requests), "beforeafter");
+        assert_eq!(requests, vec![Request::Copy("hola mundo".into())]);
+
+        // ST terminator, no padding, no selection field
+        requests.clear();
+        let text = "\x1b]52;aG9sYQ\x1b\\$ ";
+        assert_eq!(filter.filter(text, &mut requests), "$ ");
+        assert_eq!(requests, vec![Request::Copy("hola".into())]);
+    }
+
+    #[test]
+    fn sequences_cut_between_flushes() {
+        let mut filter = Osc52Filter::default();
+        let mut requests = Vec::new();
+        let text = format!("ls\r\n{}$ ", osc52("bWFyY2Fkbw=="));
+        // Cut at every position: same output, same request
+        for cut in 1..text.len() {
+            let mut out = filter.filter(&text[..cut], &mut requests);
+            out.push_str(&filter.filter(&text[cut..], &mut requests));
+            assert_eq!(out, "ls\r\n$ ", "cut at {}", cut);
+        }
+        assert!(requests
+            .iter()
+            .all(|r| *r == Request::Copy("marcado".into())));
+        assert_eq!(requests.len(), text.len() - 1);
+
+        // Other escape sequences go through untouched
+        let colored = "\x1b]0;title\x07\x1b[1mbold\x1b[0m";
+        assert_eq!(filter.filter(colored, &mut requests), colored);
+    }
+
+    #[test]
+    fn reads_oversized_and_garbage_are_refused() {
+        let mut filter = Osc52Filter::default();
+        let mut requests = Vec::new();
+        let huge = "QUFB".repeat(MAX_BYTES / 3 + 10);
+        let text = format!("{}{}{}ok", osc52("?"), osc52(&huge), osc52("!!not base64"));
+        assert_eq!(filter.filter(&text, &mut requests), "ok");
+        assert_eq!(
+            requests,
+            vec![Request::Query, Request::TooLarge, Request::Invalid]
+        );
+        assert!(filter.sequence.is_none());
+        assert_eq!(Request::Query.refusal(), Some("read_denied"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Terminal output reduced to the plain text the user saw, for transcripts
+//! readable in a text editor: colors, titles and cursor movements go away,
+//! '\r' overwrites the line like on screen (progress bars keep their last
+//! state) and full-screen programs (less, vim, htop) become one line.
+//!
+//! Streaming: an escape sequence or a line cut between two reads is
+//! completed by the next `feed`.
+
+/// Written once in place of what a full-screen program drew
+pub const ALTERNATE_SCREEN_LINE: &str = "[full-screen application]";
+/// Widest line kept (wider than any real terminal): cursor moves stop at
+/// its last column, whatever the server's sequences ask, and text beyond
+/// it is dropped
+pub const MAX_LINE: usize = 4096;
+/// Longest CSI parameter string kept: past it the rest is ignored
+const MAX_PARAMS: usize = 64;
+
+#[derive(Debug, Default, PartialEq)]
+enum State {
+    #[default]
+    Text,
+    /// After ESC
+    Escape,
+    /// ESC + intermediate bytes (charset designation "ESC ( B"…)
+    EscapeIntermediate,
+    /// ESC [ params
+    Csi,
+    /// OSC / DCS / APC / PM string, up to BEL or ST (ESC \)
+    Str,
+    /// ESC inside a string: '\' ends it
+    StrEscape,
+}
+
+#[derive(Debug, Default)]
+pub struct PlainText {
+    state: State,
+    params: String,
+    line: Vec<char>,
+    cursor: usize,
+    alternate_screen: bool,
+}
+
+impl PlainText {
+    /// The lines completed by `text`, each ending in '\n'
+    pub fn feed(&mut self, text: &str) -> String {
+        let mut out = String::new();
+        for c in text.chars() {
+            match self.state {
+                State::Text => self.text(c, &mut out),
+                State::Escape => match c {
+                    '[' => {
+                        self.params.clear();
+                        self.state = State::Csi;
+                    }
+                    ']' | 'P' | '_' | '^' | 'X' => self.state = State::Str,
+                    '\u{20}'..='\u{2f}' => self.state = State::EscapeIntermediate,
+                    // ESC c (reset), ESC 7 / 8 (save/restore cursor)…
+                    _ => self.state = State::Text,
+                },
+                State::EscapeIntermediate => {
+                    if !('\u{20}'..='\u{2f}').contains(&c) {
+                        self.state = State::Text;
+                    }
+                }
+                State::Csi => match c {
+                    '\u{40}'..='\u{7e}' => {
+                        self.state = State::Text;
+                        self.csi(c, &mut out);
+                    }
+                    _ if self.params.len() < MAX_PARAMS => self.params.push(c),
+                    _ => {}
+                },
+                State::Str => match c {
+                    '\u{7}' => self.state = State::Text,
+                    '\u{1b}' => self.state = State::StrEscape,
+                    _ => {}
+                },
+                State::StrEscape => {
+                    self.state = if c == '\\' { State::Text } else { State::Str };
+                }
+            }
+        }
+        out
+    }
+
+    /// The line still open, left in place (a prompt being matched)
+    pub fn partial(&self) -> String {
+        self.partial_tail(MAX_LINE)
+    }
+
+    /// The last `max` chars of `partial`, without copying the rest
+    pub fn partial_tail(&self, max: usize) -> String {
+        let end = self
+            .line
+            .iter()
+            .rposition(|c| !c.is_whitespace())
+            .map_or(0, |i| i + 1);
+        self.line[end.saturating_sub(max)..end].iter().collect()
+    }
+
+    /// The line still open (a prompt): taken so it can be written before a
+    /// marker or at the end
+    pub fn take_partial(&mut self) -> Option<String> {
+        if self.line.is_empty() {
+            return None;
+        }
+        self.cursor = 0;
+        let line: String = self.line.drain(..).collect();
+        Some(line.trim_end().to_string())
+    }
+
+    fn text(&mut self, c: char, out: &mut String) {
+        match c {
+            '\u{1b}' => self.state = State::Escape,
+            _ if self.alternate_screen => {}
+            '\n' => self.end_line(out),
+            '\r' => self.cursor = 0,
+            '\u{8}' => self.cursor = self.cursor.saturating_sub(1),
+            '\t' => self.put('\t'),
+            c if c.is_control() => {}
+            c => self.put(c),
+        }
+    }
+
+    fn put(&mut self, c: char) {
+        if self.cursor >= MAX_LINE {
+            return;
+        }
+        if self.cursor < self.line.len() {
+            self.line[self.cursor] = c;
+        } else {
+            self.line.resize(self.cursor, ' ');
+            self.line.push(c);
+        }
+        self.cursor += 1;
+    }
+
+    fn end_line(&mut self, out: &mut String) {
+        let line: String = self.line.drain(..).collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+        self.cursor = 0;
+    }
+
+    fn csi(&mut self, command: char, out: &mut String) {
+        let private = self.params.starts_with('?');
+        let params: Vec<usize> = self
+            .params
+            .trim_start_matches('?')
+            .split(';')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let first = params.first().copied().unwrap_or(0);
+        let count = first.max(1);
+        match command {
+            'h' | 'l' if private && params.iter().any(|p| matches!(p, 47 | 1047 | 1049)) => {
+                let entering = command == 'h';
+                if entering && !self.alternate_screen {
+                    if let Some(line) = self.take_partial() {
+                        out.push_str(&line);
+                        out.push('\n');
+                    }
+                    out.push_str(ALTERNATE_SCREEN_LINE);
+                    out.push('\n');
+                }
+                self.alternate_screen = entering;
+                self.line.clear();
+                self.cursor = 0;
+            }
+            _ if self.alternate_screen => {}
+            // Erase in line: to the end, from the start, whole line
+            'K' => match first {
+                0 => self.line.truncate(self.cursor),
+                1 => {
+                    let end = (self.cursor + 1).min(self.line.len());
+                    self.line[..end].fill(' ');
+                }
+                _ => self.line.clear(),
+            },
+            'C' => self.cursor = self.cursor.saturating_add(count).min(MAX_LINE - 1),
+            'D' => self.cursor = self.cursor.saturating_sub(count),
+            'G' => self.cursor = (count - 1).min(MAX_LINE - 1),
+            // Colors (SGR) and everything else leave the text as it is
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colored_ls_loses_its_colors() {
+        let mut plain = PlainText::default();
+        let out =
+            plain.feed("\x1b[0m\x1b[01;34mbin\x1b[0m  notes.txt  \x1b[01;32mrun.sh\x1b[0m\r\n");
+        assert_eq!(out, "bin  notes.txt  run.sh\n");
+    }
+
+    #[test]
+    fn sequences_split_between_reads_are_completed() {
+        let mut plain = PlainText::default();
+        assert_eq!(plain.feed("\x1b[01;3"), "");
+        assert_eq!(plain.feed("1mred\x1b"), "");
+        assert_eq!(plain.feed("[0m\r"), "");
+        assert_eq!(plain.feed("\n"), "red\n");
+    }
+
+    #[test]
+    fn carriage_return_progress_keeps_the_last_state() {
+        let mut plain = PlainText::default();
+        let out = plain.feed(" 10% [#         ]\r 50% [#####     ]\r100% [##########]\r\n");
+        assert_eq!(out, "100% [##########]\n");
+        // Shorter text over a longer one + erase to end of line
+        assert_eq!(plain.feed("downloading\rdone\x1b[K\r\n"), "done\n");
+    }
+
+    #[test]
+    fn titles_and_prompts() {
+        let mut plain = PlainText::default();
+        assert_eq!(plain.feed("\x1b]0;ana@db: ~\x07ana@db:~$ "), "");
+        assert_eq!(plain.partial_tail(3), ":~$");
+        assert_eq!(plain.take_partial().as_deref(), Some("ana@db:~$"));
+        // OSC ended by ST (ESC \) instead of BEL
+        assert_eq!(plain.feed("\x1b]2;t\x1b\\ok\n"), "ok\n");
+        assert_eq!(plain.take_partial(), None);
+    }
+
+    #[test]
+    fn full_screen_programs_become_one_line() {
+        let mut plain = PlainText::default();
+        let out = plain.feed(
+            "$ less notes.txt\r\n\x1b[?1049h\x1b[22;0;0t\x1b[H\x1b[2Jline 1\r\nline 2\r\n\
+             \x1b[7m(END)\x1b[27m\x1b[?1049l\x1b[23;0;0t\r$ ",
+        );
+        assert_eq!(out, "$ less notes.txt\n[full-screen application]\n");
+        assert_eq!(plain.take_partial().as_deref(), Some("$"));
+    }
+
+    #[test]
+    fn cursor_moves_and_backspace() {
+        let mut plain = PlainText::default();
+        // Readline fixing a typo; a jump right, then back to column 1
+        assert_eq!(plain.feed("$ lss\x08\x1b[K\n"), "$ ls\n");
+        assert_eq!(plain.feed("a\x1b[3Cb\x1b[1Gc\n"), "c   b\n");
+    }
+
+    #[test]
+    fn huge_cursor_moves_stay_within_the_line() {
+        let mut plain = PlainText::default();
+        let out = plain.feed("a\x1b[999999999Cb\x1b[18446744073709551615Cc\x1b[0Gd\n");
+        // Both jumps stop at the last column: "c" lands over "b"
+        assert_eq!(out.chars().count(), MAX_LINE + 1);
+        assert!(out.starts_with("d ") && out.ends_with(" c\n"));
+        // A parameter string without its end doesn't grow either
+        plain.feed(&format!("\x1b[{}", "9".repeat(100_000)));
+        assert!(plain.params.len() <= MAX_PARAMS);
+    }
+}
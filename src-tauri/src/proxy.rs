@@ -0,0 +1,439 @@
+//! Outbound proxies for the first TCP hop of a connection: HTTP CONNECT
+//! (RFC 9110, Basic auth) and SOCKS5 (RFC 1928, user/password RFC 1929).
+//! Both run over an already open stream to the proxy and leave it as a raw
+//! tunnel to the target, ready for the SSH handshake.
+
+use crate::db::{ProxyConfig, ProxyType};
+use base64::{engine::general_purpose, Engine as _};
+use std::net::IpAddr;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// A proxy answering with more header bytes than this is not a proxy
+const MAX_HTTP_RESPONSE: usize = 16 * 1024;
+
+#[derive(Error, Debug)]
+pub enum ProxyError {
+    #[error("Invalid proxy settings: {0}")]
+    Config(String),
+    #[error("Proxy authentication failed: {0}")]
+    Auth(String),
+    #[error("Proxy refused the tunnel to {target}: {reason}")]
+    Rejected { target: String, reason: String },
+    #[error("Invalid proxy reply: {0}")]
+    Protocol(String),
+    #[error("Proxy connection failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ProxyConfig {
+    /// Reject settings that can't work before opening any connection
+    pub fn validate(&self) -> Result<(), ProxyError> {
+        if self.host.trim().is_empty() {
+            return Err(ProxyError::Config("proxy host is empty".to_string()));
+        }
+        if !(1..=65535).contains(&self.port) {
+            return Err(ProxyError::Config(format!(
+                "proxy port {} out of range",
+                self.port
+            )));
+        }
+        if self.proxy_type == ProxyType::Socks5 {
+            let too_long = |v: &Option<String>| v.as_ref().is_some_and(|v| v.len() > 255);
+            if too_long(&self.username) || too_long(&self.password) {
+                return Err(ProxyError::Config(
+                    "SOCKS5 username and password are limited to 255 bytes".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Username/password pair, when a username is set
+    fn credentials(&self) -> Option<(&str, &str)> {
+        let username = self.username.as_deref().filter(|u| !u.is_empty())?;
+        Some((username, self.password.as_deref().unwrap_or("")))
+    }
+}
+
+/// "host:port" as a request target: IPv6 literals go in brackets
+fn authority(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{}]:{}", ip, port),
+        _ => format!("{}:{}", host, port),
+    }
+}
+
+/// Ask the proxy on `stream` for a tunnel to `host`:`port`. The target name
+/// is resolved by the proxy, not locally.
+pub async fn open_tunnel<S>(
+    stream: &mut S,
+    proxy: &ProxyConfig,
+    host: &str,
+    port: u16,
+) -> Result<(), ProxyError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match proxy.proxy_type {
+        ProxyType::Http => http_connect(stream, host, port, proxy.credentials()).await,
+        ProxyType::Socks5 => socks5_connect(stream, host, port, proxy.credentials()).await,
+    }
+}
+
+async fn http_connect<S>(
+    stream: &mut S,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> Result<(), ProxyError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let target = authority(host, port);
+    let mut request = format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if let Some((username, password)) = credentials {
+        let token = general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    stream.flush().await?;
+
+    // Byte by byte up to the blank line: whatever follows is the SSH banner
+    let mut response = Vec::with_capacity(256);
+    while !response.ends_with(b"\r\n\r\n") {
+        if response.len() >= MAX_HTTP_RESPONSE {
+            return Err(ProxyError::Protocol("HTTP response too long".to_string()));
+        }
+        let byte = stream.read_u8().await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => {
+                ProxyError::Protocol("connection closed before the HTTP response".to_string())
+            }
+            _ => ProxyError::Io(e),
+        })?;
+        response.push(byte);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or_default().trim();
+    let mut parts = status_line.splitn(3, ' ');
+    let (version, code) = (parts.next().unwrap_or_default(), parts.next());
+    let code: u16 = match code.and_then(|c| c.parse().ok()) {
+        Some(code) if version.starts_with("HTTP/") => code,
+        _ => {
+            return Err(ProxyError::Protocol(format!(
+                "not an HTTP response: {}",
+                status_line
+            )))
+        }
+    };
+    match code {
+        200..=299 => Ok(()),
+        407 => Err(ProxyError::Auth(if credentials.is_some() {
+            format!("credentials rejected ({})", status_line)
+        } else {
+            format!(
+                "the proxy requires a username and password ({})",
+                status_line
+            )
+        })),
+        _ => Err(ProxyError::Rejected {
+            target,
+            reason: status_line.to_string(),
+        }),
+    }
+}
+
+fn socks5_reply_reason(code: u8) -> String {
+    match code {
+        0x01 => "general SOCKS server failure".to_string(),
+        0x02 => "connection not allowed by ruleset".to_string(),
+        0x03 => "network unreachable".to_string(),
+        0x04 => "host unreachable".to_string(),
+        0x05 => "connection refused".to_string(),
+        0x06 => "TTL expired".to_string(),
+        0x07 => "command not supported".to_string(),
+        0x08 => "address type not supported".to_string(),
+        other => format!("error code {}", other),
+    }
+}
+
+async fn socks5_connect<S>(
+    stream: &mut S,
+    host: &str,
+    port: u16,
+    credentials: Option<(&str, &str)>,
+) -> Result<(), ProxyError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Greeting: offer user/password only when we have a username
+    let methods: &[u8] = if credentials.is_some() {
+        &[0x00, 0x02]
+    } else {
+        &[0x00]
+    };
+    let mut greeting = vec![0x05, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).await?;
+
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await?;
+    if choice[0] != 0x05 {
+        return Err(ProxyError::Protocol(format!(
+            "not a SOCKS5 server (version {})",
+            choice[0]
+        )));
+    }
+    match (choice[1], credentials) {
+        (0x00, _) => {}
+        (0x02, Some((username, password))) => {
+            let mut auth = vec![0x01, username.len() as u8];
+            auth.extend_from_slice(username.as_bytes());
+            auth.push(password.len() as u8);
+            auth.extend_from_slice(password.as_bytes());
+            stream.write_all(&auth).await?;
+
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).await?;
+            if status[1] != 0x00 {
+                return Err(ProxyError::Auth("credentials rejected".to_string()));
+            }
+        }
+        (0xFF, None) => {
+            return Err(ProxyError::Auth(
+                "the proxy requires a username and password".to_string(),
+            ))
+        }
+        (0xFF, Some(_)) => {
+            return Err(ProxyError::Auth(
+                "no authentication method accepted".to_string(),
+            ))
+        }
+        (other, _) => {
+            return Err(ProxyError::Protocol(format!(
+                "unexpected SOCKS5 auth method {}",
+                other
+            )))
+        }
+    }
+
+    // CONNECT by address when it is one, by name otherwise
+    let mut request = vec![0x05, 0x01, 0x00];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.len() > 255 {
+                return Err(ProxyError::Config(format!("host name too long: {}", host)));
+            }
+            request.push(0x03);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != 0x05 {
+        return Err(ProxyError::Protocol(format!(
+            "not a SOCKS5 reply (version {})",
+            reply[0]
+        )));
+    }
+    if reply[1] != 0x00 {
+        return Err(ProxyError::Rejected {
+            target: authority(host, port),
+            reason: socks5_reply_reason(reply[1]),
+        });
+    }
+    // Skip the bound address and port the server reports
+    let address_len = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        other => {
+            return Err(ProxyError::Protocol(format!(
+                "unknown SOCKS5 address type {}",
+                other
+            )))
+        }
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::duplex;
+
+    fn proxy(proxy_type: ProxyType, username: Option<&str>) -> ProxyConfig {
+        ProxyConfig {
+            proxy_type,
+            host: "proxy.local".to_string(),
+            port: 3128,
+            username: username.map(str::to_string),
+            password: username.map(|_| "s3cret".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn http_connect_sends_basic_auth_and_stops_at_the_headers() {
+        let (mut client, mut server) = duplex(4096);
+        let server_task = tokio::spawn(async move {
+            let mut request = vec![0u8; 1024];
+            let n = server.read(&mut request).await.unwrap();
+            server
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\nSSH-2.0-test\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        });
+
+        open_tunnel(
+            &mut client,
+            &proxy(ProxyType::Http, Some("me")),
+            "2001:db8::1",
+            22,
+        )
+        .await
+        .unwrap();
+        let request = server_task.await.unwrap();
+        assert!(request.starts_with("CONNECT [2001:db8::1]:22 HTTP/1.1\r\n"));
+        // base64("me:s3cret")
+        assert!(request.contains("Proxy-Authorization: Basic bWU6czNjcmV0\r\n"));
+
+        // The bytes after the blank line are left for the SSH handshake
+        let mut banner = [0u8; 14];
+        client.read_exact(&mut banner).await.unwrap();
+        assert_eq!(&banner, b"SSH-2.0-test\r\n");
+    }
+
+    #[tokio::test]
+    async fn http_connect_distinguishes_auth_failures_from_rejections() {
+        for (reply, auth_error) in [
+            ("HTTP/1.1 407 Proxy Authentication Required\r\n\r\n", true),
+            ("HTTP/1.1 403 Forbidden\r\n\r\n", false),
+        ] {
+            let (mut client, mut server) = duplex(4096);
+            tokio::spawn(async move {
+                let mut request = vec![0u8; 1024];
+                let _ = server.read(&mut request).await.unwrap();
+                server.write_all(reply.as_bytes()).await.unwrap();
+            });
+            let err = open_tunnel(
+                &mut client,
+                &proxy(ProxyType::Http, None),
+                "db.internal",
+                22,
+            )
+            .await
+            .unwrap_err();
+            match err {
+                ProxyError::Auth(msg) => {
+                    assert!(auth_error);
+                    assert!(msg.contains("requires a username"));
+                }
+                ProxyError::Rejected { target, reason } => {
+                    assert!(!auth_error);
+                    assert_eq!(target, "db.internal:22");
+                    assert_eq!(reason, "HTTP/1.1 403 Forbidden");
+                }
+                other => panic!("unexpected error: {other}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn socks5_authenticates_and_connects_by_name() {
+        let (mut client, mut server) = duplex(4096);
+        let server_task = tokio::spawn(async move {
+            let mut greeting = [0u8; 4];
+            server.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(greeting, [0x05, 0x02, 0x00, 0x02]);
+            server.write_all(&[0x05, 0x02]).await.unwrap();
+
+            let mut auth = [0u8; 1 + 1 + 2 + 1 + 6];
+            server.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x02me\x06s3cret");
+            server.write_all(&[0x01, 0x00]).await.unwrap();
+
+            let mut request = [0u8; 4 + 1 + 11 + 2];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..5], &[0x05, 0x01, 0x00, 0x03, 11]);
+            assert_eq!(&request[5..16], b"db.internal");
+            assert_eq!(&request[16..], &22u16.to_be_bytes());
+            server
+                .write_all(&[0x05, 0x00, 0x00, 0x01, 10, 0, 0, 1, 0x1f, 0x90])
+                .await
+                .unwrap();
+        });
+
+        open_tunnel(
+            &mut client,
+            &proxy(ProxyType::Socks5, Some("me")),
+            "db.internal",
+            22,
+        )
+        .await
+        .unwrap();
+        server_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn socks5_reports_auth_failures_and_refusals() {
+        let (mut client, mut server) = duplex(4096);
+        tokio::spawn(async move {
+            let mut greeting = [0u8; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            server.write_all(&[0x05, 0xFF]).await.unwrap();
+        });
+        let err = open_tunnel(&mut client, &proxy(ProxyType::Socks5, None), "10.0.0.5", 22)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProxyError::Auth(_)));
+
+        let (mut client, mut server) = duplex(4096);
+        tokio::spawn(async move {
+            let mut greeting = [0u8; 3];
+            server.read_exact(&mut greeting).await.unwrap();
+            server.write_all(&[0x05, 0x00]).await.unwrap();
+            let mut request = [0u8; 10];
+            server.read_exact(&mut request).await.unwrap();
+            assert_eq!(request[3], 0x01);
+            server
+                .write_all(&[0x05, 0x05, 0x00, 0x01, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+        });
+        let err = open_tunnel(&mut client, &proxy(ProxyType::Socks5, None), "10.0.0.5", 22)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Proxy refused the tunnel to 10.0.0.5:22: connection refused"
+        );
+    }
+
+    #[test]
+    fn validate_rejects_unusable_settings() {
+        let mut config = proxy(ProxyType::Http, None);
+        config.validate().unwrap();
+        config.host = " ".to_string();
+        assert!(config.validate().is_err());
+        let mut config = proxy(ProxyType::Socks5, Some("me"));
+        config.password = Some("x".repeat(256));
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! The last output of a channel, kept by the backend so a reloaded webview
+//! can repaint its terminals (`ssh_get_buffer`) before the live
+//! `pty_output` events go on. Bounded: the oldest bytes go first.
+//!
+//! Appended at every flush of the reader, the text already decoded: one
+//! copy into the ring, nothing allocated once it is full.
+
+use std::collections::VecDeque;
+
+/// KiB kept per channel unless the connect says otherwise
+pub const DEFAULT_KB: u32 = 512;
+/// Upper bound of `output_buffer_kb` (16 MiB)
+pub const MAX_KB: u32 = 16 * 1024;
+
+#[derive(Debug)]
+pub struct Scrollback {
+    bytes: VecDeque<u8>,
+    capacity: usize,
+    /// Bytes were dropped from the front: the first line is cut
+    wrapped: bool,
+}
+
+impl Default for Scrollback {
+    fn default() -> Self {
+        Scrollback::new(Some(DEFAULT_KB))
+    }
+}
+
+impl Scrollback {
+    /// `kb`: None = `DEFAULT_KB`, 0 = keep nothing
+    pub fn new(kb: Option<u32>) -> Self {
+        let capacity = kb.unwrap_or(DEFAULT_KB).min(MAX_KB) as usize * 1024;
+        Scrollback {
+            bytes: VecDeque::new(),
+            capacity,
+            wrapped: false,
+        }
+    }
+
+    pub fn push(&mut self, text: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        let data = text.as_bytes();
+        // Larger than the whole ring: only its tail can stay
+        let data = &data[data.len().saturating_sub(self.capacity)..];
+        let overflow = (self.bytes.len() + data.len()).saturating_sub(self.capacity);
+        if overflow > 0 {
+            self.bytes.drain(..overflow);
+            self.wrapped = true;
+        }
+        if text.len() > data.len() {
+            self.wrapped = true;
+        }
+        self.bytes.extend(data);
+    }
+
+    /// What is kept, as text. After a wrap it starts at the first complete
+    /// line: a cut escape sequence would paint garbage.
+    pub fn snapshot(&mut self) -> String {
+        let bytes = self.bytes.make_contiguous();
+        let start = if self.wrapped {
+            match bytes.iter().position(|&b| b == b'\n') {
+                Some(newline) => newline + 1,
+                // One huge line: at least skip a cut UTF-8 sequence
+                None => bytes
+                    .iter()
+                    .position(|&b| b & 0xC0 != 0x80)
+                    .unwrap_or(bytes.len()),
+            }
+        } else {
+            0
+        };
+        String::from_utf8_lossy(&bytes[start..]).into_owned()
+    }
+
+    /// The channel closed: give the memory back
+    pub fn clear(&mut self) {
+        self.bytes = VecDeque::new();
+        self.wrapped = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_everything_until_full() {
+        let mut ring = Scrollback::new(Some(1));
+        ring.push("$ ls\r\n");
+        ring.push("a  b  c\r\n$ ");
+        assert_eq!(ring.snapshot(), "$ ls\r\na  b  c\r\n$ ");
+        ring.clear();
+        assert_eq!(ring.bytes.len(), 0);
+        assert_eq!(ring.snapshot(), "");
+    }
+
+    #[test]
+    fn drops_the_oldest_bytes_and_starts_on_a_line() {
+        let mut ring = Scrollback::new(Some(1));
+        for i in 0..200 {
+            ring.push(&format!("\x1b[32mline {:03}\x1b[0m\r\n", i));
+        }
+        assert!(ring.bytes.len() <= 1024);
+        let text = ring.snapshot();
+        assert!(text.starts_with("\x1b[32mline "));
+        assert!(text.ends_with("line 199\x1b[0m\r\n"));
+
+        // A single push larger than the ring, multi-byte chars on the cut
+        ring.push(&"ñ".repeat(1000));
+        assert_eq!(ring.bytes.len(), 1024);
+        assert_eq!(ring.snapshot(), "ñ".repeat(512));
+    }
+
+    #[test]
+    fn zero_keeps_nothing_and_sizes_are_capped() {
+        let mut ring = Scrollback::new(Some(0));
+        ring.push("output");
+        assert_eq!(ring.bytes.len(), 0);
+        assert_eq!(
+            Scrollback::new(Some(u32::MAX)).capacity,
+            MAX_KB as usize * 1024
+        );
+        assert_eq!(Scrollback::default().capacity, DEFAULT_KB as usize * 1024);
+    }
+}
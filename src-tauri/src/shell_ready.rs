@@ -0,0 +1,212 @@
+//! When the remote shell is at its prompt (MOTD and login scripts done):
+//! the start of the startup-command / restore-directory features. One
+//! detection per channel, then the reader drops the detector, so nothing
+//! runs on the output afterwards.
+//!
+//! With a session prompt pattern the open line is matched at every flush
+//! (never per byte). Without one, the output going quiet with a partial
+//! line on screen is the prompt: a MOTD ends in a newline, a prompt doesn't,
+//! so a pause inside a long MOTD doesn't count. Either way the detection
+//! gives up waiting after the timeout and fires with what is on the line.
+
+use crate::plain_text::PlainText;
+use regex::Regex;
+use std::time::{Duration, Instant};
+
+// Defaults of ConnectParams shellReadyQuietMs / shellReadyTimeoutMs
+pub const DEFAULT_QUIET: Duration = Duration::from_millis(800);
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+const MIN_QUIET: Duration = Duration::from_millis(100);
+const MAX_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Quiet period that means "at the prompt" and how long to wait at most
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timings {
+    pub quiet: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Timings {
+            quiet: DEFAULT_QUIET,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl Timings {
+    /// From the frontend's milliseconds (clamped; None = default)
+    pub fn from_millis(quiet_ms: Option<u64>, timeout_ms: Option<u64>) -> Self {
+        let quiet = quiet_ms.map_or(DEFAULT_QUIET, |ms| {
+            Duration::from_millis(ms).clamp(MIN_QUIET, MAX_TIMEOUT)
+        });
+        let timeout = timeout_ms.map_or(DEFAULT_TIMEOUT, |ms| {
+            Duration::from_millis(ms).clamp(quiet, MAX_TIMEOUT)
+        });
+        Timings { quiet, timeout }
+    }
+}
+
+/// The prompt was found
+#[derive(Debug, PartialEq)]
+pub struct Ready {
+    /// The prompt line, without colors or escape sequences
+    pub prompt: String,
+    /// "pattern", "quiet" or "timeout"
+    pub trigger: &'static str,
+}
+
+pub struct ShellReady {
+    screen: PlainText,
+    pattern: Option<Regex>,
+    quiet: Duration,
+    give_up: Instant,
+    last_output: Option<Instant>,
+    /// The quiet period after the last output already passed on an empty
+    /// line: wait for more output (or the timeout)
+    quiet_missed: bool,
+}
+
+impl ShellReady {
+    /// A `pattern` that doesn't compile is an error (the caller falls back
+    /// to the quiet period)
+    pub fn new(
+        pattern: Option<&str>,
+        timings: Timings,
+        now: Instant,
+    ) -> Result<Self, regex::Error> {
+        let pattern = match pattern.map(str::trim).filter(|p| !p.is_empty()) {
+            Some(pattern) => Some(Regex::new(pattern)?),
+            None => None,
+        };
+        Ok(ShellReady {
+            screen: PlainText::default(),
+            pattern,
+            quiet: timings.quiet,
+            give_up: now + timings.timeout,
+            last_output: None,
+            quiet_missed: false,
+        })
+    }
+
+    /// Output shown by the terminal (a whole flush)
+    pub fn output(&mut self, text: &str, now: Instant) -> Option<Ready> {
+        self.screen.feed(text);
+        self.last_output = Some(now);
+        self.quiet_missed = false;
+        let pattern = self.pattern.as_ref()?;
+        let line = self.screen.partial();
+        pattern.is_match(&line).then_some(Ready {
+            prompt: line,
+            trigger: "pattern",
+        })
+    }
+
+    /// When `poll` has something to decide
+    pub fn deadline(&self) -> Instant {
+        match self.last_output {
+            Some(last) if self.pattern.is_none() && !self.quiet_missed => {
+                (last + self.quiet).min(self.give_up)
+            }
+            _ => self.give_up,
+        }
+    }
+
+    /// Called at `deadline`
+    pub fn poll(&mut self, now: Instant) -> Option<Ready> {
+        if now >= self.give_up {
+            return Some(Ready {
+                prompt: self.screen.partial(),
+                trigger: "timeout",
+            });
+        }
+        let last = self.last_output?;
+        if self.pattern.is_some() || now < last + self.quiet {
+            return None;
+        }
+        let line = self.screen.partial();
+        if line.is_empty() {
+            self.quiet_missed = true;
+            return None;
+        }
+        Some(Ready {
+            prompt: line,
+            trigger: "quiet",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUIET: Duration = Duration::from_millis(800);
+
+    fn detector(pattern: Option<&str>, now: Instant) -> ShellReady {
+        ShellReady::new(pattern, Timings::default(), now).unwrap()
+    }
+
+    #[test]
+    fn quiet_period_after_a_multi_line_motd() {
+        let start = Instant::now();
+        let mut ready = detector(None, start);
+        assert_eq!(ready.deadline(), start + Duration::from_secs(15));
+
+        // A MOTD that pauses on a complete line is not the prompt
+        assert_eq!(
+            ready.output("Welcome to Ubuntu\r\n\r\n * Docs: x\r\n", start),
+            None
+        );
+        let pause = start + QUIET;
+        assert_eq!(ready.deadline(), pause);
+        assert_eq!(ready.poll(pause), None);
+        assert_eq!(ready.deadline(), start + Duration::from_secs(15));
+
+        let later = pause + Duration::from_secs(2);
+        ready.output("Last login: Mon\r\n\x1b[01;32mana@db\x1b[0m:~$ ", later);
+        assert_eq!(ready.poll(later + QUIET / 2), None);
+        assert_eq!(
+            ready.poll(later + QUIET),
+            Some(Ready {
+                prompt: "ana@db:~$".into(),
+                trigger: "quiet"
+            })
+        );
+    }
+
+    #[test]
+    fn pattern_matches_the_open_line() {
+        let now = Instant::now();
+        let mut ready = detector(Some(r"[$#]\s*$"), now);
+        // A pause with a partial line doesn't count with a pattern
+        assert_eq!(ready.output("Loading profile...", now), None);
+        assert_eq!(ready.deadline(), now + Duration::from_secs(15));
+        assert_eq!(ready.poll(now + QUIET * 2), None);
+
+        let found = ready.output("\r\nroot@web:/srv# ", now).unwrap();
+        assert_eq!(found.prompt, "root@web:/srv#");
+        assert_eq!(found.trigger, "pattern");
+    }
+
+    #[test]
+    fn gives_up_after_the_timeout() {
+        let now = Instant::now();
+        let mut ready = detector(Some("never"), now);
+        ready.output("> ", now);
+        let found = ready.poll(now + Duration::from_secs(15)).unwrap();
+        assert_eq!(found.trigger, "timeout");
+        assert_eq!(found.prompt, ">");
+        assert!(ShellReady::new(Some("(unclosed"), Timings::default(), now).is_err());
+    }
+
+    #[test]
+    fn timings_are_clamped() {
+        assert_eq!(Timings::from_millis(None, None), Timings::default());
+        let extreme = Timings::from_millis(Some(10), Some(1_000_000));
+        assert_eq!((extreme.quiet, extreme.timeout), (MIN_QUIET, MAX_TIMEOUT));
+        // The timeout is never shorter than the quiet period
+        let inverted = Timings::from_millis(Some(5000), Some(1000));
+        assert_eq!(inverted.timeout, Duration::from_secs(5));
+    }
+}
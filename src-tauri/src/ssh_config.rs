@@ -0,0 +1,421 @@
+//! Minimal OpenSSH client config (~/.ssh/config) reader: `Host` blocks with
+//! wildcard/negated patterns and the options the app can use (HostName, User,
+//! Port, IdentityFile, ProxyJump). Like OpenSSH, the first value obtained for
+//! each option wins, so specific blocks must come before `Host *`.
+//! `Match` blocks and `Include` are not supported (skipped).
+
+use crate::db::{JumpHop, Session};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// One `Host` block (options before the first `Host` line form a block
+/// matching every host)
+#[derive(Debug, Clone, Default)]
+struct HostBlock {
+    patterns: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+/// The options resolved for one host alias
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigHost {
+    pub alias: String,
+    pub host_name: Option<String>,
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SshConfig {
+    blocks: Vec<HostBlock>,
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".ssh").join("config"))
+}
+
+/// Split "Key value", "Key=value" or "Key = value"; keys are case-insensitive
+fn split_line(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let key_end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let key = line[..key_end].to_ascii_lowercase();
+    let rest = line[key_end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    Some((key, rest.to_string()))
+}
+
+/// Whitespace-separated words, honoring double quotes
+fn words(value: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    out.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+/// OpenSSH glob: `*` any run of characters, `?` exactly one (case-insensitive)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let t: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+impl HostBlock {
+    /// A `!pattern` match excludes the host even if another pattern matches
+    fn matches(&self, host: &str) -> bool {
+        let mut matched = false;
+        for pattern in &self.patterns {
+            if let Some(negated) = pattern.strip_prefix('!') {
+                if glob_match(negated, host) {
+                    return false;
+                }
+            } else if glob_match(pattern, host) {
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+impl SshConfig {
+    pub fn parse(text: &str) -> Self {
+        let mut blocks = vec![HostBlock {
+            patterns: vec!["*".to_string()],
+            options: Vec::new(),
+        }];
+        // Inside a Match block: options are ignored until the next Host
+        let mut skipping = false;
+        for line in text.lines() {
+            let Some((key, value)) = split_line(line) else {
+                continue;
+            };
+            match key.as_str() {
+                "host" => {
+                    skipping = false;
+                    blocks.push(HostBlock {
+                        patterns: words(&value),
+                        options: Vec::new(),
+                    });
+                }
+                "match" => skipping = true,
+                _ if skipping => {}
+                _ => {
+                    let value = words(&value).join(" ");
+                    if let Some(block) = blocks.last_mut() {
+                        block.options.push((key, value));
+                    }
+                }
+            }
+        }
+        SshConfig { blocks }
+    }
+
+    /// Parse ~/.ssh/config; a missing or unreadable file is an empty config
+    pub fn load() -> Self {
+        config_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Resolve `alias` the OpenSSH way: walk every matching block in file
+    /// order and keep the first value seen for each option
+    pub fn resolve(&self, alias: &str) -> SshConfigHost {
+        let mut host = SshConfigHost {
+            alias: alias.to_string(),
+            ..Default::default()
+        };
+        for block in self.blocks.iter().filter(|b| b.matches(alias)) {
+            for (key, value) in &block.options {
+                let slot = match key.as_str() {
+                    "hostname" => &mut host.host_name,
+                    "user" => &mut host.user,
+                    "identityfile" => &mut host.identity_file,
+                    "proxyjump" => &mut host.proxy_jump,
+                    "port" => {
+                        if host.port.is_none() {
+                            host.port = value.parse().ok();
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if slot.is_none() {
+                    *slot = Some(value.clone());
+                }
+            }
+        }
+        // %h in HostName stands for the alias typed by the user
+        if let Some(name) = host.host_name.as_mut() {
+            *name = name.replace("%h", alias);
+        }
+        host
+    }
+
+    /// Every concrete alias (patterns without wildcards or negation), in
+    /// file order, with its resolved options
+    pub fn hosts(&self) -> Vec<SshConfigHost> {
+        let mut seen = std::collections::HashSet::new();
+        self.blocks
+            .iter()
+            .flat_map(|b| b.patterns.iter())
+            .filter(|p| !p.contains(['*', '?', '!']))
+            .filter(|p| seen.insert(p.to_lowercase()))
+            .map(|alias| self.resolve(alias))
+            .collect()
+    }
+
+    /// Fill the connection fields `session` leaves empty from the config
+    /// entry of its host: HostName, User, Port (when the session keeps the
+    /// default 22), IdentityFile and ProxyJump (when it has no jump hops)
+    pub fn apply(&self, session: &mut Session) {
+        let resolved = self.resolve(session.host.trim());
+
+        if let Some(name) = resolved.host_name {
+            session.host = name;
+        }
+        if session.username.trim().is_empty() {
+            if let Some(user) = resolved.user {
+                session.username = user;
+            }
+        }
+        if session.port <= 0 || session.port == 22 {
+            if let Some(port) = resolved.port {
+                session.port = port as i32;
+            }
+        }
+        let has_key = [&session.private_key_path, &session.private_key_data]
+            .iter()
+            .any(|v| v.as_deref().is_some_and(|s| !s.trim().is_empty()));
+        if !has_key {
+            if let Some(identity) = resolved.identity_file {
+                session.private_key_path = Some(identity);
+                // Nothing to send by password: use the configured key
+                let has_password = session.password.as_deref().is_some_and(|p| !p.is_empty());
+                if session.auth_method == "password" && !has_password {
+                    session.auth_method = "key".to_string();
+                }
+            }
+        }
+        if session.jump_hops.is_empty() {
+            if let Some(jumps) = resolved.proxy_jump {
+                session.jump_hops = self.proxy_jump_hops(&jumps, &session.username);
+            }
+        }
+    }
+
+    /// ProxyJump "[user@]host[:port],..." as inline hops, each one resolved
+    /// through the config too. Hops authenticate with their IdentityFile or,
+    /// failing that, the ssh-agent.
+    fn proxy_jump_hops(&self, spec: &str, default_user: &str) -> Vec<JumpHop> {
+        if spec.eq_ignore_ascii_case("none") {
+            return Vec::new();
+        }
+        spec.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|jump| {
+                let (user, host_port) = match jump.rsplit_once('@') {
+                    Some((u, rest)) => (Some(u.to_string()), rest),
+                    None => (None, jump),
+                };
+                let (alias, port) = match host_port.rsplit_once(':') {
+                    Some((h, p)) if p.parse::<u16>().is_ok() => (h, p.parse::<u16>().ok()),
+                    _ => (host_port, None),
+                };
+                let resolved = self.resolve(alias);
+                JumpHop {
+                    name: Some(alias.to_string()),
+                    ref_session_id: None,
+                    host: resolved.host_name.unwrap_or_else(|| alias.to_string()),
+                    port: port.or(resolved.port).unwrap_or(22) as i32,
+                    username: user
+                        .or(resolved.user)
+                        .unwrap_or_else(|| default_user.to_string()),
+                    auth_method: if resolved.identity_file.is_some() {
+                        "key".to_string()
+                    } else {
+                        "agent".to_string()
+                    },
+                    password: None,
+                    private_key_path: resolved.identity_file,
+                    private_key_passphrase: None,
+                    private_key_data: None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# global defaults apply to every host
+ServerAliveInterval 30
+
+Host web1
+    HostName 10.0.0.11
+    User deploy
+    Port 2222
+
+Host *.prod !db.prod
+    User ops
+    IdentityFile ~/.ssh/prod_ed25519
+    ProxyJump bastion
+
+Host bastion
+    HostName bastion.example.com
+    User jump
+    IdentityFile=\"~/.ssh/jump key\"
+
+Match host legacy
+    User ignored
+
+Host *
+    User fallback
+    Port 2200
+";
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("*.prod", "api.prod"));
+        assert!(glob_match("web?", "WEB1"));
+        assert!(!glob_match("web?", "web10"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYc"));
+        assert!(!glob_match("a*b*c", "aXXbY"));
+    }
+
+    #[test]
+    fn first_match_wins_across_blocks() {
+        let config = SshConfig::parse(CONFIG);
+
+        let web = config.resolve("web1");
+        assert_eq!(web.host_name.as_deref(), Some("10.0.0.11"));
+        assert_eq!(web.user.as_deref(), Some("deploy"));
+        assert_eq!(web.port, Some(2222));
+
+        // Wildcard block first, then `Host *` only fills what is left
+        let api = config.resolve("api.prod");
+        assert_eq!(api.host_name, None);
+        assert_eq!(api.user.as_deref(), Some("ops"));
+        assert_eq!(api.port, Some(2200));
+        assert_eq!(api.proxy_jump.as_deref(), Some("bastion"));
+
+        // Negated pattern excludes the host from the *.prod block
+        assert_eq!(config.resolve("db.prod").user.as_deref(), Some("fallback"));
+        // Match blocks are skipped
+        assert_eq!(config.resolve("legacy").user.as_deref(), Some("fallback"));
+        assert_eq!(
+            config.resolve("bastion").identity_file.as_deref(),
+            Some("~/.ssh/jump key")
+        );
+    }
+
+    #[test]
+    fn hosts_lists_concrete_aliases_only() {
+        let aliases: Vec<String> = SshConfig::parse(CONFIG)
+            .hosts()
+            .into_iter()
+            .map(|h| h.alias)
+            .collect();
+        assert_eq!(aliases, vec!["web1", "bastion"]);
+    }
+
+    fn session(host: &str) -> Session {
+        Session {
+            name: host.into(),
+            host: host.into(),
+            ..Session::test_default()
+        }
+    }
+
+    #[test]
+    fn apply_fills_only_missing_fields_and_proxy_jump() {
+        let config = SshConfig::parse(CONFIG);
+
+        let mut s = session("api.prod");
+        config.apply(&mut s);
+        assert_eq!(s.host, "api.prod");
+        assert_eq!(s.username, "ops");
+        assert_eq!(s.port, 2200);
+        assert_eq!(s.auth_method, "key");
+        assert_eq!(s.private_key_path.as_deref(), Some("~/.ssh/prod_ed25519"));
+        assert_eq!(s.jump_hops.len(), 1);
+        let hop = &s.jump_hops[0];
+        assert_eq!(hop.host, "bastion.example.com");
+        assert_eq!(hop.username, "jump");
+        assert_eq!(hop.port, 2200);
+        assert_eq!(hop.auth_method, "key");
+
+        // Explicit values are kept
+        let mut s = session("web1");
+        s.username = "me".into();
+        s.port = 2022;
+        s.password = Some("pw".into());
+        config.apply(&mut s);
+        assert_eq!(s.host, "10.0.0.11");
+        assert_eq!(s.username, "me");
+        assert_eq!(s.port, 2022);
+        assert_eq!(s.auth_method, "password");
+    }
+
+    #[test]
+    fn proxy_jump_chain_with_user_and_port() {
+        let hops = SshConfig::parse("").proxy_jump_hops("alice@gw1:2201, gw2", "bob");
+        assert_eq!(hops.len(), 2);
+        assert_eq!(
+            (
+                hops[0].username.as_str(),
+                hops[0].host.as_str(),
+                hops[0].port
+            ),
+            ("alice", "gw1", 2201)
+        );
+        assert_eq!((hops[1].username.as_str(), hops[1].port), ("bob", 22));
+        assert_eq!(hops[1].auth_method, "agent");
+        assert!(SshConfig::parse("")
+            .proxy_jump_hops("none", "bob")
+            .is_empty());
+    }
+}
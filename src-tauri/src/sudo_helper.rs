@@ -0,0 +1,236 @@
+//! The session password typed at sudo's prompt (`autoSudoPassword`). The
+//! prompt is matched on the open line of the output after `PlainText`, at
+//! every flush: sudo turns echo off and waits there, nothing typed yet.
+//!
+//! Guard rails, since a password is going out: never without a stored
+//! password, at most `MAX_PER_MINUTE` answers, and a prompt coming back
+//! right after an answer means the stored password is wrong: the helper
+//! stops for the channel instead of spending the user's sudo attempts.
+
+use crate::plain_text::PlainText;
+use regex::Regex;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+pub const MAX_PER_MINUTE: usize = 3;
+/// sudo waits ~2s after a wrong password before asking again
+const REJECTED_WITHIN: Duration = Duration::from_secs(5);
+const MINUTE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, PartialEq)]
+pub enum Refused {
+    /// The session has no stored password (key or agent auth, or typed at
+    /// the prompt)
+    NoPassword,
+    InvalidPattern(String),
+}
+
+impl Refused {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Refused::NoPassword => "no_password",
+            Refused::InvalidPattern(_) => "invalid_pattern",
+        }
+    }
+}
+
+/// What to do about a prompt just seen
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    /// Type the password (and Enter)
+    Answer { prompt: String },
+    /// Too many answers in the last minute: left to the user
+    RateLimited { prompt: String },
+    /// The last answer was refused: left to the user from now on
+    Rejected { prompt: String },
+}
+
+/// sudo's own prompt in English for the session's user: "[sudo] password
+/// for ana: ". Someone else's (after `su bob`) doesn't take this password
+fn default_prompt(username: &str) -> String {
+    format!(r"^\[sudo\] password for {}:$", regex::escape(username))
+}
+
+pub struct SudoHelper {
+    screen: PlainText,
+    pattern: Regex,
+    password: Zeroizing<String>,
+    answers: VecDeque<Instant>,
+    stopped: bool,
+}
+
+impl SudoHelper {
+    /// `pattern`: None or blank = sudo's English prompt for `username`
+    pub fn new(
+        pattern: Option<&str>,
+        username: &str,
+        password: Option<&str>,
+    ) -> Result<Self, Refused> {
+        let password = password
+            .filter(|p| !p.is_empty())
+            .ok_or(Refused::NoPassword)?;
+        let pattern = match pattern.map(str::trim).filter(|p| !p.is_empty()) {
+            Some(pattern) => pattern.to_string(),
+            None => default_prompt(username),
+        };
+        let pattern = Regex::new(&pattern).map_err(|e| Refused::InvalidPattern(e.to_string()))?;
+        Ok(SudoHelper {
+            screen: PlainText::default(),
+            pattern,
+            password: Zeroizing::new(password.to_string()),
+            answers: VecDeque::new(),
+            stopped: false,
+        })
+    }
+
+    /// Output shown by the terminal (a whole flush)
+    pub fn output(&mut self, text: &str, now: Instant) -> Option<Action> {
+        self.screen.feed(text);
+        if self.stopped {
+            return None;
+        }
+        let line = self.screen.partial();
+        if !self.pattern.is_match(&line) {
+            return None;
+        }
+        // Seen once: the next flush with the same line open is not a new prompt
+        let prompt = self.screen.take_partial().unwrap_or_default();
+
+        if let Some(&last) = self.answers.back() {
+            if now.duration_since(last) < REJECTED_WITHIN {
+                self.stopped = true;
+                return Some(Action::Rejected { prompt });
+            }
+        }
+        while let Some(&first) = self.answers.front() {
+            if now.duration_since(first) < MINUTE {
+                break;
+            }
+            self.answers.pop_front();
+        }
+        if self.answers.len() >= MAX_PER_MINUTE {
+            return Some(Action::RateLimited { prompt });
+        }
+        self.answers.push_back(now);
+        Some(Action::Answer { prompt })
+    }
+
+    /// The password and Enter, as typed
+    pub fn answer(&self) -> Zeroizing<String> {
+        Zeroizing::new(format!("{}\r", self.password.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROMPT: &str = "[sudo] password for ana: ";
+
+    #[test]
+    fn answers_the_prompt_once_per_appearance() {
+        let now = Instant::now();
+        let mut sudo = SudoHelper::new(None, "ana", Some("s3cret")).unwrap();
+        assert_eq!(sudo.output("$ sudo ls\r\n", now), None);
+        assert_eq!(
+            sudo.output(&format!("\x1b[1m{}\x1b[0m", PROMPT), now),
+            Some(Action::Answer {
+                prompt: "[sudo] password for ana:".into()
+            })
+        );
+        assert_eq!(sudo.answer().as_str(), "s3cret\r");
+        // Still on screen in the next flush: not asked again
+        assert_eq!(sudo.output("", now), None);
+        // Mentioned in a completed line: not a prompt
+        assert_eq!(
+            sudo.output("\r\necho '[sudo] password for x:'\r\n", now),
+            None
+        );
+    }
+
+    #[test]
+    fn the_default_prompt_is_only_the_session_users() {
+        let now = Instant::now();
+        let mut sudo = SudoHelper::new(None, "ana", Some("s3cret")).unwrap();
+        // After `su bob`: bob's password is asked, not ana's
+        assert_eq!(sudo.output("[sudo] password for bob: ", now), None);
+        assert_eq!(sudo.output("\r\n[sudo] password for anabel: ", now), None);
+        assert!(matches!(
+            sudo.output(&format!("\r\n{}", PROMPT), now),
+            Some(Action::Answer { .. })
+        ));
+
+        // Regex characters in a user name are literal
+        let mut sudo = SudoHelper::new(None, "a.b", Some("s3cret")).unwrap();
+        assert_eq!(sudo.output("[sudo] password for axb: ", now), None);
+        assert!(matches!(
+            sudo.output("\r\n[sudo] password for a.b: ", now),
+            Some(Action::Answer { .. })
+        ));
+    }
+
+    #[test]
+    fn refuses_without_a_password_or_with_a_bad_pattern() {
+        assert_eq!(
+            SudoHelper::new(None, "ana", None).err(),
+            Some(Refused::NoPassword)
+        );
+        assert_eq!(
+            SudoHelper::new(None, "ana", Some("")).err(),
+            Some(Refused::NoPassword)
+        );
+        let bad = SudoHelper::new(Some("(["), "ana", Some("pw"))
+            .err()
+            .unwrap();
+        assert_eq!(bad.reason(), "invalid_pattern");
+
+        // Another locale
+        let now = Instant::now();
+        let mut sudo =
+            SudoHelper::new(Some(r"^\[sudo\] contraseña para \S+:$"), "ana", Some("pw")).unwrap();
+        assert_eq!(sudo.output(PROMPT, now), None);
+        assert!(matches!(
+            sudo.output("\r\n[sudo] contraseña para ana: ", now),
+            Some(Action::Answer { .. })
+        ));
+    }
+
+    #[test]
+    fn a_prompt_right_after_an_answer_stops_the_helper() {
+        let now = Instant::now();
+        let mut sudo = SudoHelper::new(None, "ana", Some("wrong")).unwrap();
+        assert!(matches!(
+            sudo.output(PROMPT, now),
+            Some(Action::Answer { .. })
+        ));
+        let retry = now + Duration::from_secs(2);
+        let again = format!("\r\nSorry, try again.\r\n{}", PROMPT);
+        assert!(matches!(
+            sudo.output(&again, retry),
+            Some(Action::Rejected { .. })
+        ));
+        let later = now + Duration::from_secs(600);
+        assert_eq!(sudo.output(&format!("\r\n{}", PROMPT), later), None);
+    }
+
+    #[test]
+    fn at_most_n_answers_per_minute() {
+        let start = Instant::now();
+        let mut sudo = SudoHelper::new(None, "ana", Some("pw")).unwrap();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        for i in 0..MAX_PER_MINUTE as u64 {
+            let action = sudo.output(&format!("ok\r\n{}", PROMPT), at(i * 10));
+            assert!(matches!(action, Some(Action::Answer { .. })));
+        }
+        assert!(matches!(
+            sudo.output(&format!("ok\r\n{}", PROMPT), at(40)),
+            Some(Action::RateLimited { .. })
+        ));
+        // The first answer is more than a minute old again
+        assert!(matches!(
+            sudo.output(&format!("ok\r\n{}", PROMPT), at(61)),
+            Some(Action::Answer { .. })
+        ));
+    }
+}
@@ -0,0 +1,620 @@
+//! Session transcripts for audits: the terminal output of a channel appended
+//! to <data dir>/logs/<session>-<timestamp>.log, with timestamped connect /
+//! disconnect markers and, optionally, the lines typed. In plain text mode
+//! (.txt) the escape sequences are stripped first (see `plain_text`).
+//!
+//! Every batch is written straight to the file (no userspace buffer), so an
+//! app crash loses nothing; `sync_data` every SYNC_INTERVAL covers the OS.
+//! The writing happens on a thread of the transcript (`TranscriptWriter`):
+//! a slow disk doesn't hold the channel reader up. Past WRITER_QUEUE batches
+//! behind it, output is left out and the transcript says how much.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use crate::input_log::looks_like_password_prompt;
+use crate::plain_text::PlainText;
+
+/// Rotation size when none is set, and the smallest accepted
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+const MIN_MAX_BYTES: u64 = 64 * 1024;
+const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+// Output kept to tell whether the next typed line answers a password prompt
+const OUTPUT_TAIL_LIMIT: usize = 256;
+// Two tabs of a session opened in the same second: <name>-2, <name>-3...
+const MAX_NAME_TRIES: u32 = 100;
+// Bytes waiting for the writer thread; past them output is dropped (and
+// counted) until it catches up
+const WRITER_BACKLOG: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TranscriptOptions {
+    /// A new part file starts past this size
+    pub max_bytes: u64,
+    /// Also write the lines typed (never those answering a password prompt)
+    pub log_input: bool,
+    /// What the user saw, without escape sequences, instead of the raw stream
+    pub plain_text: bool,
+}
+
+impl Default for TranscriptOptions {
+    fn default() -> Self {
+        TranscriptOptions {
+            max_bytes: DEFAULT_MAX_BYTES,
+            log_input: false,
+            plain_text: false,
+        }
+    }
+}
+
+impl TranscriptOptions {
+    /// `max_mb` of the connect params (None = default)
+    pub fn new(max_mb: Option<u32>, log_input: bool, plain_text: bool) -> Self {
+        TranscriptOptions {
+            max_bytes: max_mb
+                .map(|mb| (u64::from(mb) * 1024 * 1024).max(MIN_MAX_BYTES))
+                .unwrap_or(DEFAULT_MAX_BYTES),
+            log_input,
+            plain_text,
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        if self.plain_text {
+            "txt"
+        } else {
+            "log"
+        }
+    }
+}
+
+/// Where transcripts go: next to the database
+pub fn logs_dir() -> PathBuf {
+    crate::db::data_dir().join("logs")
+}
+
+/// Session name as a file name: anything but letters, digits, '-', '_' and
+/// '.' becomes '_'
+fn file_stem(session_name: &str) -> String {
+    let stem: String = session_name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.trim_matches(['_', '.']).is_empty() {
+        "session".to_string()
+    } else {
+        stem
+    }
+}
+
+/// Keystrokes of the line being typed, as the user saw them
+#[derive(Default)]
+struct InputLine {
+    text: String,
+    // Typed right after a password prompt: dropped, never written
+    secret: bool,
+    // Inside an escape sequence (arrows, function keys)
+    escape: Escape,
+}
+
+#[derive(Default, PartialEq)]
+enum Escape {
+    #[default]
+    None,
+    Start,
+    Sequence,
+}
+
+pub struct Transcript {
+    dir: PathBuf,
+    stem: String,
+    options: TranscriptOptions,
+    file: File,
+    path: PathBuf,
+    part: u32,
+    written: u64,
+    at_line_start: bool,
+    last_sync: Instant,
+    output_tail: String,
+    input: InputLine,
+    plain: Option<PlainText>,
+}
+
+impl Transcript {
+    /// Create <dir>/<session>-<YYYYmmdd-HHMMSS>.log (.txt in plain text
+    /// mode) and `dir` if missing. Never an existing file: a name taken
+    /// (another tab of the session, same second) gets a -2, -3... suffix
+    pub fn open(dir: &Path, session_name: &str, options: TranscriptOptions) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let base = format!(
+            "{}-{}",
+            file_stem(session_name),
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let mut n = 1;
+        let (stem, path, file) = loop {
+            let stem = match n {
+                1 => base.clone(),
+                n => format!("{}-{}", base, n),
+            };
+            let path = dir.join(format!("{}.{}", stem, options.extension()));
+            match OpenOptions::new().create_new(true).append(true).open(&path) {
+                Ok(file) => break (stem, path, file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists && n < MAX_NAME_TRIES => n += 1,
+                Err(e) => return Err(e),
+            }
+        };
+        Ok(Transcript {
+            dir: dir.to_path_buf(),
+            stem,
+            options,
+            file,
+            path,
+            part: 0,
+            written: 0,
+            at_line_start: true,
+            last_sync: Instant::now(),
+            output_tail: String::new(),
+            input: InputLine::default(),
+            plain: options.plain_text.then(PlainText::default),
+        })
+    }
+
+    /// The file being written (the last part after a rotation)
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// "[2026-10-14 12:00:00] --- Connected to root@db1:22 ---" on its own line
+    pub fn marker(&mut self, event: &str) -> io::Result<()> {
+        self.write_partial_line()?;
+        let line = format!("[{}] --- {} ---\n", timestamp(), event);
+        self.write_line(&line)?;
+        self.sync()
+    }
+
+    /// Terminal output as sent to the frontend
+    pub fn output(&mut self, text: &str) -> io::Result<()> {
+        self.output_tail.push_str(text);
+        if self.output_tail.len() > OUTPUT_TAIL_LIMIT {
+            let mut cut = self.output_tail.len() - OUTPUT_TAIL_LIMIT;
+            while !self.output_tail.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.output_tail.drain(..cut);
+        }
+        match self.plain.as_mut() {
+            Some(plain) => {
+                let lines = plain.feed(text);
+                self.write(lines.as_bytes())
+            }
+            None => self.write(text.as_bytes()),
+        }
+    }
+
+    /// Plain text keeps the open line (a prompt) until it ends: written as
+    /// is before a marker
+    fn write_partial_line(&mut self) -> io::Result<()> {
+        match self.plain.as_mut().and_then(PlainText::take_partial) {
+            Some(line) => self.write(format!("{}\n", line).as_bytes()),
+            None => Ok(()),
+        }
+    }
+
+    /// Keystrokes sent to the channel: each completed line is written as
+    /// "[time] > line", unless it answers a password prompt
+    pub fn input(&mut self, text: &str) -> io::Result<()> {
+        if !self.options.log_input {
+            return Ok(());
+        }
+        for c in text.chars() {
+            match (&self.input.escape, c) {
+                (_, '\u{1b}') => self.input.escape = Escape::Start,
+                (Escape::Start, '[' | 'O') => self.input.escape = Escape::Sequence,
+                (Escape::Start, _) => self.input.escape = Escape::None,
+                (Escape::Sequence, '\u{40}'..='\u{7e}') => self.input.escape = Escape::None,
+                (Escape::Sequence, _) => {}
+                (Escape::None, '\r' | '\n') => {
+                    let line = std::mem::take(&mut self.input);
+                    if !line.secret && !line.text.trim().is_empty() {
+                        self.write_line(&format!("[{}] > {}\n", timestamp(), line.text))?;
+                    }
+                }
+                (Escape::None, '\u{7f}' | '\u{8}') => {
+                    self.input.text.pop();
+                }
+                // Ctrl-C / Ctrl-U…: the line is abandoned or rewritten
+                (Escape::None, '\u{3}' | '\u{15}') => self.input = InputLine::default(),
+                (Escape::None, c) if c.is_control() => {}
+                (Escape::None, c) => {
+                    if self.input.text.is_empty() && !self.input.secret {
+                        self.input.secret = looks_like_password_prompt(&self.output_tail);
+                    }
+                    if !self.input.secret {
+                        self.input.text.push(c);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if !self.at_line_start {
+            self.write(b"\n")?;
+        }
+        self.write(line.as_bytes())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        if self.written > 0 && self.written + bytes.len() as u64 > self.options.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(bytes)?;
+        self.written += bytes.len() as u64;
+        self.at_line_start = bytes.ends_with(b"\n");
+        if self.last_sync.elapsed() >= SYNC_INTERVAL {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Continue in <stem>.<n>.log
+    fn rotate(&mut self) -> io::Result<()> {
+        self.sync()?;
+        let previous = self
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned());
+        self.part += 1;
+        self.path = self.dir.join(format!(
+            "{}.{}.{}",
+            self.stem,
+            self.part,
+            self.options.extension()
+        ));
+        self.file = open_append(&self.path)?;
+        self.written = 0;
+        self.at_line_start = true;
+        let line = format!(
+            "[{}] --- Continued from {} ---\n",
+            timestamp(),
+            previous.unwrap_or_default()
+        );
+        self.file.write_all(line.as_bytes())?;
+        self.written = line.len() as u64;
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.last_sync = Instant::now();
+        self.file.sync_data()
+    }
+}
+
+/// What the reader hands the writer thread
+enum Entry {
+    Output(String),
+    Input(String),
+    Marker(String),
+}
+
+impl Entry {
+    fn len(&self) -> usize {
+        match self {
+            Entry::Output(text) | Entry::Input(text) | Entry::Marker(text) => text.len(),
+        }
+    }
+}
+
+/// Counters shared by a writer and its thread
+#[derive(Default)]
+struct Backlog {
+    /// Bytes queued and not written yet
+    queued: AtomicU64,
+    /// Bytes left out since the thread last said so in the file
+    dropped: AtomicU64,
+}
+
+/// A transcript written by a thread of its own. Each call queues the text
+/// and returns, never waiting: with WRITER_BACKLOG bytes still unwritten,
+/// output and input are dropped and counted (markers always go in). False
+/// once the thread gave up (the disk failed, it said why in the log), which
+/// ends the logging. Once dropped, the thread writes what is queued and
+/// closes the file on its own.
+pub struct TranscriptWriter {
+    path: PathBuf,
+    sender: mpsc::Sender<Entry>,
+    backlog: Arc<Backlog>,
+}
+
+impl TranscriptWriter {
+    /// `Transcript::open`: the file exists when this returns
+    pub fn open(dir: &Path, session_name: &str, options: TranscriptOptions) -> io::Result<Self> {
+        let mut transcript = Transcript::open(dir, session_name, options)?;
+        let path = transcript.path().to_path_buf();
+        let (sender, entries) = mpsc::channel::<Entry>();
+        let backlog = Arc::new(Backlog::default());
+        let shared = backlog.clone();
+        std::thread::Builder::new()
+            .name("transcript".into())
+            .spawn(move || {
+                for entry in entries {
+                    shared
+                        .queued
+                        .fetch_sub(entry.len() as u64, Ordering::Relaxed);
+                    let lost = shared.dropped.swap(0, Ordering::Relaxed);
+                    let noted = match lost {
+                        0 => Ok(()),
+                        lost => transcript
+                            .marker(&format!("{} bytes not logged: the disk fell behind", lost)),
+                    };
+                    let written = noted.and_then(|_| match &entry {
+                        Entry::Output(text) => transcript.output(text),
+                        Entry::Input(text) => transcript.input(text),
+                        Entry::Marker(event) => transcript.marker(event),
+                    });
+                    if let Err(e) = written {
+                        log::warn!("Transcript {} stopped: {}", transcript.path().display(), e);
+                        return;
+                    }
+                }
+            })?;
+        Ok(TranscriptWriter {
+            path,
+            sender,
+            backlog,
+        })
+    }
+
+    /// The first file of the transcript
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn output(&self, text: &str) -> bool {
+        self.send(Entry::Output(text.to_string()))
+    }
+
+    pub fn input(&self, text: &str) -> bool {
+        self.send(Entry::Input(text.to_string()))
+    }
+
+    pub fn marker(&self, event: &str) -> bool {
+        self.send(Entry::Marker(event.to_string()))
+    }
+
+    fn send(&self, entry: Entry) -> bool {
+        let len = entry.len() as u64;
+        let behind = self.backlog.queued.load(Ordering::Relaxed) >= WRITER_BACKLOG;
+        if behind && !matches!(entry, Entry::Marker(_)) {
+            self.backlog.dropped.fetch_add(len, Ordering::Relaxed);
+            // Still logging: the thread is only slow
+            return true;
+        }
+        self.backlog.queued.fetch_add(len, Ordering::Relaxed);
+        self.sender.send(entry).is_ok()
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ori-transcript-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn options(log_input: bool) -> TranscriptOptions {
+        TranscriptOptions {
+            log_input,
+            ..TranscriptOptions::default()
+        }
+    }
+
+    #[test]
+    fn session_names_become_safe_file_names() {
+        assert_eq!(file_stem("prod db/1"), "prod_db_1");
+        assert_eq!(file_stem("web-01.lan"), "web-01.lan");
+        assert_eq!(file_stem(" ../ "), "session");
+    }
+
+    #[test]
+    fn password_prompts_are_recognized() {
+        assert!(looks_like_password_prompt("[sudo] password for ana: "));
+        assert!(looks_like_password_prompt("login ok\nContraseña:"));
+        assert!(looks_like_password_prompt("Enter passphrase for key '/k':"));
+        assert!(!looks_like_password_prompt("ana@host:~$ "));
+        assert!(!looks_like_password_prompt("Password: ok\nana@host:~$ "));
+    }
+
+    #[test]
+    fn output_and_markers_land_on_their_own_lines() {
+        let dir = temp_dir();
+        let mut transcript = Transcript::open(&dir, "db", options(false)).unwrap();
+        transcript.marker("Connected to ana@db:22").unwrap();
+        transcript.output("ana@db:~$ ").unwrap();
+        transcript.input("ls\r").unwrap();
+        transcript.marker("Disconnected").unwrap();
+
+        let text = std::fs::read_to_string(transcript.path()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("--- Connected to ana@db:22 ---"));
+        // Input is off by default
+        assert_eq!(lines[1], "ana@db:~$ ");
+        assert!(lines[2].ends_with("--- Disconnected ---"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn typed_lines_skip_password_answers() {
+        let dir = temp_dir();
+        let mut transcript = Transcript::open(&dir, "db", options(true)).unwrap();
+        transcript.output("$ ").unwrap();
+        transcript.input("sudo lz\u{7f}s\u{1b}[A\r").unwrap();
+        transcript.output("[sudo] password for ana: ").unwrap();
+        transcript.input("s3cret\r").unwrap();
+        transcript.output("\n$ ").unwrap();
+        transcript.input("exit\r").unwrap();
+
+        let text = std::fs::read_to_string(transcript.path()).unwrap();
+        assert!(text.contains("] > sudo ls\n"));
+        assert!(text.contains("] > exit\n"));
+        assert!(!text.contains("s3cret"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn output_past_the_size_limit_continues_in_a_new_part() {
+        let dir = temp_dir();
+        let mut transcript = Transcript::open(
+            &dir,
+            "db",
+            TranscriptOptions {
+                max_bytes: 10,
+                ..TranscriptOptions::default()
+            },
+        )
+        .unwrap();
+        let first = transcript.path().to_path_buf();
+        transcript.output("12345678").unwrap();
+        transcript.output("abcdef").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "12345678");
+        assert!(transcript.path().to_string_lossy().ends_with(".1.log"));
+        let second = std::fs::read_to_string(transcript.path()).unwrap();
+        assert!(second.contains("--- Continued from "));
+        assert!(second.ends_with("abcdef"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn tabs_opened_in_the_same_second_get_their_own_file() {
+        let dir = temp_dir();
+        let first = Transcript::open(&dir, "db", options(false)).unwrap();
+        let second = Transcript::open(&dir, "db", options(false)).unwrap();
+        // Unless the clock ticked in between, the second one is <name>-2
+        assert_ne!(first.path(), second.path());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    /// The file once the writer thread got `ending` in (it runs on its own)
+    fn wait_for_line(path: &Path, ending: &str) -> String {
+        for _ in 0..200 {
+            let text = std::fs::read_to_string(path).unwrap_or_default();
+            if text.lines().any(|line| line.ends_with(ending)) {
+                return text;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        panic!("{} never got {:?}", path.display(), ending);
+    }
+
+    #[test]
+    fn the_writer_thread_writes_everything_before_closing() {
+        let dir = temp_dir();
+        let writer = TranscriptWriter::open(&dir, "db", options(true)).unwrap();
+        let path = writer.path().to_path_buf();
+        assert!(writer.marker("Connected"));
+        assert!(writer.output("$ "));
+        assert!(writer.input("ls\r"));
+        assert!(writer.output("bin\n$ "));
+        assert!(writer.marker("Disconnected"));
+        drop(writer);
+
+        let text = wait_for_line(&path, "--- Disconnected ---");
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].ends_with("--- Connected ---"));
+        assert_eq!(lines[1], "$ ");
+        assert!(lines[2].ends_with("] > ls"));
+        assert_eq!(lines[3], "bin");
+        assert!(lines[5].ends_with("--- Disconnected ---"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn a_writer_that_falls_behind_drops_and_says_so() {
+        let dir = temp_dir();
+        let writer = TranscriptWriter::open(&dir, "db", options(false)).unwrap();
+        let path = writer.path().to_path_buf();
+        // Past the queue nothing waits: each call returns at once
+        let batch = "x".repeat(1024);
+        // Twice the backlog, under the rotation size: one file
+        for _ in 0..WRITER_BACKLOG / 1024 * 2 {
+            assert!(writer.output(&batch));
+        }
+        // Whatever the disk managed, what comes next is noted
+        writer.backlog.dropped.fetch_add(1, Ordering::Relaxed);
+        assert!(writer.marker("Disconnected"));
+        drop(writer);
+
+        let text = wait_for_line(&path, "--- Disconnected ---");
+        assert!(text.contains(" bytes not logged: the disk fell behind ---"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn size_option_has_a_floor() {
+        assert_eq!(
+            TranscriptOptions::new(None, false, false).max_bytes,
+            DEFAULT_MAX_BYTES
+        );
+        assert_eq!(
+            TranscriptOptions::new(Some(0), false, false).max_bytes,
+            MIN_MAX_BYTES
+        );
+        assert_eq!(
+            TranscriptOptions::new(Some(2), true, false).max_bytes,
+            2 * 1024 * 1024
+        );
+    }
+
+    #[test]
+    fn plain_text_mode_writes_what_was_seen() {
+        let dir = temp_dir();
+        let mut transcript = Transcript::open(
+            &dir,
+            "db",
+            TranscriptOptions {
+                plain_text: true,
+                ..TranscriptOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(transcript.path().to_string_lossy().ends_with(".txt"));
+        transcript
+            .output("\x1b[01;32mana@db\x1b[0m:~$ ls\r\n")
+            .unwrap();
+        transcript.output("\x1b[01;34mbin\x1b[0m\r\n$ ").unwrap();
+        transcript.marker("Disconnected").unwrap();
+
+        let text = std::fs::read_to_string(transcript.path()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[..3], ["ana@db:~$ ls", "bin", "$"]);
+        assert!(lines[3].ends_with("--- Disconnected ---"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,130 @@
+//! Wake-on-LAN: the magic packet (6 × 0xFF, then the MAC 16 times) sent
+//! as a UDP broadcast, by default to 255.255.255.255 port 9.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use thiserror::Error;
+
+const DEFAULT_WOL_PORT: u16 = 9;
+
+#[derive(Error, Debug)]
+pub enum WolError {
+    #[error("Invalid MAC address: {0}")]
+    InvalidMac(String),
+    #[error("Invalid broadcast address: {0}")]
+    InvalidBroadcast(String),
+    #[error("Wake-on-LAN send failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// "aa:bb:cc:dd:ee:ff", "AA-BB-CC-DD-EE-FF" or "aabb.ccdd.eeff"
+pub fn parse_mac(mac: &str) -> Result<[u8; 6], WolError> {
+    let hex: String = mac
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    let invalid = || WolError::InvalidMac(mac.trim().to_string());
+    if hex.len() != 12 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; 6];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+/// "192.168.1.255", "192.168.1.255:7" or empty for 255.255.255.255:9
+pub fn parse_broadcast(broadcast: Option<&str>) -> Result<SocketAddr, WolError> {
+    let value = broadcast.map(str::trim).unwrap_or_default();
+    if value.is_empty() {
+        return Ok(SocketAddr::new(
+            Ipv4Addr::BROADCAST.into(),
+            DEFAULT_WOL_PORT,
+        ));
+    }
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    value
+        .parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, DEFAULT_WOL_PORT))
+        .map_err(|_| WolError::InvalidBroadcast(value.to_string()))
+}
+
+pub fn magic_packet(mac: [u8; 6]) -> [u8; 102] {
+    let mut packet = [0xFFu8; 102];
+    for chunk in packet[6..].chunks_exact_mut(6) {
+        chunk.copy_from_slice(&mac);
+    }
+    packet
+}
+
+/// Send the magic packet of `mac`; returns where it was sent
+pub async fn send_magic_packet(mac: &str, broadcast: Option<&str>) -> Result<SocketAddr, WolError> {
+    let packet = magic_packet(parse_mac(mac)?);
+    let target = parse_broadcast(broadcast)?;
+    let local: SocketAddr = if target.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = tokio::net::UdpSocket::bind(local).await?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&packet, target).await?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mac_formats_parse_to_the_same_bytes() {
+        let expected = [0xaa, 0xbb, 0xcc, 0x01, 0x02, 0x03];
+        assert_eq!(parse_mac("aa:bb:cc:01:02:03").unwrap(), expected);
+        assert_eq!(parse_mac(" AA-BB-CC-01-02-03 ").unwrap(), expected);
+        assert_eq!(parse_mac("aabb.cc01.0203").unwrap(), expected);
+        assert!(parse_mac("aa:bb:cc:01:02").is_err());
+        assert!(parse_mac("zz:bb:cc:01:02:03").is_err());
+    }
+
+    #[test]
+    fn magic_packet_is_sync_then_sixteen_macs() {
+        let mac = [1, 2, 3, 4, 5, 6];
+        let packet = magic_packet(mac);
+        assert_eq!(packet[..6], [0xFF; 6]);
+        assert!(packet[6..].chunks(6).all(|c| c == mac));
+    }
+
+    #[test]
+    fn broadcast_defaults_to_port_9() {
+        assert_eq!(
+            parse_broadcast(None).unwrap(),
+            "255.255.255.255:9".parse().unwrap()
+        );
+        assert_eq!(
+            parse_broadcast(Some("192.168.1.255")).unwrap(),
+            "192.168.1.255:9".parse().unwrap()
+        );
+        assert_eq!(
+            parse_broadcast(Some("192.168.1.255:7")).unwrap(),
+            "192.168.1.255:7".parse().unwrap()
+        );
+        assert!(parse_broadcast(Some("lan")).is_err());
+    }
+
+    #[tokio::test]
+    async fn magic_packet_reaches_a_unicast_target() {
+        let receiver = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = receiver.local_addr().unwrap().to_string();
+
+        send_magic_packet("01:02:03:04:05:06", Some(&addr))
+            .await
+            .unwrap();
+
+        let mut buf = [0u8; 128];
+        let n = receiver.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..n], &magic_packet([1, 2, 3, 4, 5, 6])[..]);
+    }
+}
@@ -0,0 +1,451 @@
+//! X11 forwarding (`x11Forwarding`): the server opens an "x11" channel for
+//! every remote client (virt-manager, wireshark…) and each one is piped to
+//! the local X server of $DISPLAY: /tmp/.X11-unix/X<n> or TCP 6000+n.
+//!
+//! Spoofed cookie, as OpenSSH does: the server gets a random fake
+//! MIT-MAGIC-COOKIE-1, never the real one. The first packet of each channel
+//! (the client's connection setup) must carry the fake cookie; it goes on to
+//! the local server with the real one (~/.Xauthority or `xauth list`), or
+//! with no auth at all when the display has no cookie.
+
+use aes_gcm::aead::OsRng;
+use rand_core::RngCore;
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+pub const AUTH_PROTOCOL: &str = "MIT-MAGIC-COOKIE-1";
+const COOKIE_LEN: usize = 16;
+const TCP_BASE_PORT: u16 = 6000;
+/// A display that doesn't answer by then is not there
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+/// Setup packet header: byte order, protocol version, auth field lengths
+const SETUP_HEADER: usize = 12;
+// Xauthority address families
+const FAMILY_LOCAL: u16 = 256;
+const FAMILY_WILD: u16 = 65535;
+
+#[derive(Debug, Error)]
+pub enum X11Error {
+    #[error("DISPLAY is not set: there is no local X server to forward to")]
+    NoDisplay,
+    #[error("Invalid DISPLAY '{0}'")]
+    InvalidDisplay(String),
+    #[error("X server {display} not reachable: {message}")]
+    Unreachable { display: String, message: String },
+    #[error("X11 client sent a wrong auth cookie")]
+    WrongCookie,
+    #[error("X11 channel I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl X11Error {
+    /// `ConnectInfo.x11_forwarding` when forwarding could not start
+    pub fn reason(&self) -> &'static str {
+        match self {
+            X11Error::NoDisplay => "no_display",
+            X11Error::InvalidDisplay(_) => "invalid_display",
+            X11Error::Unreachable { .. } => "unreachable",
+            X11Error::WrongCookie | X11Error::Io(_) => "failed",
+        }
+    }
+}
+
+/// Where the local X server listens
+#[derive(Debug, Clone, PartialEq)]
+enum Target {
+    Unix(PathBuf),
+    Tcp(String, u16),
+}
+
+/// $DISPLAY parsed: `[host]:number[.screen]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Display {
+    name: String,
+    /// "" or "unix" = local socket; a path = the socket itself (XQuartz's
+    /// launchd socket on macOS)
+    host: String,
+    number: u16,
+    pub screen: u32,
+}
+
+impl Display {
+    pub fn parse(name: &str) -> Result<Self, X11Error> {
+        let invalid = || X11Error::InvalidDisplay(name.to_string());
+        let (host, rest) = name.trim().rsplit_once(':').ok_or_else(invalid)?;
+        let (number, screen) = match rest.split_once('.') {
+            Some((number, screen)) => (number, screen.parse().map_err(|_| invalid())?),
+            None => (rest, 0),
+        };
+        let number: u16 = number.parse().map_err(|_| invalid())?;
+        if number > u16::MAX - TCP_BASE_PORT {
+            return Err(invalid());
+        }
+        Ok(Display {
+            name: name.trim().to_string(),
+            host: host.to_string(),
+            number,
+            screen,
+        })
+    }
+
+    /// The display of the local socket (what the Xauthority entries of
+    /// family "local" are for)
+    fn is_local(&self) -> bool {
+        self.host.is_empty() || self.host == "unix" || self.host.starts_with('/')
+    }
+
+    fn target(&self) -> Target {
+        if self.host.starts_with('/') {
+            return Target::Unix(PathBuf::from(format!("{}:{}", self.host, self.number)));
+        }
+        // No unix sockets for X on Windows: VcXsrv / Xming listen on TCP
+        if self.is_local() && cfg!(unix) {
+            return Target::Unix(PathBuf::from(format!("/tmp/.X11-unix/X{}", self.number)));
+        }
+        let host = if self.is_local() {
+            "localhost"
+        } else {
+            &self.host
+        };
+        Target::Tcp(host.to_string(), TCP_BASE_PORT + self.number)
+    }
+
+    async fn connect(&self) -> Result<Box<dyn LocalStream>, X11Error> {
+        let unreachable = |message: String| X11Error::Unreachable {
+            display: self.name.clone(),
+            message,
+        };
+        let connect = async {
+            match self.target() {
+                #[cfg(unix)]
+                Target::Unix(path) => tokio::net::UnixStream::connect(path)
+                    .await
+                    .map(|s| Box::new(s) as Box<dyn LocalStream>),
+                #[cfg(not(unix))]
+                Target::Unix(path) => Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    format!("no unix sockets here ({})", path.display()),
+                )),
+                Target::Tcp(host, port) => tokio::net::TcpStream::connect((host.as_str(), port))
+                    .await
+                    .map(|s| Box::new(s) as Box<dyn LocalStream>),
+            }
+        };
+        match tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+            Ok(Ok(stream)) => Ok(stream),
+            Ok(Err(e)) => Err(unreachable(e.to_string())),
+            Err(_) => Err(unreachable("timed out".to_string())),
+        }
+    }
+}
+
+pub trait LocalStream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> LocalStream for T {}
+
+/// X11 forwarding of one connection: the display, and the cookie pair
+pub struct Forwarding {
+    pub display: Display,
+    /// Sent to the server; channels must present it
+    fake: [u8; COOKIE_LEN],
+    /// What the local server expects (None = no cookie for the display)
+    real: Option<Vec<u8>>,
+}
+
+impl Forwarding {
+    /// $DISPLAY, checked to answer now: a display that isn't there fails the
+    /// request instead of leaving remote clients waiting
+    pub async fn prepare() -> Result<Self, X11Error> {
+        let name = std::env::var("DISPLAY")
+            .ok()
+            .filter(|d| !d.trim().is_empty())
+            .ok_or(X11Error::NoDisplay)?;
+        let display = Display::parse(&name)?;
+        drop(display.connect().await?);
+        let lookup = display.clone();
+        let real = tokio::task::spawn_blocking(move || find_cookie(&lookup))
+            .await
+            .ok()
+            .flatten();
+        if real.is_none() {
+            log::info!("X11: no cookie for {}, connecting without auth", name);
+        }
+        let mut fake = [0u8; COOKIE_LEN];
+        OsRng.fill_bytes(&mut fake);
+        Ok(Forwarding {
+            display,
+            fake,
+            real,
+        })
+    }
+
+    /// The cookie of the `x11-req`, in hex
+    pub fn fake_cookie(&self) -> String {
+        data_encoding::HEXLOWER.encode(&self.fake)
+    }
+
+    /// Pipe one X11 channel to the local server until either side closes
+    pub async fn proxy<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut remote: S,
+    ) -> Result<(), X11Error> {
+        let setup = read_setup(&mut remote).await?;
+        let setup = translate_setup(&setup, &self.fake, self.real.as_deref())?;
+        let mut local = self.display.connect().await?;
+        local.write_all(&setup).await?;
+        tokio::io::copy_bidirectional(&mut remote, &mut local).await?;
+        Ok(())
+    }
+}
+
+fn pad4(n: usize) -> usize {
+    n.div_ceil(4) * 4
+}
+
+/// The auth name / data lengths of a setup header, in its byte order
+fn auth_lengths(header: &[u8]) -> (usize, usize) {
+    let u16_at = |at: usize| {
+        let bytes = [header[at], header[at + 1]];
+        match header[0] {
+            b'B' => u16::from_be_bytes(bytes),
+            _ => u16::from_le_bytes(bytes),
+        }
+    };
+    (u16_at(6).into(), u16_at(8).into())
+}
+
+async fn read_setup<S: AsyncRead + Unpin>(remote: &mut S) -> Result<Vec<u8>, X11Error> {
+    let mut setup = vec![0u8; SETUP_HEADER];
+    remote.read_exact(&mut setup).await?;
+    let (name, data) = auth_lengths(&setup);
+    setup.resize(SETUP_HEADER + pad4(name) + pad4(data), 0);
+    remote.read_exact(&mut setup[SETUP_HEADER..]).await?;
+    Ok(setup)
+}
+
+/// The client's setup packet with the fake cookie swapped for the real one
+fn translate_setup(setup: &[u8], fake: &[u8], real: Option<&[u8]>) -> Result<Vec<u8>, X11Error> {
+    if setup.len() < SETUP_HEADER || !matches!(setup[0], b'B' | b'l') {
+        return Err(X11Error::WrongCookie);
+    }
+    let (name_len, data_len) = auth_lengths(setup);
+    let data_at = SETUP_HEADER + pad4(name_len);
+    if setup.len() < data_at + pad4(data_len) {
+        return Err(X11Error::WrongCookie);
+    }
+    let name = &setup[SETUP_HEADER..SETUP_HEADER + name_len];
+    let data = &setup[data_at..data_at + data_len];
+    if name != AUTH_PROTOCOL.as_bytes() || data != fake {
+        return Err(X11Error::WrongCookie);
+    }
+
+    let (name, data): (&[u8], &[u8]) = match real {
+        Some(cookie) => (AUTH_PROTOCOL.as_bytes(), cookie),
+        None => (&[], &[]),
+    };
+    let u16_bytes = |n: usize| match setup[0] {
+        b'B' => (n as u16).to_be_bytes(),
+        _ => (n as u16).to_le_bytes(),
+    };
+    let mut out = setup[..6].to_vec();
+    out.extend_from_slice(&u16_bytes(name.len()));
+    out.extend_from_slice(&u16_bytes(data.len()));
+    out.extend_from_slice(&setup[10..SETUP_HEADER]);
+    for field in [name, data] {
+        out.extend_from_slice(field);
+        out.resize(out.len() + pad4(field.len()) - field.len(), 0);
+    }
+    Ok(out)
+}
+
+/// One record of an Xauthority file
+#[derive(Debug, PartialEq)]
+struct XauthEntry {
+    family: u16,
+    number: String,
+    name: String,
+    data: Vec<u8>,
+}
+
+/// The records of an Xauthority file (a truncated last one is dropped)
+fn parse_xauthority(bytes: &[u8]) -> Vec<XauthEntry> {
+    fn field<'a>(bytes: &mut &'a [u8]) -> Option<&'a [u8]> {
+        let len = u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]) as usize;
+        let value = bytes.get(2..2 + len)?;
+        *bytes = &bytes[2 + len..];
+        Some(value)
+    }
+    let mut rest = bytes;
+    let mut entries = Vec::new();
+    while rest.len() >= 2 {
+        let family = u16::from_be_bytes([rest[0], rest[1]]);
+        rest = &rest[2..];
+        let entry = (|| {
+            let _address = field(&mut rest)?;
+            let number = field(&mut rest)?;
+            let name = field(&mut rest)?;
+            let data = field(&mut rest)?;
+            Some(XauthEntry {
+                family,
+                number: String::from_utf8_lossy(number).into_owned(),
+                name: String::from_utf8_lossy(name).into_owned(),
+                data: data.to_vec(),
+            })
+        })();
+        match entry {
+            Some(entry) => entries.push(entry),
+            None => break,
+        }
+    }
+    entries
+}
+
+/// The MIT-MAGIC-COOKIE-1 of `display` among `entries`. Local displays
+/// prefer the "local" / wildcard records: the address is the hostname, which
+/// may not be the one we would guess.
+fn cookie_for(entries: &[XauthEntry], display: &Display) -> Option<Vec<u8>> {
+    let number = display.number.to_string();
+    let mut matching = entries
+        .iter()
+        .filter(|e| e.name == AUTH_PROTOCOL && e.number == number);
+    let preferred = matching
+        .clone()
+        .find(|e| !display.is_local() || matches!(e.family, FAMILY_LOCAL | FAMILY_WILD));
+    preferred
+        .or_else(|| matching.next())
+        .map(|e| e.data.clone())
+}
+
+/// The real cookie: $XAUTHORITY (~/.Xauthority), else `xauth list`
+fn find_cookie(display: &Display) -> Option<Vec<u8>> {
+    let path = std::env::var_os("XAUTHORITY")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".Xauthority")));
+    if let Some(bytes) = path.and_then(|p| std::fs::read(p).ok()) {
+        if let Some(cookie) = cookie_for(&parse_xauthority(&bytes), display) {
+            return Some(cookie);
+        }
+    }
+    // "host/unix:0  MIT-MAGIC-COOKIE-1  0123abcd…"
+    let output = std::process::Command::new("xauth")
+        .arg("list")
+        .arg(&display.name)
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            (fields.next()? == AUTH_PROTOCOL).then_some(fields.next()?)
+        })
+        .find_map(|hex| {
+            data_encoding::HEXLOWER_PERMISSIVE
+                .decode(hex.as_bytes())
+                .ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_packet(order: u8, name: &[u8], data: &[u8]) -> Vec<u8> {
+        let u16_bytes = |n: usize| match order {
+            b'B' => (n as u16).to_be_bytes(),
+            _ => (n as u16).to_le_bytes(),
+        };
+        let mut packet = vec![order, 0];
+        packet.extend_from_slice(&u16_bytes(11));
+        packet.extend_from_slice(&u16_bytes(0));
+        packet.extend_from_slice(&u16_bytes(name.len()));
+        packet.extend_from_slice(&u16_bytes(data.len()));
+        packet.extend_from_slice(&[0, 0]);
+        for field in [name, data] {
+            packet.extend_from_slice(field);
+            packet.resize(packet.len() + pad4(field.len()) - field.len(), 0);
+        }
+        packet
+    }
+
+    #[test]
+    fn parses_display_names() {
+        let local = Display::parse(":0").unwrap();
+        assert_eq!((local.number, local.screen), (0, 0));
+        assert!(local.is_local());
+        let ssh = Display::parse("localhost:10.1").unwrap();
+        assert_eq!((ssh.number, ssh.screen), (10, 1));
+        assert_eq!(ssh.target(), Target::Tcp("localhost".into(), 6010));
+        let xquartz = Display::parse("/private/tmp/com.apple.launchd.Ab12/org.xquartz:0").unwrap();
+        assert_eq!(
+            xquartz.target(),
+            Target::Unix("/private/tmp/com.apple.launchd.Ab12/org.xquartz:0".into())
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            Display::parse("unix:1").unwrap().target(),
+            Target::Unix("/tmp/.X11-unix/X1".into())
+        );
+        for bad in ["", "0", ":x", ":0.y", ":65000"] {
+            assert_eq!(Display::parse(bad).unwrap_err().reason(), "invalid_display");
+        }
+    }
+
+    #[test]
+    fn swaps_the_fake_cookie_for_the_real_one() {
+        let fake = [7u8; COOKIE_LEN];
+        let real = [42u8; COOKIE_LEN];
+        for order in [b'l', b'B'] {
+            let setup = setup_packet(order, AUTH_PROTOCOL.as_bytes(), &fake);
+            assert_eq!(auth_lengths(&setup), (18, 16));
+            assert_eq!(
+                translate_setup(&setup, &fake, Some(&real)).unwrap(),
+                setup_packet(order, AUTH_PROTOCOL.as_bytes(), &real)
+            );
+            // A display without a cookie: no auth at all
+            assert_eq!(
+                translate_setup(&setup, &fake, None).unwrap(),
+                setup_packet(order, b"", b"")
+            );
+        }
+        // Anything but our fake cookie is refused, the real one included
+        let forged = setup_packet(b'l', AUTH_PROTOCOL.as_bytes(), &real);
+        assert!(translate_setup(&forged, &fake, Some(&real)).is_err());
+        let no_auth = setup_packet(b'l', b"", b"");
+        assert!(translate_setup(&no_auth, &fake, Some(&real)).is_err());
+        assert!(translate_setup(&[b'l', 0, 11], &fake, None).is_err());
+    }
+
+    #[test]
+    fn finds_the_display_cookie_in_xauthority() {
+        fn record(family: u16, address: &str, number: &str, data: &[u8]) -> Vec<u8> {
+            let mut out = family.to_be_bytes().to_vec();
+            for field in [
+                address.as_bytes(),
+                number.as_bytes(),
+                AUTH_PROTOCOL.as_bytes(),
+                data,
+            ] {
+                out.extend_from_slice(&(field.len() as u16).to_be_bytes());
+                out.extend_from_slice(field);
+            }
+            out
+        }
+        let mut file = record(0, "\x7f\0\0\x01", "0", &[1; 16]);
+        file.extend(record(FAMILY_LOCAL, "laptop", "0", &[2; 16]));
+        file.extend(record(FAMILY_LOCAL, "laptop", "1", &[3; 16]));
+        // Truncated record at the end: ignored
+        file.extend_from_slice(&[1, 0, 0, 6, b'l']);
+        let entries = parse_xauthority(&file);
+        assert_eq!(entries.len(), 3);
+
+        let display = |name: &str| Display::parse(name).unwrap();
+        assert_eq!(cookie_for(&entries, &display(":0")), Some(vec![2; 16]));
+        assert_eq!(cookie_for(&entries, &display(":1.0")), Some(vec![3; 16]));
+        assert_eq!(
+            cookie_for(&entries, &display("127.0.0.1:0")),
+            Some(vec![1; 16])
+        );
+        assert_eq!(cookie_for(&entries, &display(":2")), None);
+    }
+}
@@ -16,6 +16,8 @@ import {
   CommandPalette,
   ToastContainer,
   TitleBar,
+  AuthPromptDialog,
+  HostKeyChangedDialog,
 } from './components';
 import { SettingsModal } from './components/SettingsModal';
 
@@ -34,7 +36,8 @@ function App() {
 
   useEffect(() => {
     sshService.initialize();
-    initialize();
+    // Sessions first: recovered channels are matched to them
+    initialize().then(() => sshService.recoverChannels());
   }, [initialize]);
 
   if (!isInitialized) {
@@ -98,6 +101,8 @@ function App() {
       <SessionInfoModal />
       <CommandPalette />
       <SettingsModal />
+      <AuthPromptDialog />
+      <HostKeyChangedDialog />
       <ToastContainer />
     </div>
   );
@@ -0,0 +1,197 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { KeyRound } from 'lucide-react';
+import { sshService } from '../hooks/sshService';
+
+interface AuthPromptPayload {
+  requestId: string;
+  progressId?: string | null;
+  host: string;
+  name: string;
+  instructions: string;
+  prompts: { prompt: string; echo: boolean }[];
+  // Set for auth_passphrase_required rounds, answered via ssh_provide_passphrase
+  passphrase?: boolean;
+  // Set for password_change_required, answered via ssh_change_password
+  passwordChange?: { connectId: string };
+}
+
+interface PassphrasePayload {
+  requestId: string;
+  progressId?: string | null;
+  host: string;
+  key: string;
+  reason: string;
+  attempt: number;
+  maxAttempts: number;
+}
+
+interface PasswordChangePayload {
+  connectId: string;
+  progressId?: string | null;
+  host: string;
+  username: string;
+  message: string;
+}
+
+// Keyboard-interactive prompts (PAM, OTP tokens) raised by the backend during
+// ssh_connect. The backend waits up to 60s per round for ssh_auth_answer;
+// several rounds arrive as successive events and are answered in order.
+// A missing/wrong key passphrase is asked the same way (up to maxAttempts),
+// and so is the new password of an expired account.
+export function AuthPromptDialog() {
+  const [queue, setQueue] = useState<AuthPromptPayload[]>([]);
+  const current = queue[0];
+
+  useEffect(() => {
+    const unlisten = listen<AuthPromptPayload>('auth_prompt', (event) => {
+      setQueue((q) => [...q, event.payload]);
+    });
+    const unlistenPassphrase = listen<PassphrasePayload>('auth_passphrase_required', (event) => {
+      const p = event.payload;
+      setQueue((q) => [
+        ...q,
+        {
+          requestId: p.requestId,
+          progressId: p.progressId,
+          host: p.host,
+          name: `Passphrase de la clave (${p.attempt}/${p.maxAttempts})`,
+          instructions: `${p.reason}\n${p.key}`,
+          prompts: [{ prompt: 'Passphrase', echo: false }],
+          passphrase: true,
+        },
+      ]);
+    });
+    const unlistenChange = listen<PasswordChangePayload>('password_change_required', (event) => {
+      const p = event.payload;
+      setQueue((q) => [
+        ...q,
+        {
+          requestId: `password-change:${p.connectId}`,
+          progressId: p.progressId,
+          host: p.host,
+          name: `Contraseña caducada (${p.username}@${p.host})`,
+          instructions: p.message,
+          prompts: [
+            { prompt: 'Contraseña actual (vacío: la guardada)', echo: false },
+            { prompt: 'Nueva contraseña', echo: false },
+            { prompt: 'Repite la nueva contraseña', echo: false },
+          ],
+          passwordChange: { connectId: p.connectId },
+        },
+      ]);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+      unlistenPassphrase.then((fn) => fn());
+      unlistenChange.then((fn) => fn());
+    };
+  }, []);
+
+  if (!current) return null;
+
+  const reply = (values: string[] | null) => {
+    const change = current.passwordChange;
+    const request = change
+      ? values
+        ? sshService.changePassword(change.connectId, values[0], values[1])
+        : sshService.cancelConnect(change.connectId)
+      : current.passphrase
+      ? invoke('ssh_provide_passphrase', {
+          requestId: current.requestId,
+          passphrase: values ? values[0] : null,
+        })
+      : invoke('ssh_auth_answer', { requestId: current.requestId, answers: values });
+    request.catch((err) => console.error('auth prompt answer failed:', err));
+    setQueue((q) => q.slice(1));
+  };
+
+  // Keyed by request: every round starts with empty answers
+  return <AuthPromptForm key={current.requestId} request={current} onReply={reply} />;
+}
+
+function AuthPromptForm({
+  request: current,
+  onReply: reply,
+}: {
+  request: AuthPromptPayload;
+  onReply: (answers: string[] | null) => void;
+}) {
+  const [answers, setAnswers] = useState<string[]>(() => current.prompts.map(() => ''));
+  // Password change: the new one is typed twice and can't be empty
+  const mismatch =
+    !!current.passwordChange && (!answers[1] || answers[1] !== answers[2]);
+
+  return (
+    <div className="fixed inset-0 z-[100] flex items-center justify-center p-4">
+      <button
+        type="button"
+        className="absolute inset-0 bg-black/50"
+        onClick={() => reply(null)}
+        aria-label="Cancelar"
+      />
+      <form
+        role="dialog"
+        aria-modal="true"
+        onSubmit={(e) => {
+          e.preventDefault();
+          if (!mismatch) reply(answers);
+        }}
+        className="relative w-full max-w-sm rounded-lg border border-zinc-300 bg-white p-4 shadow-2xl dark:border-zinc-700 dark:bg-zinc-900"
+      >
+        <div className="mb-4 flex items-start gap-3">
+          <div className="mt-0.5 flex h-9 w-9 shrink-0 items-center justify-center rounded-md bg-blue-500/10 text-blue-600 dark:text-blue-400">
+            <KeyRound className="h-5 w-5" />
+          </div>
+          <div className="min-w-0">
+            <h2 className="text-sm font-semibold text-zinc-900 dark:text-zinc-100">
+              {current.name || `Autenticación en ${current.host}`}
+            </h2>
+            {current.instructions && (
+              <p className="mt-1 whitespace-pre-line text-sm leading-5 text-zinc-600 dark:text-zinc-400">
+                {current.instructions}
+              </p>
+            )}
+          </div>
+        </div>
+        <div className="mb-4 space-y-3">
+          {current.prompts.map((p, i) => (
+            <label key={i} className="block text-sm text-zinc-700 dark:text-zinc-300">
+              {p.prompt}
+              <input
+                type={p.echo ? 'text' : 'password'}
+                autoFocus={i === 0}
+                autoComplete="off"
+                value={answers[i] ?? ''}
+                onChange={(e) =>
+                  setAnswers((a) => a.map((v, j) => (j === i ? e.target.value : v)))
+                }
+                className="mt-1 w-full rounded-md border border-zinc-300 bg-white px-2 py-1.5 text-sm text-zinc-900 outline-none focus:border-blue-500 dark:border-zinc-700 dark:bg-zinc-800 dark:text-zinc-100"
+              />
+            </label>
+          ))}
+          {current.passwordChange && answers[2] && answers[1] !== answers[2] && (
+            <p className="text-xs text-red-600 dark:text-red-400">Las contraseñas no coinciden</p>
+          )}
+        </div>
+        <div className="flex justify-end gap-2">
+          <button
+            type="button"
+            onClick={() => reply(null)}
+            className="rounded-md px-3 py-1.5 text-sm text-zinc-700 transition-colors hover:bg-zinc-100 dark:text-zinc-300 dark:hover:bg-zinc-800"
+          >
+            Cancelar
+          </button>
+          <button
+            type="submit"
+            disabled={mismatch}
+            className="rounded-md bg-blue-600 px-3 py-1.5 text-sm font-medium text-white transition-colors hover:bg-blue-700 disabled:opacity-50"
+          >
+            {current.passwordChange ? 'Cambiar' : 'Enviar'}
+          </button>
+        </div>
+      </form>
+    </div>
+  );
+}
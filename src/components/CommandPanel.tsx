@@ -9,6 +9,8 @@ import {
   ZoomIn,
   ZoomOut,
   RotateCcw,
+  ScrollText,
+  X,
 } from 'lucide-react';
 import { useState } from 'react';
 import { useShallow } from 'zustand/react/shallow';
@@ -16,7 +18,11 @@ import { useStore } from '../store/useStore';
 import { sshService } from '../hooks/sshService';
 import { NoteBadge } from './NoteBadge';
 import { ConfirmDialog } from './ConfirmDialog';
-import type { SavedCommand } from '../types';
+import { errorMessage } from '../utils/appError';
+import type { ExecResult, SavedCommand } from '../types';
+
+// Resultado de "ejecutar en segundo plano" de un comando guardado
+type ExecState = { running: true } | { running: false; result?: ExecResult; error?: string };
 
 export function CommandPanel() {
   const {
@@ -45,6 +51,7 @@ export function CommandPanel() {
 
   const [deleteTarget, setDeleteTarget] = useState<SavedCommand | null>(null);
   const [isDeleting, setIsDeleting] = useState(false);
+  const [execStates, setExecStates] = useState<Record<string, ExecState>>({});
 
   const activeTab = tabs.find((t) => t.id === activeTabId);
   const canSendCommand = activeTab?.status === 'connected' && activeTab.channelId;
@@ -59,6 +66,26 @@ export function CommandPanel() {
     await sshService.send(activeTab.channelId, command + '\n');
   };
 
+  // Canal exec aparte: la salida no se mezcla con el terminal
+  const runInBackground = async (cmd: SavedCommand) => {
+    if (!canSendCommand || !activeTab?.channelId) return;
+    setExecStates((s) => ({ ...s, [cmd.id]: { running: true } }));
+    try {
+      const result = await sshService.exec({ channelId: activeTab.channelId }, cmd.command);
+      setExecStates((s) => ({ ...s, [cmd.id]: { running: false, result } }));
+    } catch (error) {
+      setExecStates((s) => ({ ...s, [cmd.id]: { running: false, error: errorMessage(error) } }));
+    }
+  };
+
+  const dismissExec = (id: string) => {
+    setExecStates((s) => {
+      const next = { ...s };
+      delete next[id];
+      return next;
+    });
+  };
+
   const handleEdit = (command: SavedCommand) => {
     openCommandModal({ command, mode: 'edit' });
   };
@@ -186,6 +213,14 @@ export function CommandPanel() {
                 >
                   <Play className="w-3.5 h-3.5" />
                 </button>
+                <button
+                  onClick={() => runInBackground(cmd)}
+                  disabled={!canSendCommand || execStates[cmd.id]?.running}
+                  className="p-1 rounded hover:bg-blue-500/20 text-zinc-600 dark:text-zinc-400 hover:text-blue-600 dark:hover:text-blue-400 transition-colors disabled:opacity-30 disabled:cursor-not-allowed"
+                  title="Ejecutar y ver resultado"
+                >
+                  <ScrollText className="w-3.5 h-3.5" />
+                </button>
                 <button
                   onClick={() => handleEdit(cmd)}
                   className="p-1 rounded hover:bg-zinc-200 dark:hover:bg-zinc-700 text-zinc-600 dark:text-zinc-400 hover:text-zinc-900 dark:hover:text-white transition-colors"
@@ -205,6 +240,37 @@ export function CommandPanel() {
             <code className="text-xs text-zinc-500 font-mono block truncate">
               {cmd.command}
             </code>
+            {execStates[cmd.id] && (() => {
+              const state = execStates[cmd.id];
+              const result = !state.running ? state.result : undefined;
+              const failed = !state.running && (!!state.error || !!result?.exitSignal || !!result?.exitStatus);
+              return (
+                <div className="mt-1.5 rounded-md border border-zinc-200 dark:border-white/10 bg-zinc-100 dark:bg-zinc-800/60">
+                  <div className="flex items-center justify-between px-2 py-1 text-[10px] text-zinc-500">
+                    <span className={failed ? 'text-red-600 dark:text-red-400' : undefined}>
+                      {state.running
+                        ? 'Ejecutando…'
+                        : state.error
+                          ? state.error
+                          : `${result?.exitSignal ? `señal ${result.exitSignal}` : `código ${result?.exitStatus ?? '?'}`} · ${result?.durationMs} ms${
+                              result?.truncated ? ' · salida recortada' : ''
+                            }`}
+                    </span>
+                    {!state.running && (
+                      <button onClick={() => dismissExec(cmd.id)} className="p-0.5 rounded hover:text-zinc-900 dark:hover:text-white" title="Cerrar">
+                        <X className="w-3 h-3" />
+                      </button>
+                    )}
+                  </div>
+                  {result && (result.stdout || result.stderr) && (
+                    <pre className="max-h-48 overflow-auto px-2 pb-1.5 text-[11px] font-mono whitespace-pre-wrap break-all text-zinc-700 dark:text-zinc-300">
+                      {result.stdout}
+                      {result.stderr && <span className="text-red-600 dark:text-red-400">{result.stderr}</span>}
+                    </pre>
+                  )}
+                </div>
+              );
+            })()}
           </div>
         ))}
 
@@ -0,0 +1,115 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { ShieldAlert } from 'lucide-react';
+import { useShallow } from 'zustand/react/shallow';
+import { useStore } from '../store/useStore';
+import { errorMessage } from '../utils/appError';
+
+// OpenSSH-style "REMOTE HOST IDENTIFICATION HAS CHANGED" warning, opened by
+// sshService when ssh_connect fails with a hostKeyChanged payload. Replacing
+// the key trusts exactly the new fingerprint shown here.
+export function HostKeyChangedDialog() {
+  const { hostKeyModal, closeHostKeyModal, addToast } = useStore(
+    useShallow((s) => ({
+      hostKeyModal: s.hostKeyModal,
+      closeHostKeyModal: s.closeHostKeyModal,
+      addToast: s.addToast,
+    }))
+  );
+  const [busy, setBusy] = useState(false);
+
+  const change = hostKeyModal.data;
+  if (!hostKeyModal.isOpen || !change) return null;
+
+  const replace = async () => {
+    setBusy(true);
+    try {
+      await invoke('ssh_replace_host_key', {
+        host: change.host,
+        port: change.port,
+        fingerprint: change.newFingerprint,
+      });
+      closeHostKeyModal();
+      addToast({
+        type: 'success',
+        title: 'Host key reemplazada',
+        message: 'Pulsa Reconectar para conectar con la nueva clave.',
+      });
+    } catch (err) {
+      console.error('ssh_replace_host_key failed:', err);
+      addToast({ type: 'error', title: 'Error', message: errorMessage(err) });
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  return (
+    <div className="fixed inset-0 z-[100] flex items-center justify-center p-4">
+      <button
+        type="button"
+        className="absolute inset-0 bg-black/50"
+        onClick={closeHostKeyModal}
+        aria-label="Cancelar"
+        disabled={busy}
+      />
+      <div
+        role="alertdialog"
+        aria-modal="true"
+        className="relative w-full max-w-md rounded-lg border border-zinc-300 bg-white p-4 shadow-2xl dark:border-zinc-700 dark:bg-zinc-900"
+      >
+        <div className="mb-4 flex items-start gap-3">
+          <div className="mt-0.5 flex h-9 w-9 shrink-0 items-center justify-center rounded-md bg-red-500/10 text-red-600 dark:text-red-400">
+            <ShieldAlert className="h-5 w-5" />
+          </div>
+          <div className="min-w-0">
+            <h2 className="text-sm font-semibold uppercase text-red-600 dark:text-red-400">
+              Remote host identification has changed!
+            </h2>
+            <p className="mt-1 text-sm leading-5 text-zinc-600 dark:text-zinc-400">
+              La clave de {change.host}:{change.port} no coincide con la guardada. Alguien podría
+              estar interceptando la conexión (man-in-the-middle), o el servidor se ha reinstalado.
+            </p>
+          </div>
+        </div>
+        <dl className="mb-4 space-y-2 rounded-md bg-zinc-100 p-3 text-xs dark:bg-zinc-800">
+          <div>
+            <dt className="text-zinc-500">Clave guardada ({change.keyType})</dt>
+            <dd className="break-all font-mono text-zinc-900 dark:text-zinc-100">
+              {change.oldFingerprint}
+            </dd>
+          </div>
+          <div>
+            <dt className="text-zinc-500">Clave recibida ({change.keyType})</dt>
+            <dd className="break-all font-mono text-zinc-900 dark:text-zinc-100">
+              {change.newFingerprint}
+            </dd>
+          </div>
+          <div>
+            <dt className="text-zinc-500">Entrada</dt>
+            <dd className="break-all font-mono text-zinc-900 dark:text-zinc-100">
+              {change.knownHosts}:{change.line}
+            </dd>
+          </div>
+        </dl>
+        <div className="flex justify-end gap-2">
+          <button
+            type="button"
+            onClick={closeHostKeyModal}
+            disabled={busy}
+            className="rounded-md px-3 py-1.5 text-sm text-zinc-700 transition-colors hover:bg-zinc-100 dark:text-zinc-300 dark:hover:bg-zinc-800"
+          >
+            Cancelar
+          </button>
+          <button
+            type="button"
+            onClick={replace}
+            disabled={busy}
+            className="rounded-md bg-red-600 px-3 py-1.5 text-sm font-medium text-white transition-colors hover:bg-red-700 disabled:opacity-50"
+          >
+            Reemplazar clave
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,5 @@
 import { motion, AnimatePresence } from 'framer-motion';
-import { X, Monitor, Moon, Sun, Palette, Laptop, Type, TerminalSquare, History, ScrollText } from 'lucide-react';
+import { X, Monitor, Moon, Sun, Palette, Laptop, Type, TerminalSquare, History, ScrollText, Network } from 'lucide-react';
 import { useShallow } from 'zustand/react/shallow';
 import { useStore } from '../store/useStore';
 import { useTheme } from '../contexts/ThemeContext';
@@ -436,6 +436,284 @@ export function SettingsModal() {
                 />
               </span>
             </button>
+
+            {/* Transcripts of the sessions with "Guardar registro en archivo" */}
+            <button
+              onClick={() => updateSettings({ transcriptInput: !(settings.transcriptInput ?? false) })}
+              className={`flex w-full items-center justify-between rounded-xl border p-3 text-left transition-all ${
+                isDark
+                  ? 'border-white/10 bg-white/5 hover:border-white/20'
+                  : 'border-zinc-200 bg-zinc-50 hover:border-zinc-300'
+              }`}
+            >
+              <div className="flex flex-col gap-0.5 pr-3">
+                <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                  Incluir lo tecleado en los archivos de registro
+                </span>
+                <span className={`text-xs ${isDark ? 'text-zinc-400' : 'text-zinc-500'}`}>
+                  Cada línea escrita queda con su hora. Las respuestas a un prompt de contraseña
+                  nunca se escriben.
+                </span>
+              </div>
+              <span
+                className={`relative h-6 w-11 flex-shrink-0 rounded-full transition-colors ${
+                  (settings.transcriptInput ?? false) ? 'bg-cyan-500' : isDark ? 'bg-zinc-600' : 'bg-zinc-300'
+                }`}
+              >
+                <span
+                  className={`absolute top-0.5 h-5 w-5 rounded-full bg-white shadow transition-transform ${
+                    (settings.transcriptInput ?? false) ? 'translate-x-[22px]' : 'translate-x-0.5'
+                  }`}
+                />
+              </span>
+            </button>
+
+            <button
+              onClick={() => updateSettings({ transcriptPlainText: !(settings.transcriptPlainText ?? false) })}
+              className={`flex w-full items-center justify-between rounded-xl border p-3 text-left transition-all ${
+                isDark
+                  ? 'border-white/10 bg-white/5 hover:border-white/20'
+                  : 'border-zinc-200 bg-zinc-50 hover:border-zinc-300'
+              }`}
+            >
+              <div className="flex flex-col gap-0.5 pr-3">
+                <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                  Registro en texto plano
+                </span>
+                <span className={`text-xs ${isDark ? 'text-zinc-400' : 'text-zinc-500'}`}>
+                  Sin colores ni secuencias de escape: lo que se vio en pantalla, legible en cualquier
+                  editor (.txt). Los programas a pantalla completa quedan en una línea.
+                </span>
+              </div>
+              <span
+                className={`relative h-6 w-11 flex-shrink-0 rounded-full transition-colors ${
+                  (settings.transcriptPlainText ?? false) ? 'bg-cyan-500' : isDark ? 'bg-zinc-600' : 'bg-zinc-300'
+                }`}
+              >
+                <span
+                  className={`absolute top-0.5 h-5 w-5 rounded-full bg-white shadow transition-transform ${
+                    (settings.transcriptPlainText ?? false) ? 'translate-x-[22px]' : 'translate-x-0.5'
+                  }`}
+                />
+              </span>
+            </button>
+
+            <label
+              className={`flex items-center justify-between rounded-xl border p-3 ${
+                isDark ? 'border-white/10 bg-white/5' : 'border-zinc-200 bg-zinc-50'
+              }`}
+            >
+              <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                Tamaño máximo de cada archivo (MB)
+              </span>
+              <input
+                type="number"
+                min={1}
+                max={1024}
+                value={settings.transcriptMaxMb ?? 10}
+                onChange={(e) =>
+                  updateSettings({ transcriptMaxMb: Math.min(1024, Math.max(1, parseInt(e.target.value) || 10)) })
+                }
+                className={`w-20 rounded-lg border px-2 py-1 text-sm ${
+                  isDark ? 'border-white/10 bg-zinc-800 text-white' : 'border-zinc-200 bg-white text-zinc-900'
+                }`}
+              />
+            </label>
+
+            <label
+              className={`flex items-center justify-between rounded-xl border p-3 ${
+                isDark ? 'border-white/10 bg-white/5' : 'border-zinc-200 bg-zinc-50'
+              }`}
+              title="Al recargar la ventana, los terminales que siguen abiertos se repintan con esta salida"
+            >
+              <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                Salida guardada por terminal (KB, 0 = nada)
+              </span>
+              <input
+                type="number"
+                min={0}
+                max={16384}
+                value={settings.outputBufferKb ?? 512}
+                onChange={(e) => {
+                  const kb = parseInt(e.target.value);
+                  updateSettings({ outputBufferKb: Math.min(16384, Math.max(0, isNaN(kb) ? 512 : kb)) });
+                }}
+                className={`w-20 rounded-lg border px-2 py-1 text-sm ${
+                  isDark ? 'border-white/10 bg-zinc-800 text-white' : 'border-zinc-200 bg-white text-zinc-900'
+                }`}
+              />
+            </label>
+
+            {/* Debugging escape hatch: off unless someone needs the keystrokes */}
+            <button
+              onClick={() => updateSettings({ verboseInputLog: !(settings.verboseInputLog ?? false) })}
+              className={`flex w-full items-center justify-between rounded-xl border p-3 text-left transition-all ${
+                isDark
+                  ? 'border-white/10 bg-white/5 hover:border-white/20'
+                  : 'border-zinc-200 bg-zinc-50 hover:border-zinc-300'
+              }`}
+            >
+              <div className="flex flex-col gap-0.5 pr-3">
+                <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                  Registro detallado de lo tecleado (depuración)
+                </span>
+                <span className={`text-xs ${isDark ? 'text-zinc-400' : 'text-zinc-500'}`}>
+                  El log de la aplicación guarda el texto enviado, contraseñas incluidas. Solo para
+                  depurar; se aplica a las conexiones nuevas.
+                </span>
+              </div>
+              <span
+                className={`relative h-6 w-11 flex-shrink-0 rounded-full transition-colors ${
+                  (settings.verboseInputLog ?? false) ? 'bg-red-500' : isDark ? 'bg-zinc-600' : 'bg-zinc-300'
+                }`}
+              >
+                <span
+                  className={`absolute top-0.5 h-5 w-5 rounded-full bg-white shadow transition-transform ${
+                    (settings.verboseInputLog ?? false) ? 'translate-x-[22px]' : 'translate-x-0.5'
+                  }`}
+                />
+              </span>
+            </button>
+          </div>
+
+          {/* Connections */}
+          <div className="mt-6 space-y-4">
+            <div className={`flex items-center gap-2 text-sm font-medium ${isDark ? 'text-zinc-300' : 'text-zinc-700'}`}>
+              <Network className="h-4 w-4" />
+              <span>Conexiones</span>
+            </div>
+
+            <button
+              onClick={() => updateSettings({ reuseConnections: !(settings.reuseConnections ?? true) })}
+              className={`flex w-full items-center justify-between rounded-xl border p-3 text-left transition-all ${
+                isDark
+                  ? 'border-white/10 bg-white/5 hover:border-white/20'
+                  : 'border-zinc-200 bg-zinc-50 hover:border-zinc-300'
+              }`}
+            >
+              <div className="flex flex-col gap-0.5 pr-3">
+                <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                  Reutilizar conexiones abiertas
+                </span>
+                <span className={`text-xs ${isDark ? 'text-zinc-400' : 'text-zinc-500'}`}>
+                  Otra pestaña al mismo host y usuario abre un canal sobre la conexión existente,
+                  sin volver a autenticarse (evita bloqueos de fail2ban y los saltos lentos).
+                </span>
+              </div>
+              <span
+                className={`relative h-6 w-11 flex-shrink-0 rounded-full transition-colors ${
+                  (settings.reuseConnections ?? true) ? 'bg-cyan-500' : isDark ? 'bg-zinc-600' : 'bg-zinc-300'
+                }`}
+              >
+                <span
+                  className={`absolute top-0.5 h-5 w-5 rounded-full bg-white shadow transition-transform ${
+                    (settings.reuseConnections ?? true) ? 'translate-x-[22px]' : 'translate-x-0.5'
+                  }`}
+                />
+              </span>
+            </button>
+
+            <button
+              onClick={() => updateSettings({ bracketedPaste: !(settings.bracketedPaste ?? true) })}
+              className={`flex w-full items-center justify-between rounded-xl border p-3 text-left transition-all ${
+                isDark
+                  ? 'border-white/10 bg-white/5 hover:border-white/20'
+                  : 'border-zinc-200 bg-zinc-50 hover:border-zinc-300'
+              }`}
+            >
+              <div className="flex flex-col gap-0.5 pr-3">
+                <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                  Pegado protegido (bracketed paste)
+                </span>
+                <span className={`text-xs ${isDark ? 'text-zinc-400' : 'text-zinc-500'}`}>
+                  Al pegar textos grandes, si el shell lo admite, llegan como un único bloque y no se
+                  ejecuta cada línea al recibirla. Se envían por partes y se pueden cancelar.
+                </span>
+              </div>
+              <span
+                className={`relative h-6 w-11 flex-shrink-0 rounded-full transition-colors ${
+                  (settings.bracketedPaste ?? true) ? 'bg-cyan-500' : isDark ? 'bg-zinc-600' : 'bg-zinc-300'
+                }`}
+              >
+                <span
+                  className={`absolute top-0.5 h-5 w-5 rounded-full bg-white shadow transition-transform ${
+                    (settings.bracketedPaste ?? true) ? 'translate-x-[22px]' : 'translate-x-0.5'
+                  }`}
+                />
+              </span>
+            </button>
+
+            <label
+              className={`flex items-center justify-between rounded-xl border p-3 ${
+                isDark ? 'border-white/10 bg-white/5' : 'border-zinc-200 bg-zinc-50'
+              }`}
+            >
+              <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                Silencio que indica el prompt (ms)
+              </span>
+              <input
+                type="number"
+                min={100}
+                max={120000}
+                step={100}
+                value={settings.shellReadyQuietMs ?? 800}
+                onChange={(e) =>
+                  updateSettings({ shellReadyQuietMs: Math.min(120000, Math.max(100, parseInt(e.target.value) || 800)) })
+                }
+                className={`w-24 rounded-lg border px-2 py-1 text-sm ${
+                  isDark ? 'border-white/10 bg-zinc-800 text-white' : 'border-zinc-200 bg-white text-zinc-900'
+                }`}
+              />
+            </label>
+
+            <label
+              className={`flex items-center justify-between rounded-xl border p-3 ${
+                isDark ? 'border-white/10 bg-white/5' : 'border-zinc-200 bg-zinc-50'
+              }`}
+            >
+              <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                Espera máxima del prompt (ms)
+              </span>
+              <input
+                type="number"
+                min={1000}
+                max={120000}
+                step={100}
+                value={settings.shellReadyTimeoutMs ?? 15000}
+                onChange={(e) =>
+                  updateSettings({ shellReadyTimeoutMs: Math.min(120000, Math.max(1000, parseInt(e.target.value) || 15000)) })
+                }
+                className={`w-24 rounded-lg border px-2 py-1 text-sm ${
+                  isDark ? 'border-white/10 bg-zinc-800 text-white' : 'border-zinc-200 bg-white text-zinc-900'
+                }`}
+              />
+            </label>
+
+            <label
+              className={`flex items-center justify-between rounded-xl border p-3 ${
+                isDark ? 'border-white/10 bg-white/5' : 'border-zinc-200 bg-zinc-50'
+              }`}
+              title="Sin salida ni respuesta a los keepalives durante este tiempo, se comprueba la conexión; si no contesta se da por perdida (y se reconecta)"
+            >
+              <span className={`text-sm font-medium ${isDark ? 'text-zinc-200' : 'text-zinc-800'}`}>
+                Vigilancia de red (s, 0 = desactivada)
+              </span>
+              <input
+                type="number"
+                min={0}
+                max={3600}
+                value={settings.networkWatchdogSecs ?? 60}
+                onChange={(e) => {
+                  const secs = parseInt(e.target.value);
+                  updateSettings({
+                    networkWatchdogSecs: isNaN(secs) ? 60 : secs <= 0 ? 0 : Math.min(3600, Math.max(10, secs)),
+                  });
+                }}
+                className={`w-24 rounded-lg border px-2 py-1 text-sm ${
+                  isDark ? 'border-white/10 bg-zinc-800 text-white' : 'border-zinc-200 bg-white text-zinc-900'
+                }`}
+              />
+            </label>
           </div>
 
           </div>
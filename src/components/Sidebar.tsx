@@ -17,11 +17,14 @@ import {
   Search,
   Download,
   Network,
+  Power,
+  SquareTerminal,
   X,
 } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
 import { useShallow } from 'zustand/react/shallow';
 import { useStore } from '../store/useStore';
+import { sshService } from '../hooks/sshService';
 import { AnchoredMenu } from './AnchoredMenu';
 import { NoteBadge } from './NoteBadge';
 import { ConfirmDialog } from './ConfirmDialog';
@@ -243,6 +246,20 @@ function SessionItem({
             <Edit2 className="w-3.5 h-3.5" />
             Editar
           </button>
+          {session.wolMacAddress && (
+            <button
+              onPointerDown={(e) => e.stopPropagation()}
+              onClick={(e) => {
+                e.stopPropagation();
+                sshService.sendWakeOnLan(session);
+                setMenuAnchor(null);
+              }}
+              className="w-full px-3 py-1.5 text-left text-sm text-zinc-700 dark:text-zinc-300 hover:bg-zinc-200 dark:hover:bg-zinc-700 flex items-center gap-2"
+            >
+              <Power className="w-3.5 h-3.5" />
+              Despertar (Wake-on-LAN)
+            </button>
+          )}
           <button
             onPointerDown={(e) => e.stopPropagation()}
             onClick={(e) => {
@@ -663,6 +680,7 @@ export function Sidebar() {
     openInfoModal,
     openSettingsModal,
     createTab,
+    createLocalTab,
     addSession,
     addToast,
     addGroup,
@@ -687,6 +705,7 @@ export function Sidebar() {
       openInfoModal: s.openInfoModal,
       openSettingsModal: s.openSettingsModal,
       createTab: s.createTab,
+      createLocalTab: s.createLocalTab,
       addSession: s.addSession,
       addToast: s.addToast,
       addGroup: s.addGroup,
@@ -1147,6 +1166,13 @@ export function Sidebar() {
               >
                 <Download className="w-4 h-4" />
               </button>
+              <button
+                onClick={() => createLocalTab()}
+                className="p-1.5 rounded-md hover:bg-zinc-200 dark:hover:bg-zinc-800 text-zinc-500 hover:text-zinc-700 dark:hover:text-zinc-300 transition-colors"
+                title="Terminal local"
+              >
+                <SquareTerminal className="w-4 h-4" />
+              </button>
               <button
                 onClick={() => openGroupModal({ mode: 'create', parentId: null })}
                 className="p-1.5 rounded-md hover:bg-zinc-200 dark:hover:bg-zinc-800 text-zinc-500 hover:text-zinc-700 dark:hover:text-zinc-300 transition-colors"
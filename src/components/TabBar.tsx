@@ -1,13 +1,15 @@
 import { useEffect, useRef, useState } from 'react';
 import { motion, AnimatePresence } from 'framer-motion';
-import { Terminal, X, ChevronDown, Unplug, XCircle, CopyX, Network } from 'lucide-react';
+import { Terminal, X, ChevronDown, Unplug, XCircle, CopyX, Network, Radio, FileText, Circle } from 'lucide-react';
 import { useShallow } from 'zustand/react/shallow';
 import { useStore } from '../store/useStore';
 import { sshService } from '../hooks/sshService';
 import { AnchoredMenu } from './AnchoredMenu';
+import { formatRate, sparklinePoints } from '../utils/throughput';
+import type { Session, TerminalTab } from '../types';
 
 export function TabBar() {
-  const { tabs, activeTabId, sessions, setActiveTab, closeTab, openInfoModal } = useStore(
+  const { tabs, activeTabId, sessions, setActiveTab, closeTab, openInfoModal, toggleTabBroadcast } = useStore(
     useShallow((s) => ({
       tabs: s.tabs,
       activeTabId: s.activeTabId,
@@ -15,6 +17,7 @@ export function TabBar() {
       setActiveTab: s.setActiveTab,
       closeTab: s.closeTab,
       openInfoModal: s.openInfoModal,
+      toggleTabBroadcast: s.toggleTabBroadcast,
     }))
   );
 
@@ -43,6 +46,24 @@ export function TabBar() {
     return sessions.find((s) => s.id === sessionId);
   };
 
+  // Las pestañas locales no tienen sesión: su nombre es el de la shell
+  const tabName = (tab: TerminalTab, session?: Session) =>
+    tab.local ? tab.title : session?.name || 'Unknown';
+  const tabTooltip = (tab: TerminalTab, session?: Session) =>
+    tab.local
+      ? `Terminal local — ${tab.shell ?? tab.title}`
+      : session
+        ? `${session.name} — ${session.username}@${session.host}:${session.port}`
+        : 'Unknown';
+
+  // Verde < 100 ms, ámbar < 300 ms, rojo por encima
+  const latencyColor = (ms: number) =>
+    ms < 100
+      ? 'text-green-600 dark:text-green-400'
+      : ms < 300
+        ? 'text-amber-600 dark:text-amber-400'
+        : 'text-red-600 dark:text-red-400';
+
   const statusDot = (status: string) => {
     const colors = {
       idle: 'bg-zinc-500',
@@ -77,7 +98,7 @@ export function TabBar() {
                 initial={{ opacity: 0, scale: 0.9 }}
                 animate={{ opacity: 1, scale: 1 }}
                 exit={{ opacity: 0, scale: 0.9 }}
-                title={session ? `${session.name} — ${session.username}@${session.host}:${session.port}` : 'Unknown'}
+                title={tabTooltip(tab, session)}
                 onClick={() => setActiveTab(tab.id)}
                 onContextMenu={(e) => {
                   e.preventDefault();
@@ -97,10 +118,46 @@ export function TabBar() {
                 `}
               >
                 <div className={`w-2 h-2 rounded-full shrink-0 ${statusDot(tab.status)}`} />
-                <Terminal className="w-3.5 h-3.5 flex-shrink-0" />
+                {tab.broadcast ? (
+                  <Radio className="w-3.5 h-3.5 flex-shrink-0 text-orange-500" aria-label="Difusión activa" />
+                ) : (
+                  <Terminal className="w-3.5 h-3.5 flex-shrink-0" />
+                )}
                 <span className="truncate flex-1 text-left">
-                  {session?.name || 'Unknown'}
+                  {tabName(tab, session)}
                 </span>
+                {tab.recordingPath && (
+                  <Circle
+                    className="w-2.5 h-2.5 shrink-0 fill-red-500 text-red-500 animate-pulse"
+                    aria-label="Grabando"
+                  />
+                )}
+                {tab.status === 'connected' && tab.activity && tab.activity.length > 1 && (
+                  <svg
+                    width={24}
+                    height={10}
+                    className="shrink-0 text-sky-500 dark:text-sky-400"
+                    aria-label="Actividad"
+                  >
+                    <title>
+                      {`↑ ${formatRate(tab.sentPerSec ?? 0)}  ↓ ${formatRate(tab.receivedPerSec ?? 0)}`}
+                    </title>
+                    <polyline
+                      points={sparklinePoints(tab.activity, 24, 10)}
+                      fill="none"
+                      stroke="currentColor"
+                      strokeWidth={1}
+                    />
+                  </svg>
+                )}
+                {tab.status === 'connected' && tab.latencyMs !== undefined && (
+                  <span
+                    className={`text-[10px] tabular-nums shrink-0 ${latencyColor(tab.latencyMs)}`}
+                    title="Latencia (RTT)"
+                  >
+                    {tab.latencyMs} ms
+                  </span>
+                )}
                 <span
                   role="button"
                   tabIndex={-1}
@@ -144,7 +201,7 @@ export function TabBar() {
             return (
               <button
                 key={tab.id}
-                title={session ? `${session.name} — ${session.username}@${session.host}:${session.port}` : 'Unknown'}
+                title={tabTooltip(tab, session)}
                 onClick={() => {
                   setActiveTab(tab.id);
                   setMenuAnchor(null);
@@ -156,7 +213,7 @@ export function TabBar() {
                 }`}
               >
                 <span className={`w-2 h-2 rounded-full shrink-0 ${statusDot(tab.status)}`} />
-                <span className="truncate flex-1">{session?.name || 'Unknown'}</span>
+                <span className="truncate flex-1">{tabName(tab, session)}</span>
                 <span
                   role="button"
                   tabIndex={-1}
@@ -217,6 +274,56 @@ export function TabBar() {
                 Desconectar
               </button>
             )}
+            <button
+              onClick={() => {
+                toggleTabBroadcast(ctxTab.id);
+                setCtxMenu(null);
+              }}
+              className={itemClass}
+              title="Lo que se escribe en una pestaña con difusión llega a todas ellas"
+            >
+              <Radio className="w-3.5 h-3.5" />
+              {ctxTab.broadcast ? 'Quitar de la difusión' : 'Difundir escritura'}
+            </button>
+            <button
+              disabled={ctxTab.status !== 'connected' || ctxTab.local}
+              onClick={() => {
+                sshService.setLogging(ctxTab.id, !ctxTab.logPath);
+                setCtxMenu(null);
+              }}
+              className={itemClass}
+              title={ctxTab.logPath ?? 'Guarda la salida del terminal en la carpeta logs'}
+            >
+              <FileText className="w-3.5 h-3.5" />
+              {ctxTab.logPath ? 'Dejar de registrar' : 'Registrar en archivo'}
+            </button>
+            <button
+              disabled={ctxTab.status !== 'connected' || ctxTab.local}
+              onClick={() => {
+                if (ctxTab.recordingPath) {
+                  sshService.stopRecording(ctxTab.id);
+                } else {
+                  sshService.startRecording(ctxTab.id);
+                }
+                setCtxMenu(null);
+              }}
+              className={itemClass}
+              title={ctxTab.recordingPath ?? 'Graba la sesión en formato asciinema (.cast), sin lo tecleado'}
+            >
+              <Circle className="w-3.5 h-3.5" />
+              {ctxTab.recordingPath ? 'Detener grabación' : 'Grabar sesión (asciinema)'}
+            </button>
+            <button
+              disabled={!tabs.some((t) => t.status === 'connected')}
+              onClick={() => {
+                sshService.disconnectAll();
+                setCtxMenu(null);
+              }}
+              className={itemClass}
+            >
+              <Unplug className="w-3.5 h-3.5" />
+              Desconectar todas
+            </button>
             <button
               onClick={() => {
                 closeMany([ctxTab.id]);
@@ -1,9 +1,9 @@
 import { useEffect, useRef, useCallback, useState } from 'react';
-import { X, Circle, RefreshCw, StopCircle, Copy, ClipboardList, Search, ChevronUp, ChevronDown } from 'lucide-react';
+import { X, Circle, RefreshCw, StopCircle, ListX, Copy, ClipboardList, Search, ChevronUp, ChevronDown } from 'lucide-react';
 import { useShallow } from 'zustand/react/shallow';
 import { useStore } from '../store/useStore';
 import { useTerminal } from '../hooks/useTerminal';
-import { sshService } from '../hooks/sshService';
+import { sshService, type OutputCallback } from '../hooks/sshService';
 
 interface TerminalViewProps {
   tabId: string;
@@ -12,6 +12,10 @@ interface TerminalViewProps {
 // Max buffer size: 500KB to prevent memory issues on long sessions
 const MAX_BUFFER_SIZE = 500 * 1024;
 
+// Pastes longer than this go through ssh_paste (chunked, cancellable)
+// instead of a single ssh_send
+const LARGE_PASTE = 4 * 1024;
+
 // Base font size in px per settings option (zoom multiplies on top)
 const FONT_SIZES = { small: 12, medium: 14, large: 16 } as const;
 
@@ -56,11 +60,38 @@ export function TerminalView({ tabId }: TerminalViewProps) {
 
   const handleData = useCallback((data: string) => {
     const channelId = currentChannelRef.current;
-    if (channelId) {
+    if (!channelId) return;
+    // Broadcast tab: what is typed goes to every connected broadcast tab
+    const { tabs } = useStore.getState();
+    const targets = tabs.find((t) => t.id === tabId)?.broadcast
+      ? tabs
+          .filter((t) => t.broadcast && t.status === 'connected' && t.channelId)
+          .map((t) => t.channelId!)
+      : [];
+    if (targets.length > 1) {
+      sshService.sendBroadcast(targets, data);
+    } else {
       sshService.send(channelId, data);
     }
+  }, [tabId]);
+
+  // Binary input is mouse reports: for this terminal only, never broadcast
+  const handleBinary = useCallback((data: Uint8Array) => {
+    const channelId = currentChannelRef.current;
+    if (channelId) sshService.sendBytes(channelId, data);
   }, []);
 
+  // Broadcast tabs keep the normal path: the paste goes to every tab at once
+  const handlePaste = useCallback((text: string, bracketed: boolean) => {
+    const { tabs, settings } = useStore.getState();
+    const currentTab = tabs.find((t) => t.id === tabId);
+    if (text.length < LARGE_PASTE || !currentTab?.channelId || currentTab.broadcast) {
+      return false;
+    }
+    void sshService.paste(tabId, text, bracketed && settings?.bracketedPaste !== false);
+    return true;
+  }, [tabId]);
+
   const handleResize = useCallback(
     (cols: number, rows: number) => {
       const channelId = currentChannelRef.current;
@@ -80,6 +111,8 @@ export function TerminalView({ tabId }: TerminalViewProps) {
 
   const { initTerminal, write, writeln, focus, fit, getSize, getBufferText, getLastBlock, scrollToBottom, applyOptions, findNext, findPrevious, clearSearch } = useTerminal({
     onData: handleData,
+    onBinary: handleBinary,
+    onPaste: handlePaste,
     onResize: handleResize,
     fontSize,
     background: terminalBackground,
@@ -101,7 +134,7 @@ export function TerminalView({ tabId }: TerminalViewProps) {
     const state = useStore.getState();
     const currentTab = state.tabs.find((t) => t.id === tabId);
     const currentSession = state.sessions.find((s) => s.id === currentTab?.sessionId);
-    if (!containerRef.current || !currentTab || !currentSession) return;
+    if (!containerRef.current || !currentTab || (!currentSession && !currentTab.local)) return;
 
     const cleanup = initTerminal(containerRef.current);
 
@@ -121,8 +154,8 @@ export function TerminalView({ tabId }: TerminalViewProps) {
       bufferRef.current = '';
     }
 
-    const onDataCallback = (data: string) => {
-      write(data);
+    const onDataCallback: OutputCallback = (data, done) => {
+      write(data, done);
       bufferRef.current = trimBuffer(bufferRef.current + data, MAX_BUFFER_SIZE);
     };
 
@@ -138,8 +171,10 @@ export function TerminalView({ tabId }: TerminalViewProps) {
         });
         focus();
 
-        // Enable auto-reconnect
-        sshService.enableAutoReconnect(tabId, currentSession, size.cols, size.rows, onDataCallback);
+        // Enable auto-reconnect (SSH only: a local shell that ends is done)
+        if (currentSession) {
+          sshService.enableAutoReconnect(tabId, currentSession, size.cols, size.rows, onDataCallback);
+        }
       }, 200);
     };
 
@@ -154,20 +189,29 @@ export function TerminalView({ tabId }: TerminalViewProps) {
 
       const { cols, rows } = getSize();
 
-      const connectMsg = `\x1b[33mConnecting to ${currentSession.name} (${currentSession.host})...\x1b[0m\r\n`;
+      const connectMsg = currentSession
+        ? `\x1b[33mConnecting to ${currentSession.name} (${currentSession.host})...\x1b[0m\r\n`
+        : `\x1b[33mStarting local shell...\x1b[0m\r\n`;
       write(connectMsg);
       bufferRef.current += connectMsg;
 
-      const channelId = await sshService.connect(tabId, currentSession, cols, rows);
+      const channelId = currentSession
+        ? await sshService.connect(tabId, currentSession, cols, rows)
+        : await sshService.connectLocal(tabId, cols, rows);
 
       if (channelId) {
         attachToChannel(channelId);
+      } else if (useStore.getState().tabs.find((t) => t.id === tabId)?.status === 'disconnected') {
+        // Cancelled with the stop button
+        writeln(`\x1b[33mConnection cancelled.\x1b[0m`);
       } else {
         writeln(`\x1b[31mConnection failed. Click reconnect to retry.\x1b[0m`);
         useStore.getState().addToast({
           type: 'error',
           title: 'Conexión fallida',
-          message: 'No se pudo establecer la sesión SSH',
+          message: currentSession
+            ? 'No se pudo establecer la sesión SSH'
+            : 'No se pudo abrir la shell local',
           duration: 3000,
         });
       }
@@ -243,22 +287,28 @@ export function TerminalView({ tabId }: TerminalViewProps) {
   };
 
   const handleReconnect = async () => {
-    if (!session || !tab) return;
+    if (!tab || (!session && !tab.local)) return;
 
     if (tab.channelId) {
       await sshService.disconnect(tabId, tab.channelId);
     }
 
-    writeln(`\r\n\x1b[33mReconnecting to ${session.name} (${session.host})...\x1b[0m`);
+    writeln(
+      session
+        ? `\r\n\x1b[33mReconnecting to ${session.name} (${session.host})...\x1b[0m`
+        : `\r\n\x1b[33mStarting local shell...\x1b[0m`
+    );
     updateTabStatus(tabId, 'connecting');
 
     const { cols, rows } = getSize();
-    const channelId = await sshService.connect(tabId, session, cols, rows);
+    const channelId = session
+      ? await sshService.connect(tabId, session, cols, rows)
+      : await sshService.connectLocal(tabId, cols, rows);
     if (channelId) {
       currentChannelRef.current = channelId;
       setTimeout(() => fit(), 100);
-      sshService.startReading(channelId, (data) => {
-        write(data);
+      sshService.startReading(channelId, (data, done) => {
+        write(data, done);
         bufferRef.current = trimBuffer(bufferRef.current + data, MAX_BUFFER_SIZE);
       });
     } else {
@@ -267,6 +317,10 @@ export function TerminalView({ tabId }: TerminalViewProps) {
   };
 
   const handleStop = async () => {
+    if (tab?.status === 'connecting' && !tab.channelId) {
+      await sshService.cancelConnect(tabId);
+      return;
+    }
     if (!tab?.channelId) return;
 
     await sshService.disconnect(tabId, tab.channelId);
@@ -288,6 +342,7 @@ export function TerminalView({ tabId }: TerminalViewProps) {
       .filter((line) => !/^last login/i.test(line.trim()))
       .filter((line) => !/^Connecting to /i.test(line.trim()))
       .filter((line) => !/^Reconnecting to /i.test(line.trim()))
+      .filter((line) => !/^Starting local shell/i.test(line.trim()))
       .join('\n')
       .trim();
   };
@@ -336,7 +391,7 @@ export function TerminalView({ tabId }: TerminalViewProps) {
     }
   };
 
-  if (!tab || !session) return null;
+  if (!tab || (!session && !tab.local)) return null;
 
   const statusConfig = {
     idle: { color: 'text-zinc-500', label: 'Inactivo' },
@@ -365,20 +420,30 @@ export function TerminalView({ tabId }: TerminalViewProps) {
               {tab.title}
             </span>
             <span className="text-xs text-zinc-500">
-              {session.username}@{session.host}:{session.port}
+              {session ? `${session.username}@${session.host}:${session.port}` : tab.shell}
             </span>
           </div>
           <div className="flex items-center gap-1">
-            {isConnected && (
+            {(isConnected || tab.status === 'connecting') && (
               <button
                 onClick={handleStop}
-                aria-label="Desconectar"
+                aria-label={isConnected ? 'Desconectar' : 'Cancelar conexión'}
                 className="p-1.5 rounded-lg transition-colors hover:bg-zinc-900/5 dark:hover:bg-white/5 text-zinc-600 dark:text-zinc-400 hover:text-orange-600 dark:hover:text-orange-400"
-                title="Desconectar"
+                title={isConnected ? 'Desconectar' : 'Cancelar conexión'}
               >
                 <StopCircle className="w-4 h-4" />
               </button>
             )}
+            {isConnected && !tab.local && (
+              <button
+                onClick={() => sshService.discardOutput(tabId)}
+                aria-label="Descartar salida"
+                className="p-1.5 rounded-lg transition-colors hover:bg-zinc-900/5 dark:hover:bg-white/5 text-zinc-600 dark:text-zinc-400 hover:text-orange-600 dark:hover:text-orange-400"
+                title="Descartar salida: deja de mostrar la salida en curso hasta que se detenga (cat de un archivo enorme, yes)"
+              >
+                <ListX className="w-4 h-4" />
+              </button>
+            )}
             <button
               onClick={handleCopyOutput}
               aria-label="Copiar todo"
@@ -421,6 +486,27 @@ export function TerminalView({ tabId }: TerminalViewProps) {
         {/* Terminal Container: padding lives on the wrapper so FitAddon
             measures the inner element's exact content box */}
         <div className="relative flex-1 min-h-0 overflow-hidden px-2 pt-1 pb-2" onClick={() => focus()}>
+          {tab.pasteProgress !== undefined && (
+            <div
+              className="absolute bottom-3 right-4 z-10 flex items-center gap-2 px-3 py-1.5 rounded-lg border border-zinc-300 dark:border-white/10 bg-white/95 dark:bg-zinc-900/95 backdrop-blur-xl shadow-lg text-xs text-zinc-700 dark:text-zinc-300"
+              onClick={(e) => e.stopPropagation()}
+            >
+              <span>Pegando... {Math.round(tab.pasteProgress * 100)}%</span>
+              <div className="w-24 h-1.5 rounded bg-zinc-900/10 dark:bg-white/10 overflow-hidden">
+                <div
+                  className="h-full bg-blue-500 transition-[width]"
+                  style={{ width: `${Math.round(tab.pasteProgress * 100)}%` }}
+                />
+              </div>
+              <button
+                onClick={() => sshService.cancelPaste(tabId)}
+                className="px-1.5 py-0.5 rounded hover:bg-zinc-900/5 dark:hover:bg-white/10 text-zinc-600 dark:text-zinc-400 hover:text-red-600 dark:hover:text-red-400"
+                title="Cancelar pegado"
+              >
+                Cancelar
+              </button>
+            </div>
+          )}
           {searchOpen && (
             <div
               className="absolute top-2 right-4 z-10 flex items-center gap-1 px-2 py-1.5 rounded-lg border border-zinc-300 dark:border-white/10 bg-white/95 dark:bg-zinc-900/95 backdrop-blur-xl shadow-lg"
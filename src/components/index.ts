@@ -9,3 +9,5 @@ export { SessionInfoModal } from './SessionInfoModal';
 export { CommandPalette } from './CommandPalette';
 export { ToastContainer } from './ToastContainer';
 export { TitleBar } from './TitleBar';
+export { AuthPromptDialog } from './AuthPromptDialog';
+export { HostKeyChangedDialog } from './HostKeyChangedDialog';
@@ -2,21 +2,197 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { useStore } from '../store/useStore';
-import type { Session, ConnectParams } from '../types';
+import type {
+  AppError,
+  Session,
+  ConnectParams,
+  ConnectInfo,
+  LocalInfo,
+  ChannelSummary,
+  ChannelStats,
+  ConnectionInfo,
+  ExecResult,
+  QueueOutcome,
+  MultiExecSummary,
+  HostKeyChange,
+  RemoteTarget,
+  ForwardInfo,
+  ForwardKind,
+} from '../types';
 import { logSessionEvent, isPasswordPrompt } from '../utils/sessionLog';
+import { formatBytes } from '../utils/throughput';
+import { asAppError, errorMessage } from '../utils/appError';
 
 // How much recent output we keep per channel to feed the password-prompt guard
 const OUTPUT_TAIL_LIMIT = 256;
+// Tab activity sparklines: ssh_get_channel_stats poll period
+const STATS_POLL_MS = 2000;
+// Flow control: written output is acked (pty_ack) in batches of this much,
+// or after this delay; the backend pauses the channel past 1 MB unacked
+const ACK_BATCH = 64 * 1024;
+const ACK_DELAY_MS = 50;
+// Why remote X11 windows won't show (ConnectInfo.x11Forwarding)
+const X11_FAILURES: Record<Exclude<NonNullable<ConnectInfo['x11Forwarding']>, 'on'>, string> = {
+  no_display: 'DISPLAY is not set: start an X server (XQuartz, VcXsrv) and reconnect',
+  invalid_display: 'DISPLAY is not a valid X display name',
+  unreachable: 'The local X server is not answering on DISPLAY',
+  refused: 'The server does not allow X11 forwarding (X11Forwarding, xauth)',
+};
 
+// bytes: what to ack once written (absent for local terminals)
 interface PtyOutputPayload {
   channelId: string;
   data: string;
+  bytes?: number;
 }
 
+// Receives a channel's output; calls done() once the terminal has written it
+export type OutputCallback = (data: string, done: () => void) => void;
+
+// OSC 52 of an allowRemoteClipboard session: copied (length in chars) or
+// refused (read_denied, too_large, invalid, clipboard_error)
+interface ClipboardSetPayload {
+  channelId: string;
+  length: number;
+}
+
+interface ClipboardRefusedPayload {
+  channelId: string;
+  reason: string;
+}
+
+// A forward that could not listen again after a reconnect
+interface ForwardClosedPayload {
+  forwardId: string;
+  channelId: string;
+  error: string;
+}
+
+// The saved forwards of a session, started after its shell: one result each
+interface ForwardsStartedPayload {
+  channelId: string;
+  results: {
+    ruleId: string;
+    kind: ForwardKind;
+    ok: boolean;
+    forward?: ForwardInfo;
+    error?: string;
+  }[];
+}
+
+// ssh_discard_output finished: the output stopped and this much was dropped
+interface PtyDiscardedPayload {
+  channelId: string;
+  bytes: number;
+}
+
+// eof: the shell ended (exit, EOF); error: the connection failed (errorKind
+// is the io error kind); disconnected: the server sent a disconnect; user:
+// ssh_disconnect; reconnect_failed: the backend retries gave up;
+// idle_timeout: closed after the session's idleTimeoutSecs without activity;
+// network_lost: the network watchdog's probe went unanswered;
+// unclaimed: no tab took it back after a webview reload; emit_failed: the
+// events stopped reaching the webview (only seen if it comes back)
 interface PtyClosedPayload {
   channelId: string;
-  reason?: 'normal' | 'error';
+  reason?:
+    | 'eof'
+    | 'error'
+    | 'disconnected'
+    | 'user'
+    | 'reconnect_failed'
+    | 'idle_timeout'
+    | 'network_lost'
+    | 'unclaimed'
+    | 'emit_failed';
   exitStatus?: number | null;
+  exitSignal?: string | null;
+  errorKind?: string | null;
+  message?: string | null;
+}
+
+// "Sesión cerrada (código 3)", "Conexión perdida (ConnectionReset)"…
+function describeClose(closed: PtyClosedPayload): string {
+  if (closed.reason === 'eof') {
+    if (closed.exitSignal) return `Sesión cerrada (señal ${closed.exitSignal})`;
+    if (closed.exitStatus) return `Sesión cerrada (código ${closed.exitStatus})`;
+    return 'Sesión cerrada';
+  }
+  if (closed.reason === 'disconnected') {
+    return closed.message
+      ? `El servidor cerró la conexión: ${closed.message}`
+      : 'El servidor cerró la conexión';
+  }
+  if (closed.reason === 'idle_timeout') {
+    return 'Desconectado por inactividad';
+  }
+  if (closed.reason === 'network_lost') {
+    return 'Conexión perdida (la red no responde)';
+  }
+  return closed.errorKind ? `Conexión perdida (${closed.errorKind})` : 'Conexión perdida';
+}
+
+// Backend reconnect of a dropped channel (ConnectParams.autoReconnect)
+interface PtyReconnectingPayload {
+  channelId: string;
+  attempt: number;
+  maxAttempts: number;
+  delayMs: number;
+  lastError?: string | null;
+}
+
+interface PtyReconnectedPayload {
+  channelId: string;
+  attempt: number;
+}
+
+// Keepalive round trip, averaged (EWMA) over `samples` measurements
+interface PasteProgressPayload {
+  channelId: string;
+  sent: number;
+  total: number;
+}
+
+// trigger: 'pattern' (the session's promptPattern matched), 'quiet' (output
+// stopped on a prompt-like line) or 'timeout' (gave up waiting)
+interface ShellReadyPayload {
+  channelId: string;
+  prompt: string;
+  trigger: 'pattern' | 'quiet' | 'timeout';
+}
+
+// autoSudoPassword: the stored password was typed at sudo's prompt, or it
+// was left to the user (rate_limited: too many this minute; rejected: the
+// previous answer was wrong, the helper stays off for the channel)
+interface SudoPasswordPayload {
+  channelId: string;
+  prompt: string;
+  reason?: 'rate_limited' | 'rejected';
+}
+
+// A session auto-response rule matched the output and its response was typed
+interface AutoResponseFiredPayload {
+  channelId: string;
+  pattern: string;
+  response: string;
+}
+
+interface PasteOutcome {
+  bytesSent: number;
+  cancelled: boolean;
+}
+
+interface ChannelLatencyPayload {
+  channelId: string;
+  rttMs: number;
+  samples: number;
+}
+
+// Sent once per idle period, about a minute before the idle disconnect
+interface IdleWarningPayload {
+  channelId: string;
+  secondsLeft: number;
+  idleTimeoutSecs: number;
 }
 
 interface SshProgressPayload {
@@ -29,77 +205,148 @@ interface ReconnectConfig {
   session: Session;
   cols: number;
   rows: number;
-  onData: (data: string) => void;
+  onData: OutputCallback;
 }
 
-// Error type classification for better UX
-function classifyError(error: string): { title: string; message: string } {
-  const errorLower = error.toLowerCase();
+// Changed host key: the dialog's data comes in the error's details
+function asHostKeyChange(error: unknown): HostKeyChange | null {
+  const { kind, message, details } = asAppError(error);
+  return kind === 'hostKeyChanged' ? ({ ...details, kind, message } as HostKeyChange) : null;
+}
 
-  if (errorLower.includes('host key')) {
-    return {
-      title: 'Host Key Verification Failed',
-      message: error,
-    };
-  }
-  if (errorLower.includes('auth') || errorLower.includes('permission denied')) {
-    return {
-      title: 'Authentication Failed',
-      message: 'Invalid username, password, or SSH key. Please check your credentials.',
-    };
-  }
-  if (errorLower.includes('timeout') || errorLower.includes('timed out')) {
-    return {
-      title: 'Connection Timeout',
-      message: 'Server took too long to respond. Check if the host is reachable.',
-    };
-  }
-  if (errorLower.includes('refused') || errorLower.includes('connection refused')) {
-    return {
-      title: 'Connection Refused',
-      message: 'Server refused the connection. Verify the port and firewall settings.',
-    };
-  }
-  if (errorLower.includes('unreachable') || errorLower.includes('no route')) {
-    return {
-      title: 'Host Unreachable',
-      message: 'Cannot reach the server. Check network connectivity.',
-    };
-  }
-  if (errorLower.includes('key') && errorLower.includes('not found')) {
-    return {
-      title: 'SSH Key Not Found',
-      message: 'The specified private key file does not exist.',
-    };
-  }
-  if (errorLower.includes('dns') || errorLower.includes('resolve')) {
-    return {
-      title: 'DNS Resolution Failed',
-      message: 'Could not resolve hostname. Check the server address.',
-    };
+// Aborted by cancelConnect: not an error to report
+function isCancelled(error: unknown): boolean {
+  return asAppError(error).kind === 'cancelled';
+}
+
+// Error kind → toast. Network failures share one kind: the message tells
+// refused, unreachable and unresolved apart.
+function classifyError(error: AppError): { title: string; message: string } {
+  // The "(last stage: authenticating)" suffix must not drive the match
+  const text = error.message;
+  const errorLower = text.replace(/ \(last stage: [a-z0-9_]+\)/g, '').toLowerCase();
+
+  switch (error.kind) {
+    case 'hostKeyUnknown':
+      return {
+        title: 'Unknown Host Key',
+        message: `${text}. Verify the fingerprint before trusting this server.`,
+      };
+    case 'hostKey':
+    case 'hostKeyChanged':
+      return {
+        title: 'Host Key Verification Failed',
+        message: text,
+      };
+    case 'proxyAuth':
+      return {
+        title: 'Proxy Authentication Failed',
+        message: 'The proxy rejected the username or password.',
+      };
+    case 'proxy':
+      return {
+        title: 'Proxy Error',
+        message: text,
+      };
+    case 'notFound':
+    case 'permissionDenied':
+    case 'invalidInput':
+      // The key never reached the server: a missing, unreadable or bad file
+      if (errorLower.includes('key file not found')) {
+        return {
+          title: 'SSH Key Not Found',
+          message: 'The specified private key file does not exist.',
+        };
+      }
+      if (errorLower.includes('key file') || errorLower.includes('cannot load')) {
+        return {
+          title: 'SSH Key Unusable',
+          message: text,
+        };
+      }
+      break;
+    case 'auth':
+    case 'keyPassphrase':
+      // Refused password changes are auth failures too
+      if (errorLower.includes('password change rejected')) {
+        return {
+          title: 'Password Change Rejected',
+          message: 'The server refused the new password (policy or wrong current password).',
+        };
+      }
+      return {
+        title: 'Authentication Failed',
+        message: 'Invalid username, password, or SSH key. Please check your credentials.',
+      };
+    case 'timeout':
+      return {
+        title: 'Connection Timeout',
+        message: 'Server took too long to respond. Check if the host is reachable.',
+      };
+    case 'network':
+      if (errorLower.includes('refused')) {
+        return {
+          title: 'Connection Refused',
+          message: 'Server refused the connection. Verify the port and firewall settings.',
+        };
+      }
+      if (errorLower.includes('unreachable') || errorLower.includes('no route')) {
+        return {
+          title: 'Host Unreachable',
+          message: 'Cannot reach the server. Check network connectivity.',
+        };
+      }
+      if (errorLower.includes('dns') || errorLower.includes('resolve')) {
+        return {
+          title: 'DNS Resolution Failed',
+          message: 'Could not resolve hostname. Check the server address.',
+        };
+      }
+      break;
   }
 
   return {
     title: 'Connection Failed',
-    message: error,
+    message: text,
   };
 }
 
 class SSHService {
   private static instance: SSHService;
-  private callbacks = new Map<string, (data: string) => void>();
+  private callbacks = new Map<string, OutputCallback>();
+  // Written bytes not acked yet, per channel (sent every ACK_DELAY_MS)
+  private pendingAcks = new Map<string, number>();
+  private ackTimer: ReturnType<typeof setTimeout> | null = null;
   // Multi-hop connection progress, keyed by tab id
   private progressCallbacks = new Map<string, (message: string) => void>();
   private reconnectConfigs = new Map<string, ReconnectConfig>();
   private reconnectTimers = new Map<string, ReturnType<typeof setTimeout>>();
   private intentionalCloseChannels = new Set<string>();
   private inputBuffers = new Map<string, string>();
+  // New password set during an expired-password change, by connect id (tab),
+  // offered for saving once that connect succeeds
+  private changedPasswords = new Map<string, string>();
   // Recent server output per channel, used only by the password-prompt guard
   private outputTails = new Map<string, string>();
   private initialized = false;
   private outputUnlisten: (() => void) | null = null;
   private closedUnlisten: (() => void) | null = null;
   private progressUnlisten: (() => void) | null = null;
+  private reconnectingUnlisten: (() => void) | null = null;
+  private reconnectedUnlisten: (() => void) | null = null;
+  private idleWarningUnlisten: (() => void) | null = null;
+  private latencyUnlisten: (() => void) | null = null;
+  private pasteProgressUnlisten: (() => void) | null = null;
+  private shellReadyUnlisten: (() => void) | null = null;
+  private autoResponseUnlisten: (() => void) | null = null;
+  private sudoSentUnlisten: (() => void) | null = null;
+  private sudoSkippedUnlisten: (() => void) | null = null;
+  private discardedUnlisten: (() => void) | null = null;
+  private clipboardSetUnlisten: (() => void) | null = null;
+  private clipboardRefusedUnlisten: (() => void) | null = null;
+  private forwardClosedUnlisten: (() => void) | null = null;
+  private forwardsStartedUnlisten: (() => void) | null = null;
+  private statsTimer: ReturnType<typeof setInterval> | null = null;
 
   private constructor() {}
 
@@ -118,22 +365,248 @@ class SSHService {
 
     // Listen for PTY output
     this.outputUnlisten = await listen<PtyOutputPayload>('pty_output', (event) => {
-      const { channelId, data } = event.payload;
+      const { channelId, data, bytes } = event.payload;
       // Keep a short tail of recent output for the password-prompt guard
       const tail = (this.outputTails.get(channelId) ?? '') + data;
       this.outputTails.set(channelId, tail.slice(-OUTPUT_TAIL_LIMIT));
+      const done = () => this.ackOutput(channelId, bytes ?? 0);
       const callback = this.callbacks.get(channelId);
       if (callback) {
-        callback(data);
+        callback(data, done);
+      } else {
+        done();
       }
     });
 
+    this.discardedUnlisten = await listen<PtyDiscardedPayload>('pty_discarded', (event) => {
+      const { channelId, bytes } = event.payload;
+      const { tabs, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+      logSessionEvent(tab.sessionId, 'event', `Salida descartada: ${formatBytes(bytes)}`);
+      addToast({
+        type: 'info',
+        title: 'Output discarded',
+        message: `${formatBytes(bytes)} of output were dropped`,
+        duration: 3000,
+      });
+    });
+
     // Multi-hop connection progress ("Hop 1/2: connecting to ...")
     this.progressUnlisten = await listen<SshProgressPayload>('ssh_progress', (event) => {
       const { progressId, message } = event.payload;
       this.progressCallbacks.get(progressId)?.(message);
     });
 
+    // The backend keeps the channelId while it reconnects: the terminal stays
+    // bound, only the tab status and a notice change
+    this.reconnectingUnlisten = await listen<PtyReconnectingPayload>('pty_reconnecting', (event) => {
+      const { channelId, attempt, maxAttempts, delayMs } = event.payload;
+      const { tabs, updateTabStatus, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+
+      updateTabStatus(tab.id, 'connecting', channelId);
+      if (attempt === 1) {
+        logSessionEvent(tab.sessionId, 'event', 'Conexión perdida');
+        addToast({
+          type: 'warning',
+          title: 'Connection Lost',
+          message: 'Attempting to reconnect...',
+          duration: 3000,
+        });
+      }
+      logSessionEvent(tab.sessionId, 'event', `Reconectando (${attempt}/${maxAttempts})`);
+      this.callbacks.get(channelId)?.(
+        `\r\n\x1b[33mConnection lost. Reconnecting (${attempt}/${maxAttempts}) in ${Math.round(
+          delayMs / 1000
+        )}s...\x1b[0m\r\n`
+      );
+    });
+
+    this.reconnectedUnlisten = await listen<PtyReconnectedPayload>('pty_reconnected', (event) => {
+      const { channelId } = event.payload;
+      const { tabs, sessions, updateTabStatus, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+
+      updateTabStatus(tab.id, 'connected', channelId);
+      // The new shell starts from scratch: the half-typed line is gone
+      this.inputBuffers.delete(channelId);
+      logSessionEvent(tab.sessionId, 'event', 'Reconectado');
+      const session = sessions.find(s => s.id === tab.sessionId);
+      addToast({
+        type: 'success',
+        title: 'Reconnected',
+        message: `Successfully reconnected to ${session?.name ?? 'the server'}`,
+      });
+    });
+
+    this.latencyUnlisten = await listen<ChannelLatencyPayload>('channel_latency', (event) => {
+      const { channelId, rttMs } = event.payload;
+      const { tabs, updateTabLatency } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (tab && tab.latencyMs !== rttMs) {
+        updateTabLatency(tab.id, rttMs);
+      }
+    });
+
+    this.pasteProgressUnlisten = await listen<PasteProgressPayload>('paste_progress', (event) => {
+      const { channelId, sent, total } = event.payload;
+      const { tabs, setTabPasteProgress } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (tab && total > 0) {
+        setTabPasteProgress(tab.id, sent / total);
+      }
+    });
+
+    // Once per channel, when the login (MOTD, profile scripts) is over
+    this.shellReadyUnlisten = await listen<ShellReadyPayload>('shell_ready', (event) => {
+      const { channelId, prompt, trigger } = event.payload;
+      const { tabs, setTabShellPrompt } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+      setTabShellPrompt(tab.id, prompt);
+      if (trigger === 'timeout') {
+        console.warn('No prompt detected, shell_ready after the timeout', { channelId, prompt });
+      }
+    });
+
+    // Audit only: the answer shows up in the terminal as the server echoes it
+    this.autoResponseUnlisten = await listen<AutoResponseFiredPayload>('auto_response_fired', (event) => {
+      const { channelId, pattern } = event.payload;
+      const tab = useStore.getState().tabs.find(t => t.channelId === channelId);
+      if (tab) {
+        logSessionEvent(tab.sessionId, 'event', `Respuesta automática a /${pattern}/`);
+      }
+    });
+
+    this.sudoSentUnlisten = await listen<SudoPasswordPayload>('sudo_password_sent', (event) => {
+      const tab = useStore.getState().tabs.find(t => t.channelId === event.payload.channelId);
+      if (tab) {
+        logSessionEvent(tab.sessionId, 'event', 'Contraseña de sudo enviada automáticamente');
+      }
+    });
+
+    this.sudoSkippedUnlisten = await listen<SudoPasswordPayload>('sudo_password_skipped', (event) => {
+      const { channelId, reason } = event.payload;
+      const { tabs, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+      const rejected = reason === 'rejected';
+      logSessionEvent(
+        tab.sessionId,
+        'event',
+        rejected
+          ? 'sudo rechazó la contraseña guardada; respuesta automática desactivada'
+          : 'Demasiadas peticiones de sudo; contraseña no enviada'
+      );
+      addToast({
+        type: 'warning',
+        title: rejected ? 'Sudo password rejected' : 'Sudo password not sent',
+        message: rejected
+          ? 'The stored password was not accepted; type it yourself from now on'
+          : 'Too many sudo prompts in the last minute; type the password yourself',
+      });
+    });
+
+    this.clipboardSetUnlisten = await listen<ClipboardSetPayload>('clipboard_set', (event) => {
+      const { channelId, length } = event.payload;
+      const { tabs, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+      logSessionEvent(tab.sessionId, 'event', `El servidor copió ${length} caracteres al portapapeles`);
+      addToast({
+        type: 'info',
+        title: 'Copied from server',
+        message: `${length} characters copied to the clipboard`,
+        duration: 2500,
+      });
+    });
+
+    this.clipboardRefusedUnlisten = await listen<ClipboardRefusedPayload>('clipboard_refused', (event) => {
+      const { channelId, reason } = event.payload;
+      const { tabs, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+      logSessionEvent(tab.sessionId, 'event', `Petición de portapapeles rechazada (${reason})`);
+      // Reads are refused silently: tmux asks on attach, not worth a toast
+      if (reason === 'read_denied') return;
+      addToast({
+        type: 'warning',
+        title: 'Clipboard not set',
+        message:
+          reason === 'too_large'
+            ? 'The server tried to copy more than 100 KB'
+            : 'The server sent clipboard data that could not be copied',
+      });
+    });
+
+    this.forwardClosedUnlisten = await listen<ForwardClosedPayload>('forward_closed', (event) => {
+      const { channelId, error } = event.payload;
+      const { tabs, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+      logSessionEvent(tab.sessionId, 'event', `Reenvío de puerto perdido al reconectar: ${error}`);
+      addToast({
+        type: 'warning',
+        title: 'Port forward stopped',
+        message: error,
+      });
+    });
+
+    // The terminal is already up: a forward that failed is only reported
+    this.forwardsStartedUnlisten = await listen<ForwardsStartedPayload>(
+      'forwards_started',
+      (event) => {
+        const { channelId, results } = event.payload;
+        const { tabs, addToast } = useStore.getState();
+        const tab = tabs.find(t => t.channelId === channelId);
+        if (!tab) return;
+        for (const result of results) {
+          const f = result.forward;
+          logSessionEvent(
+            tab.sessionId,
+            'event',
+            f
+              ? `Reenvío ${f.kind} en ${f.bindHost}:${f.bindPort}` +
+                  (f.targetHost ? ` → ${f.targetHost}:${f.targetPort}` : '')
+              : `Reenvío ${result.kind} no iniciado: ${result.error}`
+          );
+        }
+        const failed = results.filter(r => !r.ok);
+        if (failed.length === 0) return;
+        addToast({
+          type: 'warning',
+          title:
+            failed.length === 1
+              ? 'Port forward not started'
+              : `${failed.length} port forwards not started`,
+          message: failed.map(r => r.error).join('\n'),
+        });
+      }
+    );
+
+    this.statsTimer = setInterval(() => {
+      void this.pollChannelStats();
+    }, STATS_POLL_MS);
+
+    // Any keystroke or output resets the idle timer: the warning is enough
+    this.idleWarningUnlisten = await listen<IdleWarningPayload>('idle_warning', (event) => {
+      const { channelId, secondsLeft } = event.payload;
+      const { tabs, sessions, addToast } = useStore.getState();
+      const tab = tabs.find(t => t.channelId === channelId);
+      if (!tab) return;
+
+      const session = sessions.find(s => s.id === tab.sessionId);
+      addToast({
+        type: 'warning',
+        title: 'Idle Session',
+        message: `${session?.name ?? 'The session'} will be disconnected in ${secondsLeft}s. Type anything to stay connected.`,
+        duration: Math.min(secondsLeft, 10) * 1000,
+      });
+    });
+
     // Listen for PTY closed events - trigger auto-reconnect only for unexpected closures
     this.closedUnlisten = await listen<PtyClosedPayload | string>('pty_closed', (event) => {
       const closed = this.normalizeClosedPayload(event.payload);
@@ -141,18 +614,47 @@ class SSHService {
       const { tabs, updateTabStatus, addToast } = useStore.getState();
       const tab = tabs.find(t => t.channelId === channelId);
 
+      // Our own ssh_disconnect: disconnect() already updated and logged it
+      if (closed.reason === 'user') {
+        this.callbacks.delete(channelId);
+        this.intentionalCloseChannels.delete(channelId);
+        return;
+      }
+
       if (tab) {
         updateTabStatus(tab.id, 'disconnected');
 
         const isIntentionalClose =
-          closed.reason === 'normal' || this.intentionalCloseChannels.has(channelId);
+          closed.reason === 'eof' ||
+          closed.reason === 'idle_timeout' ||
+          this.intentionalCloseChannels.has(channelId);
 
-        // Audit: record how the connection ended
-        logSessionEvent(
-          tab.sessionId,
-          'event',
-          isIntentionalClose ? 'Sesión cerrada' : 'Conexión perdida'
-        );
+        // The backend already retried (see pty_reconnecting): nothing left to do
+        if (closed.reason === 'reconnect_failed') {
+          logSessionEvent(tab.sessionId, 'event', 'Reconexión fallida');
+          addToast({
+            type: 'error',
+            title: 'Reconnection Failed',
+            message: 'Could not reconnect to the server.',
+            duration: 5000,
+          });
+          this.disableAutoReconnect(tab.id);
+        } else {
+          // Audit: record how the connection ended
+          logSessionEvent(tab.sessionId, 'event', describeClose(closed));
+        }
+
+        // A shell that failed or was killed is worth a notice
+        if (closed.reason === 'eof' && (closed.exitSignal || closed.exitStatus)) {
+          addToast({
+            type: 'warning',
+            title: 'Shell exited',
+            message: closed.exitSignal
+              ? `The ${tab.local ? 'local' : 'remote'} shell was killed by signal ${closed.exitSignal}`
+              : `The ${tab.local ? 'local' : 'remote'} shell exited with status ${closed.exitStatus}`,
+            duration: 5000,
+          });
+        }
 
         if (isIntentionalClose) {
           this.disableAutoReconnect(tab.id);
@@ -189,7 +691,7 @@ class SSHService {
     }
 
     return {
-      channelId: payload.channelId,
+      ...payload,
       reason: payload.reason ?? 'error',
       exitStatus: payload.exitStatus ?? null,
     };
@@ -252,7 +754,7 @@ class SSHService {
   }
 
   // Enable auto-reconnect for a tab
-  enableAutoReconnect(tabId: string, session: Session, cols: number, rows: number, onData: (data: string) => void) {
+  enableAutoReconnect(tabId: string, session: Session, cols: number, rows: number, onData: OutputCallback) {
     this.reconnectConfigs.set(tabId, { tabId, session, cols, rows, onData });
   }
 
@@ -314,7 +816,7 @@ class SSHService {
     cols?: number,
     rows?: number
   ): Promise<string | null> {
-    const { updateTabStatus, addToast } = useStore.getState();
+    const { updateTabStatus, addToast, settings } = useStore.getState();
     updateTabStatus(tabId, 'connecting');
 
     // Only the id travels over IPC: the backend resolves credentials internally
@@ -323,11 +825,30 @@ class SSHService {
       cols,
       rows,
       progressId: tabId,
+      // Dropped connections are retried in the backend on the same channel
+      autoReconnect: true,
+      connectId: tabId,
+      // Suspended hosts are woken and waited for ("Waiting for host to wake")
+      wakeOnLan: !!session.wolMacAddress,
+      // Another tab to the same host/port/user lends its connection
+      reuseConnection: settings.reuseConnections ?? true,
+      logMaxMb: settings.transcriptMaxMb,
+      logInput: settings.transcriptInput ?? false,
+      logPlainText: settings.transcriptPlainText ?? false,
+      shellReadyQuietMs: settings.shellReadyQuietMs,
+      shellReadyTimeoutMs: settings.shellReadyTimeoutMs,
+      outputBufferKb: settings.outputBufferKb,
+      verboseInputLog: settings.verboseInputLog ?? false,
+      networkWatchdogSecs: settings.networkWatchdogSecs,
     };
 
     try {
-      const channelId = await invoke<string>('ssh_connect', { params });
+      const { channelId, agentForwarding, x11Forwarding, compression, term, encoding, reusedConnection, logPath, sudoHelper } = await invoke<ConnectInfo>('ssh_connect', {
+        params,
+      });
       updateTabStatus(tabId, 'connected', channelId);
+      useStore.getState().setTabLogPath(tabId, logPath ?? undefined);
+      this.offerSavingChangedPassword(tabId, session);
       logSessionEvent(
         session.id,
         'event',
@@ -340,58 +861,197 @@ class SSHService {
         title: 'Connected',
         message: `Connected to ${session.name}`,
       });
+      if (reusedConnection) {
+        logSessionEvent(session.id, 'event', 'Canal nuevo sobre la conexión ya abierta');
+      }
+      if (logPath) {
+        logSessionEvent(session.id, 'event', `Registro en archivo: ${logPath}`);
+      }
+      logSessionEvent(session.id, 'event', `TERM remoto: ${term}`);
+      if (encoding !== 'UTF-8') {
+        logSessionEvent(session.id, 'event', `Codificación del terminal: ${encoding}`);
+      }
+      if (session.compression) {
+        logSessionEvent(session.id, 'event', `Compresión negociada: ${compression}`);
+      }
+      if (agentForwarding === false) {
+        logSessionEvent(session.id, 'event', 'El servidor rechazó el reenvío del agente SSH');
+        addToast({
+          type: 'warning',
+          title: 'Agent forwarding refused',
+          message: `${session.host} does not allow agent forwarding; the shell runs without it`,
+        });
+      }
+      if (x11Forwarding && x11Forwarding !== 'on') {
+        logSessionEvent(session.id, 'event', `Reenvío X11 desactivado (${x11Forwarding})`);
+        addToast({
+          type: 'warning',
+          title: 'X11 forwarding off',
+          message: X11_FAILURES[x11Forwarding],
+        });
+      }
+      if (sudoHelper && sudoHelper !== 'on') {
+        logSessionEvent(session.id, 'event', `Contraseña automática de sudo desactivada (${sudoHelper})`);
+        addToast({
+          type: 'warning',
+          title: 'Sudo helper off',
+          message:
+            sudoHelper === 'no_password'
+              ? `${session.name} has no stored password to answer sudo with`
+              : 'The sudo prompt pattern is not a valid regex',
+        });
+      }
       return channelId;
     } catch (error) {
+      this.changedPasswords.delete(tabId);
+      if (isCancelled(error)) {
+        updateTabStatus(tabId, 'disconnected');
+        logSessionEvent(session.id, 'event', 'Conexión cancelada');
+        return null;
+      }
       console.error('SSH connection failed:', error);
       updateTabStatus(tabId, 'error');
 
-      const errorText = String(error);
-      const errorInfo = classifyError(errorText);
+      const hostKeyChange = asHostKeyChange(error);
+      const appError = asAppError(error);
+      const errorText = appError.message;
+      const errorInfo = classifyError(appError);
       logSessionEvent(session.id, 'event', `Error de conexión: ${errorInfo.title} — ${errorText}`);
+      if (hostKeyChange) {
+        // Changed key: the dedicated warning dialog replaces the toast
+        useStore.getState().openHostKeyModal(hostKeyChange);
+        return null;
+      }
       addToast({
         type: 'error',
         title: errorInfo.title,
         message: errorInfo.message,
-        duration: errorInfo.title === 'Host Key Verification Failed' ? 12000 : 5000,
-        action: this.buildHostKeyAction(errorText),
+        duration: errorInfo.title === 'Unknown Host Key' ? 12000 : 5000,
+        action: this.buildAcceptHostKeyAction(appError),
       });
       return null;
     }
   }
 
-  /// When the failure is a host key mismatch, offer to forget the stored key
-  /// (the offending host:port is parsed from the backend error, so this also
-  /// works when the mismatch happens on a jump hop)
-  private buildHostKeyAction(errorText: string) {
-    const match = errorText.match(/Host key for ([^\s:]+):(\d+) CHANGED/i);
-    if (!match) return undefined;
+  /// When the server is unknown, offer to trust the exact key whose
+  /// fingerprint the backend reported (it is kept pending until accepted)
+  private buildAcceptHostKeyAction(error: AppError) {
+    if (error.kind !== 'hostKeyUnknown' || !error.details) return undefined;
 
-    const [, host, port] = match;
+    const { host, port, fingerprint } = error.details as { host: string; port: number; fingerprint: string };
     return {
-      label: `Olvidar clave de ${host}`,
+      label: `Confiar en ${host}`,
       onClick: () => {
-        invoke<boolean>('forget_host_key', { host, port: Number(port) })
-          .then((removed) => {
+        invoke('ssh_accept_host_key', { host, port, fingerprint })
+          .then(() => {
             useStore.getState().addToast({
-              type: removed ? 'success' : 'warning',
-              title: removed ? 'Host key olvidada' : 'Sin cambios',
-              message: removed
-                ? 'Pulsa Reconectar para confiar en la nueva clave.'
-                : 'No se encontró la entrada en known_hosts.',
+              type: 'success',
+              title: 'Host key aceptada',
+              message: 'Pulsa Reconectar para conectar con el servidor.',
             });
           })
           .catch((err) => {
-            console.error('forget_host_key failed:', err);
+            console.error('ssh_accept_host_key failed:', err);
             useStore.getState().addToast({
               type: 'error',
               title: 'Error',
-              message: 'No se pudo eliminar la host key',
+              message: errorMessage(err),
             });
           });
       },
     };
   }
 
+  // Local shell tab: no session, no credentials, no auto-reconnect
+  async connectLocal(tabId: string, cols?: number, rows?: number): Promise<string | null> {
+    const { updateTabStatus, setTabShell, addToast, settings } = useStore.getState();
+    updateTabStatus(tabId, 'connecting');
+    try {
+      const { channelId, shell } = await invoke<LocalInfo>('local_connect', {
+        cols,
+        rows,
+        verboseInputLog: settings.verboseInputLog ?? false,
+      });
+      updateTabStatus(tabId, 'connected', channelId);
+      setTabShell(tabId, shell);
+      return channelId;
+    } catch (error) {
+      console.error('Local shell failed:', error);
+      updateTabStatus(tabId, 'error');
+      addToast({
+        type: 'error',
+        title: 'Local shell failed',
+        message: errorMessage(error),
+        duration: 5000,
+      });
+      return null;
+    }
+  }
+
+  // Abort the connect in progress on a tab (typo'd host, endless timeout);
+  // its connect() resolves to null with the tab 'disconnected'
+  // Magic packet on demand (session menu), without connecting
+  async sendWakeOnLan(session: Session) {
+    const { addToast } = useStore.getState();
+    if (!session.wolMacAddress) return;
+    try {
+      const sentTo = await invoke<string>('send_wol', {
+        mac: session.wolMacAddress,
+        broadcast: session.wolBroadcastAddress || null,
+      });
+      logSessionEvent(session.id, 'event', `Wake-on-LAN enviado a ${sentTo}`);
+      addToast({
+        type: 'success',
+        title: 'Wake-on-LAN sent',
+        message: `Magic packet for ${session.name} sent to ${sentTo}`,
+      });
+    } catch (error) {
+      addToast({
+        type: 'error',
+        title: 'Wake-on-LAN Failed',
+        message: errorMessage(error),
+      });
+    }
+  }
+
+  /// Answer `password_change_required`: `oldPassword` empty uses the stored one
+  async changePassword(connectId: string, oldPassword: string, newPassword: string) {
+    await invoke('ssh_change_password', { connectId, old: oldPassword, new: newPassword });
+    this.changedPasswords.set(connectId, newPassword);
+  }
+
+  /// The server accepted a new password: the stored one is now stale
+  private offerSavingChangedPassword(tabId: string, session: Session) {
+    const password = this.changedPasswords.get(tabId);
+    if (password === undefined) return;
+    this.changedPasswords.delete(tabId);
+    logSessionEvent(session.id, 'event', 'Contraseña caducada cambiada en el servidor');
+    useStore.getState().addToast({
+      type: 'info',
+      title: 'Contraseña cambiada',
+      message: `¿Guardar la nueva contraseña en "${session.name}"?`,
+      duration: 15000,
+      action: {
+        label: 'Guardar',
+        onClick: () => {
+          useStore
+            .getState()
+            .updateSession(session.id, { password })
+            .catch(() => {});
+        },
+      },
+    });
+  }
+
+  async cancelConnect(tabId: string) {
+    this.disableAutoReconnect(tabId);
+    try {
+      await invoke<boolean>('ssh_cancel_connect', { connectId: tabId });
+    } catch (error) {
+      console.error('Failed to cancel connect:', error);
+    }
+  }
+
   async send(channelId: string, data: string) {
     const { addToast } = useStore.getState();
     try {
@@ -408,7 +1068,164 @@ class SSHService {
     }
   }
 
-  startReading(channelId: string, onData: (data: string) => void) {
+  // Bytes a JSON string would mangle (xterm's binary input): sent as a list
+  // of numbers, written unchanged
+  async sendBytes(channelId: string, data: Uint8Array) {
+    try {
+      await invoke('ssh_send_bytes', { channelId, data: Array.from(data) });
+    } catch (error) {
+      console.error('Failed to send bytes:', error);
+      useStore.getState().addToast({
+        type: 'error',
+        title: 'Send Failed',
+        message: 'Could not send data to server',
+        duration: 2500,
+      });
+    }
+  }
+
+  // Large paste written by the backend in bounded chunks; `bracketed` when
+  // the remote program enabled bracketed paste mode
+  async paste(tabId: string, data: string, bracketed: boolean) {
+    const { tabs, setTabPasteProgress, addToast } = useStore.getState();
+    const channelId = tabs.find((t) => t.id === tabId)?.channelId;
+    if (!channelId) return;
+    this.trackPotentialLogout(channelId, data);
+    setTabPasteProgress(tabId, 0);
+    try {
+      const outcome = await invoke<PasteOutcome>('ssh_paste', { channelId, data, bracketed });
+      if (outcome.cancelled) {
+        addToast({
+          type: 'info',
+          title: 'Paste cancelled',
+          message: 'The rest of the pasted text was not sent',
+          duration: 2500,
+        });
+      }
+    } catch (error) {
+      console.error('Failed to paste:', error);
+      addToast({
+        type: 'error',
+        title: 'Paste Failed',
+        message: errorMessage(error),
+        duration: 2500,
+      });
+    } finally {
+      useStore.getState().setTabPasteProgress(tabId, undefined);
+    }
+  }
+
+  async cancelPaste(tabId: string) {
+    const channelId = useStore.getState().tabs.find((t) => t.id === tabId)?.channelId;
+    if (!channelId) return;
+    await invoke<boolean>('ssh_cancel_paste', { channelId }).catch((err) =>
+      console.error('ssh_cancel_paste failed:', err)
+    );
+  }
+
+  // After a webview reload the backend may still hold live channels: give
+  // each one of a known session a tab again (TerminalView attaches to it
+  // instead of connecting) and let the backend close the rest
+  async recoverChannels() {
+    let channels: ChannelSummary[];
+    try {
+      channels = await invoke<ChannelSummary[]>('ssh_list_channels');
+    } catch (error) {
+      console.error('Failed to list channels:', error);
+      return;
+    }
+    if (channels.length === 0) return;
+
+    const { sessions, createTab, updateTabStatus, addToast } = useStore.getState();
+    const claimed: string[] = [];
+    for (const channel of channels) {
+      const bound = useStore.getState().tabs.some((t) => t.channelId === channel.channelId);
+      if (bound) {
+        claimed.push(channel.channelId);
+        continue;
+      }
+      if (!channel.isConnected || !sessions.some((s) => s.id === channel.sessionId)) continue;
+      const tabId = createTab(channel.sessionId);
+      // Repainted from the backend's copy before the live output goes on
+      const buffer = await this.getBuffer(channel.channelId);
+      if (buffer) useStore.getState().setTabBuffer(tabId, buffer);
+      updateTabStatus(tabId, 'connected', channel.channelId);
+      logSessionEvent(channel.sessionId, 'event', 'Sesión recuperada tras recargar la ventana');
+      claimed.push(channel.channelId);
+    }
+    await invoke('ssh_claim_channels', { channelIds: claimed }).catch((err) =>
+      console.error('ssh_claim_channels failed:', err)
+    );
+    if (claimed.length > 0) {
+      addToast({
+        type: 'info',
+        title: 'Sessions Restored',
+        message: `${claimed.length} open terminal(s) reattached`,
+      });
+    }
+  }
+
+  // Cluster typing: one write per channel in the backend, failures reported
+  // per channel (a dead tab doesn't stop the others)
+  async sendBroadcast(channelIds: string[], data: string) {
+    channelIds.forEach((id) => this.trackPotentialLogout(id, data));
+    try {
+      const results = await invoke<Record<string, string | null>>('ssh_send_broadcast', {
+        channelIds,
+        data,
+      });
+      const failed = Object.entries(results).filter(([, error]) => error);
+      if (failed.length > 0) {
+        console.error('Broadcast failed on some channels:', failed);
+        useStore.getState().addToast({
+          type: 'error',
+          title: 'Send Failed',
+          message: `Could not send to ${failed.length} of ${channelIds.length} terminals`,
+          duration: 2500,
+        });
+      }
+    } catch (error) {
+      console.error('Failed to broadcast:', error);
+    }
+  }
+
+  // Output written by the terminal: acked in batches so the backend goes on
+  // reading (it pauses the channel when too much is unacked)
+  private ackOutput(channelId: string, bytes: number) {
+    if (bytes <= 0) return;
+    const total = (this.pendingAcks.get(channelId) ?? 0) + bytes;
+    this.pendingAcks.set(channelId, total);
+    if (total >= ACK_BATCH) {
+      this.pendingAcks.delete(channelId);
+      invoke('pty_ack', { channelId, bytes: total }).catch(() => {
+        // The channel closed meanwhile
+      });
+    } else if (!this.ackTimer) {
+      this.ackTimer = setTimeout(() => this.flushAcks(), ACK_DELAY_MS);
+    }
+  }
+
+  private flushAcks() {
+    this.ackTimer = null;
+    for (const [channelId, bytes] of this.pendingAcks) {
+      invoke('pty_ack', { channelId, bytes }).catch(() => {});
+    }
+    this.pendingAcks.clear();
+  }
+
+  // "Descartar salida": the backend drops the channel's output until it
+  // stops (pty_discarded then) instead of sending megabytes to render
+  async discardOutput(tabId: string) {
+    const channelId = useStore.getState().tabs.find((t) => t.id === tabId)?.channelId;
+    if (!channelId) return;
+    try {
+      await invoke('ssh_discard_output', { channelId });
+    } catch (error) {
+      console.error('ssh_discard_output failed:', error);
+    }
+  }
+
+  startReading(channelId: string, onData: OutputCallback) {
     this.callbacks.set(channelId, onData);
   }
 
@@ -424,6 +1241,263 @@ class SSHService {
     }
   }
 
+  // Output stays out of the terminal; rejects with "Command timed out after
+  // N ms" past timeoutMs (default 30000)
+  async exec(
+    target: RemoteTarget,
+    command: string,
+    options: { timeoutMs?: number; maxOutputBytes?: number } = {}
+  ): Promise<ExecResult> {
+    return invoke<ExecResult>('ssh_exec', {
+      target,
+      command,
+      timeoutMs: options.timeoutMs,
+      maxOutputBytes: options.maxOutputBytes,
+    });
+  }
+
+  // Saved commands in order, each in its own exec channel; queue_progress
+  // events ({ queueId, index, total, step }) follow each step
+  async runQueue(
+    channelId: string,
+    commands: string[],
+    options: { stopOnError?: boolean; stepTimeoutMs?: number; maxOutputBytes?: number; queueId?: string } = {}
+  ): Promise<QueueOutcome> {
+    return invoke<QueueOutcome>('ssh_run_queue', {
+      channelId,
+      commands,
+      stopOnError: options.stopOnError ?? true,
+      stepTimeoutMs: options.stepTimeoutMs,
+      maxOutputBytes: options.maxOutputBytes,
+      queueId: options.queueId,
+    });
+  }
+
+  // Fleet check: one exec per saved session, results as exec_multi_result
+  // events tagged with batchId; resolves with the summary
+  async execMulti(
+    sessionIds: string[],
+    command: string,
+    options: { parallelism?: number; timeoutMs?: number; maxOutputBytes?: number; batchId?: string } = {}
+  ): Promise<MultiExecSummary> {
+    return invoke<MultiExecSummary>('ssh_exec_multi', {
+      sessionIds,
+      command,
+      parallelism: options.parallelism,
+      timeoutMs: options.timeoutMs,
+      maxOutputBytes: options.maxOutputBytes,
+      batchId: options.batchId,
+    });
+  }
+
+  async cancelQueue(queueId: string): Promise<boolean> {
+    return invoke<boolean>('ssh_cancel_queue', { queueId });
+  }
+
+  // Null once the channel is gone
+  async getConnectionInfo(channelId: string): Promise<ConnectionInfo | null> {
+    try {
+      return await invoke<ConnectionInfo>('ssh_get_connection_info', { channelId });
+    } catch (error) {
+      console.error('Failed to get connection info:', error);
+      return null;
+    }
+  }
+
+  // Start / stop the transcript of a connected tab
+  async setLogging(tabId: string, enabled: boolean) {
+    const { tabs, setTabLogPath, addToast } = useStore.getState();
+    const tab = tabs.find((t) => t.id === tabId);
+    if (!tab?.channelId) return;
+    try {
+      const logPath = await invoke<string | null>('ssh_set_logging', {
+        channelId: tab.channelId,
+        enabled,
+      });
+      setTabLogPath(tabId, logPath ?? undefined);
+      logSessionEvent(
+        tab.sessionId,
+        'event',
+        logPath ? `Registro en archivo: ${logPath}` : 'Registro en archivo detenido'
+      );
+    } catch (error) {
+      addToast({
+        type: 'error',
+        title: 'Logging failed',
+        message: errorMessage(error),
+      });
+    }
+  }
+
+  // asciinema recording of a connected tab: asks where to save it
+  async startRecording(tabId: string) {
+    const { tabs, sessions, setTabRecordingPath, addToast } = useStore.getState();
+    const tab = tabs.find((t) => t.id === tabId);
+    if (!tab?.channelId) return;
+    try {
+      const { save } = await import('@tauri-apps/plugin-dialog');
+      const name = sessions.find((s) => s.id === tab.sessionId)?.name ?? 'session';
+      const stamp = new Date().toISOString().slice(0, 19).replace(/[:T]/g, '-');
+      const path = await save({
+        defaultPath: `${name.replace(/[^\w.-]+/g, '_')}-${stamp}.cast`,
+        filters: [{ name: 'asciinema', extensions: ['cast'] }],
+      });
+      if (!path) return;
+      await invoke('ssh_start_recording', { channelId: tab.channelId, path, recordInput: false });
+      setTabRecordingPath(tabId, path);
+      logSessionEvent(tab.sessionId, 'event', `Grabación iniciada: ${path}`);
+    } catch (error) {
+      addToast({
+        type: 'error',
+        title: 'Recording failed',
+        message: errorMessage(error),
+      });
+    }
+  }
+
+  async stopRecording(tabId: string) {
+    const { tabs, setTabRecordingPath, addToast } = useStore.getState();
+    const tab = tabs.find((t) => t.id === tabId);
+    if (!tab?.channelId) return;
+    try {
+      await invoke<boolean>('ssh_stop_recording', { channelId: tab.channelId });
+      setTabRecordingPath(tabId, undefined);
+      if (tab.recordingPath) {
+        logSessionEvent(tab.sessionId, 'event', `Grabación guardada: ${tab.recordingPath}`);
+        addToast({
+          type: 'success',
+          title: 'Recording saved',
+          message: tab.recordingPath,
+        });
+      }
+    } catch (error) {
+      addToast({
+        type: 'error',
+        title: 'Recording failed',
+        message: errorMessage(error),
+      });
+    }
+  }
+
+  // ssh -L: listens on localBindHost:localPort here ('' = 127.0.0.1, 0 = any
+  // free port) and opens each connection to remoteHost:remotePort from the
+  // server
+  async forwardLocal(
+    channelId: string,
+    localBindHost: string,
+    localPort: number,
+    remoteHost: string,
+    remotePort: number
+  ): Promise<ForwardInfo> {
+    const info = await invoke<ForwardInfo>('ssh_forward_local', {
+      channelId,
+      localBindHost,
+      localPort,
+      remoteHost,
+      remotePort,
+    });
+    const tab = useStore.getState().tabs.find((t) => t.channelId === channelId);
+    logSessionEvent(
+      tab?.sessionId ?? '',
+      'event',
+      `Reenvío local ${info.bindHost}:${info.bindPort} → ${info.targetHost}:${info.targetPort}`
+    );
+    return info;
+  }
+
+  // ssh -R: the server listens on remoteBindHost:remotePort (0 = its pick)
+  // and relays each connection to localHost:localPort on this machine
+  async forwardRemote(
+    channelId: string,
+    remoteBindHost: string,
+    remotePort: number,
+    localHost: string,
+    localPort: number
+  ): Promise<ForwardInfo> {
+    const info = await invoke<ForwardInfo>('ssh_forward_remote', {
+      channelId,
+      remoteBindHost,
+      remotePort,
+      localHost,
+      localPort,
+    });
+    const tab = useStore.getState().tabs.find((t) => t.channelId === channelId);
+    logSessionEvent(
+      tab?.sessionId ?? '',
+      'event',
+      `Reenvío remoto ${info.bindHost}:${info.bindPort} → ${info.targetHost}:${info.targetPort}`
+    );
+    return info;
+  }
+
+  // ssh -D: SOCKS5 proxy on 127.0.0.1:localPort (0 = any free port) that
+  // reaches out through the channel's connection
+  async startSocks(channelId: string, localPort: number): Promise<ForwardInfo> {
+    const info = await invoke<ForwardInfo>('ssh_start_socks', { channelId, localPort });
+    const tab = useStore.getState().tabs.find((t) => t.channelId === channelId);
+    logSessionEvent(tab?.sessionId ?? '', 'event', `Proxy SOCKS5 en 127.0.0.1:${info.bindPort}`);
+    return info;
+  }
+
+  async stopSocks(forwardId: string) {
+    try {
+      await invoke('ssh_stop_socks', { forwardId });
+    } catch (error) {
+      console.error('ssh_stop_socks failed:', error);
+    }
+  }
+
+  async cancelForward(forwardId: string) {
+    try {
+      await invoke('ssh_cancel_forward', { forwardId });
+    } catch (error) {
+      console.error('ssh_cancel_forward failed:', error);
+    }
+  }
+
+  async listForwards(channelId?: string): Promise<ForwardInfo[]> {
+    try {
+      return await invoke<ForwardInfo[]>('ssh_list_forwards', { channelId: channelId ?? null });
+    } catch (error) {
+      console.error('ssh_list_forwards failed:', error);
+      return [];
+    }
+  }
+
+  // Recent output the backend kept for the channel ('' when gone)
+  async getBuffer(channelId: string): Promise<string> {
+    try {
+      return await invoke<string>('ssh_get_buffer', { channelId });
+    } catch (error) {
+      console.error('ssh_get_buffer failed:', error);
+      return '';
+    }
+  }
+
+  async getChannelStats(channelId: string): Promise<ChannelStats | null> {
+    try {
+      return await invoke<ChannelStats>('ssh_get_channel_stats', { channelId });
+    } catch {
+      // The channel closed between the poll and the call
+      return null;
+    }
+  }
+
+  private async pollChannelStats() {
+    const connected = useStore
+      .getState()
+      .tabs.filter((t) => t.status === 'connected' && t.channelId && !t.local);
+    await Promise.all(
+      connected.map(async (tab) => {
+        const stats = await this.getChannelStats(tab.channelId!);
+        const current = useStore.getState().tabs.find((t) => t.id === tab.id);
+        if (stats && current?.status === 'connected' && current.channelId === tab.channelId) {
+          useStore.getState().updateTabActivity(tab.id, stats);
+        }
+      })
+    );
+  }
+
   async disconnect(tabId: string, channelId: string) {
     const { updateTabStatus } = useStore.getState();
 
@@ -445,10 +1519,55 @@ class SSHService {
     }
   }
 
+  /// Close every open connection at once (tabs stay, disconnected)
+  async disconnectAll() {
+    const { tabs, updateTabStatus, addToast } = useStore.getState();
+    const open = tabs.filter((t) => t.channelId && t.status === 'connected');
+    // Connects in progress are aborted too
+    tabs.forEach((t) => this.disableAutoReconnect(t.id));
+
+    try {
+      const closed = await invoke<number>('ssh_disconnect_all');
+      for (const tab of open) {
+        this.callbacks.delete(tab.channelId!);
+        this.inputBuffers.delete(tab.channelId!);
+        this.outputTails.delete(tab.channelId!);
+        logSessionEvent(tab.sessionId, 'event', 'Desconexión manual (todas)');
+        updateTabStatus(tab.id, 'disconnected');
+      }
+      addToast({
+        type: 'info',
+        title: 'Disconnected',
+        message: `Closed ${closed} connection(s)`,
+      });
+    } catch (error) {
+      console.error('Failed to disconnect all:', error);
+    }
+  }
+
   cleanup() {
     this.outputUnlisten?.();
     this.closedUnlisten?.();
     this.progressUnlisten?.();
+    this.reconnectingUnlisten?.();
+    this.reconnectedUnlisten?.();
+    this.idleWarningUnlisten?.();
+    this.latencyUnlisten?.();
+    this.pasteProgressUnlisten?.();
+    this.shellReadyUnlisten?.();
+    this.autoResponseUnlisten?.();
+    this.sudoSentUnlisten?.();
+    this.sudoSkippedUnlisten?.();
+    this.discardedUnlisten?.();
+    this.clipboardSetUnlisten?.();
+    this.clipboardRefusedUnlisten?.();
+    this.forwardClosedUnlisten?.();
+    this.forwardsStartedUnlisten?.();
+    if (this.ackTimer) clearTimeout(this.ackTimer);
+    this.ackTimer = null;
+    this.pendingAcks.clear();
+    if (this.statsTimer) clearInterval(this.statsTimer);
+    this.statsTimer = null;
     this.callbacks.clear();
     this.progressCallbacks.clear();
     this.reconnectConfigs.clear();
@@ -18,7 +18,12 @@ const SEARCH_DECORATIONS = {
 
 interface UseTerminalOptions {
   onData?: (data: string) => void;
+  // xterm's binary input (mouse reports in X10 mode): one byte per char code
+  onBinary?: (data: Uint8Array) => void;
   onResize?: (cols: number, rows: number) => void;
+  // Clipboard paste before xterm sees it: true when handled (xterm skips it).
+  // `bracketed`: the remote program enabled bracketed paste mode.
+  onPaste?: (text: string, bracketed: boolean) => boolean;
   fontSize?: number;
   background?: string;
   isLight?: boolean;
@@ -168,6 +173,20 @@ export function useTerminal(options: UseTerminalOptions = {}) {
     terminal.onData((data) => {
       optionsRef.current.onData?.(data);
     });
+    terminal.onBinary((data) => {
+      optionsRef.current.onBinary?.(Uint8Array.from(data, (c) => c.charCodeAt(0) & 0xff));
+    });
+
+    // Capture phase: runs before xterm's own paste handler on its textarea
+    const handlePaste = (event: ClipboardEvent) => {
+      const text = event.clipboardData?.getData('text/plain');
+      if (!text) return;
+      if (optionsRef.current.onPaste?.(text, terminal.modes.bracketedPasteMode)) {
+        event.preventDefault();
+        event.stopPropagation();
+      }
+    };
+    container.addEventListener('paste', handlePaste, true);
 
     // Notify PTY on ANY dimension change (fit from observer, font-size/zoom
     // changes, etc.) — xterm only fires this when cols/rows actually change
@@ -192,6 +211,7 @@ export function useTerminal(options: UseTerminalOptions = {}) {
     return () => {
       if (resizeTimerRef.current) clearTimeout(resizeTimerRef.current);
       resizeObserver.disconnect();
+      container.removeEventListener('paste', handlePaste, true);
       // A throw here would unmount the whole React tree (black screen):
       // dispose defensively
       disposeWebgl();
@@ -225,8 +245,13 @@ export function useTerminal(options: UseTerminalOptions = {}) {
     searchAddonRef.current?.clearDecorations();
   }, []);
 
-  const write = useCallback((data: string) => {
-    terminalRef.current?.write(data);
+  // done: called once xterm has processed the data (flow control acks)
+  const write = useCallback((data: string, done?: () => void) => {
+    if (terminalRef.current) {
+      terminalRef.current.write(data, done);
+    } else {
+      done?.();
+    }
   }, []);
 
   const writeln = useCallback((data: string) => {
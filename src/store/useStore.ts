@@ -10,6 +10,7 @@ import type {
   SessionGroup,
   AppSettings,
 } from '../types';
+import { pushActivity } from '../utils/throughput';
 
 // Generate unique IDs
 const generateId = () => crypto.randomUUID();
@@ -36,6 +37,16 @@ const defaultSettings: AppSettings = {
   cursorStyle: 'block',
   scrollback: 10000,
   logCommands: true,
+  reuseConnections: true,
+  transcriptMaxMb: 10,
+  transcriptInput: false,
+  transcriptPlainText: false,
+  bracketedPaste: true,
+  shellReadyQuietMs: 800,
+  shellReadyTimeoutMs: 15000,
+  outputBufferKb: 512,
+  verboseInputLog: false,
+  networkWatchdogSecs: 60,
 };
 
 export const useStore = create<AppStore>()(
@@ -69,6 +80,7 @@ export const useStore = create<AppStore>()(
       commandModal: { isOpen: false },
       infoModal: { isOpen: false },
       settingsModal: { isOpen: false },
+      hostKeyModal: { isOpen: false },
       commandPaletteOpen: false,
       sidebarCollapsed: false,
       sidebarWidth: 280,
@@ -301,6 +313,25 @@ export const useStore = create<AppStore>()(
     return tabId;
   },
 
+  createLocalTab: () => {
+    const tabId = generateId();
+    const newTab: TerminalTab = {
+      id: tabId,
+      sessionId: '',
+      title: 'Terminal local',
+      isActive: true,
+      status: 'idle',
+      local: true,
+    };
+
+    set((state) => ({
+      tabs: [...state.tabs.map((t) => ({ ...t, isActive: false })), newTab],
+      activeTabId: tabId,
+    }));
+
+    return tabId;
+  },
+
   closeTab: async (tabId) => {
     const { tabs, activeTabId } = get();
     const tabToClose = tabs.find((t) => t.id === tabId);
@@ -344,13 +375,85 @@ export const useStore = create<AppStore>()(
   },
 
   updateTabStatus: (tabId, status, channelId) => {
+    // The backend keeps transcripts and recordings across its reconnects
+    const channelAlive = status === 'connected' || status === 'connecting';
     set((state) => ({
       tabs: state.tabs.map((t) =>
-        t.id === tabId ? { ...t, status, channelId: channelId ?? t.channelId } : t
+        t.id === tabId
+          ? {
+              ...t,
+              status,
+              channelId: channelId ?? t.channelId,
+              latencyMs: status === 'connected' ? t.latencyMs : undefined,
+              activity: status === 'connected' ? t.activity : undefined,
+              logPath: channelAlive ? t.logPath : undefined,
+              recordingPath: channelAlive ? t.recordingPath : undefined,
+              shellPrompt: channelAlive ? t.shellPrompt : undefined,
+            }
+          : t
       ),
     }));
   },
 
+  updateTabLatency: (tabId, latencyMs) => {
+    set((state) => ({
+      tabs: state.tabs.map((t) => (t.id === tabId ? { ...t, latencyMs } : t)),
+    }));
+  },
+
+  updateTabActivity: (tabId, stats) => {
+    set((state) => ({
+      tabs: state.tabs.map((t) =>
+        t.id === tabId
+          ? {
+              ...t,
+              activity: pushActivity(t.activity, stats.sentPerSec + stats.receivedPerSec),
+              sentPerSec: stats.sentPerSec,
+              receivedPerSec: stats.receivedPerSec,
+            }
+          : t
+      ),
+    }));
+  },
+
+  setTabLogPath: (tabId, logPath) => {
+    set((state) => ({
+      tabs: state.tabs.map((t) => (t.id === tabId ? { ...t, logPath } : t)),
+    }));
+  },
+
+  // The tab is named after the program: "zsh", "cmd.exe"
+  setTabShell: (tabId, shell) => {
+    const title = shell.split(/[\\/]/).pop() || shell;
+    set((state) => ({
+      tabs: state.tabs.map((t) => (t.id === tabId ? { ...t, shell, title } : t)),
+    }));
+  },
+
+  setTabRecordingPath: (tabId, recordingPath) => {
+    set((state) => ({
+      tabs: state.tabs.map((t) => (t.id === tabId ? { ...t, recordingPath } : t)),
+    }));
+  },
+
+  setTabPasteProgress: (tabId, pasteProgress) => {
+    set((state) => ({
+      tabs: state.tabs.map((t) => (t.id === tabId ? { ...t, pasteProgress } : t)),
+    }));
+  },
+
+  setTabShellPrompt: (tabId, shellPrompt) => {
+    set((state) => ({
+      tabs: state.tabs.map((t) => (t.id === tabId ? { ...t, shellPrompt } : t)),
+    }));
+  },
+
+  toggleTabBroadcast: (tabId) => {
+    set((state) => ({
+      tabs: state.tabs.map((t) => (t.id === tabId ? { ...t, broadcast: !t.broadcast } : t)),
+    }));
+  },
+
   setTabBuffer: (tabId, text) => {
     set((state) => ({
       tabBuffers: { ...state.tabBuffers, [tabId]: text },
@@ -438,6 +541,14 @@ export const useStore = create<AppStore>()(
     set({ settingsModal: { isOpen: false } });
   },
 
+  openHostKeyModal: (data) => {
+    set({ hostKeyModal: { isOpen: true, data } });
+  },
+
+  closeHostKeyModal: () => {
+    set({ hostKeyModal: { isOpen: false } });
+  },
+
   toggleCommandPalette: () => {
     set((state) => ({ commandPaletteOpen: !state.commandPaletteOpen }));
   },
@@ -35,6 +35,36 @@ export interface JumpHop {
   privateKeyPassphrase?: string;
 }
 
+// Proxy in front of the first hop (or the host when there are no jumps).
+// The password is write-only: empty on edit keeps the stored one.
+export type ProxyType = 'http' | 'socks5';
+
+export interface ProxyConfig {
+  proxyType: ProxyType;
+  host: string;
+  port: number;
+  username?: string | null;
+  password?: string | null;
+}
+
+// One knock of a port knocking sequence (knockd): a TCP SYN or UDP packet,
+// then delayMs of wait before the next knock or the connection
+export type KnockProtocol = 'tcp' | 'udp';
+
+export interface KnockStep {
+  port: number;
+  protocol: KnockProtocol;
+  delayMs?: number;
+}
+
+// Typed by the backend when the recent output matches pattern (a regex):
+// pagers, "Press any key" banners. once: only the first match per tab.
+export interface AutoResponseRule {
+  pattern: string;
+  response: string;
+  once?: boolean;
+}
+
 export interface Session {
   id: string;
   name: string;
@@ -45,9 +75,61 @@ export interface Session {
   password?: string;
   privateKeyPath?: string;
   privateKeyPassphrase?: string;
+  // Pasted private key text (write-only: never returned by get_sessions).
+  // Takes precedence over privateKeyPath.
+  privateKeyData?: string;
+  // TOTP seed (base32 or otpauth:// URI) answering the verification code
+  // prompt after the password. Write-only, like the password.
+  totpSecret?: string;
   jumpHops?: JumpHop[];
   // When true this session can be picked as a jump host by other sessions.
   usableAsJump?: boolean;
+  // Forward the local SSH agent into the remote shell (SSH_AUTH_SOCK)
+  agentForwarding?: boolean;
+  // Show remote X11 windows (virt-manager, wireshark) on the local display
+  x11Forwarding?: boolean;
+  // Ask for zlib transport compression (slow / high-latency links)
+  compression?: boolean;
+  // Seconds of silence before a keepalive probe (default 30, 0 = off);
+  // three unanswered probes close the connection
+  keepaliveInterval?: number;
+  // HTTP CONNECT / SOCKS5 proxy used to reach the first hop
+  proxy?: ProxyConfig | null;
+  // Sent to the host before every connect (and reconnect)
+  knockSequence?: KnockStep[];
+  // Wake-on-LAN: magic packet MAC and broadcast ("192.168.1.255[:port]",
+  // default 255.255.255.255:9)
+  wolMacAddress?: string | null;
+  wolBroadcastAddress?: string | null;
+  // PTY terminal type, the remote $TERM (default xterm-256color)
+  term?: string;
+  // Remote terminal charset for non-UTF-8 servers (latin1, gbk, shift_jis…)
+  encoding?: string;
+  // Disconnect after this many seconds without keystrokes nor output
+  // (0/absent = never); idle_warning comes a minute before
+  idleTimeoutSecs?: number;
+  // Append the terminal output of every connect to a transcript file under
+  // <data dir>/logs (ssh_set_logging toggles it mid-session)
+  logToFile?: boolean;
+  // Regex for this server's prompt: shell_ready fires when the open line
+  // matches it instead of after a quiet period (slow logins, odd prompts)
+  promptPattern?: string | null;
+  // Typed by the backend once the shell is ready (one line at a time,
+  // startupLineDelayMs apart). Sensitive ones are stored encrypted and come
+  // back empty, like secrets: empty on save keeps the stored command.
+  startupCommand?: string | null;
+  startupCommandSensitive?: boolean;
+  startupLineDelayMs?: number;
+  autoResponses?: AutoResponseRule[];
+  // Type the stored password at sudo's prompt (at most 3 times a minute;
+  // refused without a stored password). sudoPromptPattern: a regex for
+  // the prompt in other locales (default "[sudo] password for <user>:",
+  // with the session's username: another user's prompt is not answered)
+  autoSudoPassword?: boolean;
+  sudoPromptPattern?: string | null;
+  // The server may set the local clipboard with OSC 52 (tmux, vim; at most
+  // 100 KB). Reading the clipboard is never allowed
+  allowRemoteClipboard?: boolean;
   color: SessionColor;
   // Optional icon name; when absent the sidebar shows the colored dot.
   icon?: string | null;
@@ -80,6 +162,33 @@ export interface AppSettings {
   // Audit: capture launched commands in the session log (events are always
   // logged). Default on; the password-prompt guard skips secret input.
   logCommands?: boolean;
+  // New tabs to a host/port/user already open share its connection (one
+  // channel each) instead of authenticating again. Default on.
+  reuseConnections?: boolean;
+  // Session transcripts (logToFile sessions / tab menu): size before a new
+  // part file starts (MB, default 10) and whether typed lines go in too
+  // (lines answering a password prompt never do). Input default off.
+  transcriptMaxMb?: number;
+  transcriptInput?: boolean;
+  // Write what the terminal showed (no colors / escape sequences, '\r'
+  // overwrites applied) to a .txt instead of the raw stream. Default off.
+  transcriptPlainText?: boolean;
+  // Large pastes go between bracketed-paste markers when the remote program
+  // enabled the mode (mode 2004), so shells don't run each line. Default on.
+  bracketedPaste?: boolean;
+  // shell_ready detection: output quiet this long with a prompt-like line
+  // (ms, default 800) and the longest wait for the prompt (default 15000)
+  shellReadyQuietMs?: number;
+  shellReadyTimeoutMs?: number;
+  // Output the backend keeps per channel (KiB, default 512, 0 = none): a
+  // reloaded window repaints its reattached terminals from it
+  outputBufferKb?: number;
+  // Debugging only: the app's log gets the typed text itself, passwords
+  // included, instead of just its length. Default off; applies on connect.
+  verboseInputLog?: boolean;
+  // Seconds without output nor keepalive answer before a connection is
+  // probed and, unanswered, dropped (default 60, min 10, 0 = off)
+  networkWatchdogSecs?: number;
 }
 
 // ==================== SESSION AUDIT LOG ====================
@@ -119,6 +228,208 @@ export interface ConnectParams {
   cols?: number;
   rows?: number;
   progressId?: string;
+  // Fill empty user/port/key/ProxyJump from ~/.ssh/config (matched by host)
+  useSshConfig?: boolean;
+  // Forward the agent for this connection even if the session doesn't
+  agentForwarding?: boolean;
+  // Same for X11 forwarding and transport compression
+  x11Forwarding?: boolean;
+  compression?: boolean;
+  // Preferred algorithm lists for this connection (defaults when omitted)
+  algorithms?: AlgorithmPrefs;
+  // Resolve + TCP connect deadline per hop, every address included (default
+  // 10000), and max wait for each handshake/auth reply (default 15000);
+  // clamped to 500 ms – 5 min
+  connectTimeoutMs?: number;
+  ioTimeoutMs?: number;
+  // Overrides the session's keepaliveInterval for this connection
+  keepaliveInterval?: number;
+  // Same for idleTimeoutSecs (0 = off)
+  idleTimeoutSecs?: number;
+  // Seconds between channel_latency RTT samples (default 10, 0 = off)
+  latencyIntervalSecs?: number;
+  // Open the shell on an open connection to the same host/port/user when
+  // there is one (ConnectInfo.reusedConnection says whether it happened)
+  reuseConnection?: boolean;
+  // Transcript rotation size (MB) and typed-line capture
+  logMaxMb?: number;
+  logInput?: boolean;
+  logPlainText?: boolean;
+  shellReadyQuietMs?: number;
+  shellReadyTimeoutMs?: number;
+  // Reconnect in the backend when the connection drops, keeping the same
+  // channelId (pty_reconnecting / pty_reconnected events); after
+  // reconnectMaxAttempts (default 5) pty_closed says 'reconnect_failed'
+  autoReconnect?: boolean;
+  reconnectMaxAttempts?: number;
+  // Id for ssh_cancel_connect (the tab id); a cancelled connect rejects
+  // with { kind: 'cancelled' }
+  connectId?: string;
+  // Address family tried first when a host resolves to both (default: the
+  // resolver's order); the other family is still tried as a fallback
+  ipPreference?: 'system' | 'ipv4' | 'ipv6';
+  // Proxy for this connection, replacing the session's one (default port
+  // 8080 for http, 1080 for socks5)
+  proxyType?: ProxyType;
+  proxyHost?: string;
+  proxyPort?: number;
+  proxyUsername?: string;
+  proxyPassword?: string;
+  // Connect without sending the session's knock sequence
+  skipKnock?: boolean;
+  // Connect without typing the session's startup command
+  skipStartupCommand?: boolean;
+  // Output kept by the backend for ssh_get_buffer (KiB, default 512)
+  outputBufferKb?: number;
+  // Trace-log the typed text, not only its length
+  verboseInputLog?: boolean;
+  // Silence before the connection is probed (default 60, 0 = off)
+  networkWatchdogSecs?: number;
+  // Send the session's magic packet and wait up to wakeTimeoutSecs (default
+  // 90) for the host; connect_progress repeats 'waking' meanwhile. Behind
+  // jump hops or a proxy the packet is only sent, without waiting
+  wakeOnLan?: boolean;
+  wakeTimeoutSecs?: number;
+  // Terminal type for this connection instead of the session's
+  term?: string;
+}
+
+// OpenSSH algorithm names, most preferred first; an empty list keeps the
+// built-in order. ssh_supported_algorithms returns the accepted values.
+export interface AlgorithmPrefs {
+  kex?: string[];
+  hostKey?: string[];
+  cipher?: string[];
+  mac?: string[];
+}
+
+export interface NegotiatedAlgorithms {
+  kex: string;
+  hostKey: string;
+  cipher: string;
+  mac: string;
+  compression: string;
+}
+
+// connect_progress event, per host of the chain (hop: jump host number,
+// null for the target). The ssh_connect error ends with "(last stage: x)".
+export type ConnectStage =
+  | 'waking'
+  | 'knocking'
+  | 'resolving'
+  | 'tcp_connected'
+  | 'handshake_done'
+  | 'authenticating'
+  | `jump_hop_${number}`
+  | 'opening_channel'
+  | 'shell_started'
+  // ssh_test_connection only: authentication succeeded
+  | 'authenticated';
+
+export interface ConnectProgress {
+  connectId: string;
+  stage: ConnectStage;
+  host: string;
+  port: number;
+  hop?: number | null;
+  // 'waking' only: seconds waited so far and the limit
+  elapsedSecs?: number;
+  timeoutSecs?: number;
+}
+
+// ssh_connect result. agentForwarding: undefined/null = not requested,
+// false = refused by the server (the shell still opens without it).
+// compression: negotiated algorithm, 'none' unless requested and offered.
+export interface ConnectInfo {
+  channelId: string;
+  agentForwarding?: boolean | null;
+  // 'on', or why remote X11 windows won't show; null = not requested
+  x11Forwarding?: 'on' | 'no_display' | 'invalid_display' | 'unreachable' | 'refused' | null;
+  compression: string;
+  algorithms?: NegotiatedAlgorithms | null;
+  // Terminal type requested for the PTY: what $TERM is on the remote side
+  term: string;
+  // Charset output/input are converted from/to ('UTF-8', 'GBK'…); a latin1
+  // session says 'windows-1252', which it really is (0x80–0x9F printable)
+  encoding: string;
+  // New channel on a connection another tab opened
+  reusedConnection: boolean;
+  // Transcript being written (logToFile sessions)
+  logPath?: string | null;
+  // autoSudoPassword: 'on', or why the helper refused to run; null = off
+  sudoHelper?: 'on' | 'no_password' | 'invalid_pattern' | null;
+}
+
+// local_connect result: the channel takes the same ssh_send / ssh_resize /
+// ssh_disconnect calls and pty_output / pty_closed events as an SSH one
+export interface LocalInfo {
+  channelId: string;
+  shell: string;
+}
+
+// ssh_get_connection_info: details of an open channel's connection.
+// Byte counters are terminal data since ssh_connect (reconnects included);
+// connectedAtMs is the current connection's start (Unix ms)
+export interface ConnectionInfo {
+  host: string;
+  port: number;
+  username: string;
+  serverVersion?: string | null;
+  algorithms?: NegotiatedAlgorithms | null;
+  hostKeyFingerprint?: string | null;
+  jumpHops: number;
+  connectedAtMs: number;
+  bytesSent: number;
+  bytesReceived: number;
+}
+
+// ssh_get_channel_stats: terminal bytes since connect and the current rate
+export interface ChannelStats {
+  bytesSent: number;
+  bytesReceived: number;
+  sentPerSec: number;
+  receivedPerSec: number;
+}
+
+export type ForwardKind = 'local' | 'remote' | 'dynamic';
+
+// A saved forward (get_forwards / save_forward): started after the shell on
+// every connect of its session while enabled. bindHost '' = loopback
+export interface ForwardRule {
+  id: string;
+  sessionId: string;
+  kind: ForwardKind;
+  bindHost: string;
+  bindPort: number;
+  targetHost?: string | null;
+  targetPort?: number | null;
+  enabled: boolean;
+}
+
+// ssh_forward_* / ssh_start_socks result, ssh_list_forwards entry.
+// bindPort is the port listening (the server's or the OS's pick when 0 was
+// asked for); dynamic (SOCKS5) forwards have no fixed target
+export interface ForwardInfo {
+  forwardId: string;
+  channelId: string;
+  kind: ForwardKind;
+  bindHost: string;
+  bindPort: number;
+  targetHost?: string | null;
+  targetPort?: number | null;
+}
+
+// ssh_list_channels: a channel the backend still holds (after a webview
+// reload the frontend rebinds it to a new tab and claims it)
+export interface ChannelSummary {
+  channelId: string;
+  sessionId: string;
+  host: string;
+  port: number;
+  username: string;
+  connectedAtMs: number;
+  isConnected: boolean;
+  hasJump: boolean;
 }
 
 export interface SSHConnection {
@@ -137,6 +448,27 @@ export interface TerminalTab {
   title: string;
   isActive: boolean;
   status: 'idle' | 'connecting' | 'connected' | 'disconnected' | 'error';
+  // Smoothed RTT of the last channel_latency event; cleared when not connected
+  latencyMs?: number;
+  // Send + receive rate of the last ssh_get_channel_stats polls (bytes/s)
+  activity?: number[];
+  // Rates of the last poll, for the tab tooltip
+  sentPerSec?: number;
+  receivedPerSec?: number;
+  // Cluster typing: input typed in any broadcast tab goes to all of them
+  broadcast?: boolean;
+  // Path of the transcript being written (ssh_set_logging) and of the
+  // asciinema recording in progress; both outlive backend reconnects
+  logPath?: string;
+  recordingPath?: string;
+  // Fraction written of the large paste in progress (ssh_paste)
+  pasteProgress?: number;
+  // Prompt seen by the shell_ready event: the shell finished its login
+  shellPrompt?: string;
+  // Local shell tab (local_connect): no session behind it, sessionId is ''
+  local?: boolean;
+  // Program the local tab runs ("/bin/zsh", "cmd.exe")
+  shell?: string;
 }
 
 export interface TabBuffersState {
@@ -158,6 +490,158 @@ export interface ToastMessage {
   action?: { label: string; onClick: () => void };
 }
 
+// One host alias from ~/.ssh/config (ssh_config_hosts), options resolved
+// with OpenSSH first-match-wins semantics
+export interface SshConfigHost {
+  alias: string;
+  hostName?: string | null;
+  user?: string | null;
+  port?: number | null;
+  identityFile?: string | null;
+  proxyJump?: string | null;
+}
+
+// ssh_install_public_key outcome (ssh-copy-id); the target is either
+// { channelId } of an open terminal or { sessionId } for a one-shot connect
+export type KeyInstallResult = 'added' | 'alreadyPresent';
+
+// Where ssh_exec / ssh_install_public_key run
+export type RemoteTarget = { channelId: string } | { sessionId: string };
+
+// ssh_exec result: the command ran without a PTY. truncated: stdout or
+// stderr went past maxOutputBytes (default 1 MiB each)
+export interface ExecResult {
+  stdout: string;
+  stderr: string;
+  exitStatus?: number | null;
+  exitSignal?: string | null;
+  durationMs: number;
+  truncated: boolean;
+}
+
+// One step of ssh_run_queue. status: 'ok' (exit 0), 'failed' (another
+// status or a signal), 'timed_out' (past stepTimeoutMs), 'cancelled'
+// (ssh_cancel_queue) or 'error' (it could not run; see error)
+export interface QueueStep extends ExecResult {
+  command: string;
+  status: 'ok' | 'failed' | 'timed_out' | 'cancelled' | 'error';
+  error?: string;
+}
+
+// ssh_run_queue result: the steps that ran, in order. Fewer steps than
+// commands without cancelled = a failed step stopped it (stopOnError)
+export interface QueueOutcome {
+  steps: QueueStep[];
+  cancelled: boolean;
+}
+
+// exec_multi_result event of ssh_exec_multi: one host's outcome (name and
+// host are empty when the session id was not found)
+export interface HostResult extends QueueStep {
+  batchId: string;
+  sessionId: string;
+  name: string;
+  host: string;
+}
+
+// ssh_exec_multi result, also sent as exec_multi_done. errors: hosts that
+// could not run the command (unreachable, authentication, timeout)
+export interface MultiExecSummary {
+  batchId: string;
+  total: number;
+  ok: number;
+  failed: number;
+  errors: number;
+  durationMs: number;
+}
+
+// generate_keypair result (files written with 600/644 permissions)
+export interface GeneratedKey {
+  privateKeyPath: string;
+  publicKeyPath: string;
+  publicKey: string;
+  fingerprint: string;
+}
+
+// ssh_get_host_fingerprint result: handshake only, no authentication
+// ssh_test_connection: each stage reached, atMs after the start, lasted
+// durationMs until the next one
+export interface PhaseTiming {
+  stage: ConnectStage;
+  host: string;
+  port: number;
+  hop?: number | null;
+  atMs: number;
+  durationMs: number;
+}
+
+export interface TestReport {
+  phases: PhaseTiming[];
+  totalMs: number;
+  // Identification string of the server ("SSH-2.0-OpenSSH_9.6")
+  serverVersion?: string | null;
+  algorithms?: NegotiatedAlgorithms | null;
+}
+
+// ssh_test_connection rejection, from the AppError and its details; phase
+// is the last stage reached
+export interface TestFailure {
+  kind: AppErrorKind;
+  message: string;
+  phase?: ConnectStage | null;
+  phases: PhaseTiming[];
+}
+
+export interface HostFingerprint {
+  banner: string;
+  keyType: string;
+  md5: string;
+  sha256: string;
+}
+
+// What every command rejects with (error::AppError): the UI decides on
+// kind; message is the backend's text; details depends on the kind
+// (hostKeyChanged: the HostKeyChange fields, hostKeyUnknown: host, port,
+// keyType and fingerprint; ssh_test_connection adds phase and phases)
+export type AppErrorKind =
+  | 'auth'
+  | 'keyPassphrase'
+  | 'hostKeyUnknown'
+  | 'hostKeyChanged'
+  | 'hostKey'
+  | 'network'
+  | 'timeout'
+  | 'proxy'
+  | 'proxyAuth'
+  | 'notFound'
+  | 'permissionDenied'
+  | 'cancelled'
+  | 'invalidInput'
+  | 'database'
+  | 'io'
+  | 'failed';
+
+export interface AppError {
+  kind: AppErrorKind;
+  message: string;
+  details?: Record<string, unknown>;
+}
+
+// ssh_connect error when a server's host key no longer matches the stored
+// one: the AppError's details (ssh::HostKeyChange on the backend) with its
+// kind and message
+export interface HostKeyChange {
+  kind: 'hostKeyChanged';
+  message: string;
+  host: string;
+  port: number;
+  keyType: string;
+  oldFingerprint: string;
+  newFingerprint: string;
+  knownHosts: string;
+  line: number;
+}
+
 export interface ModalState {
   isOpen: boolean;
   data?: unknown;
@@ -199,9 +683,18 @@ export interface TerminalsSlice {
   tabs: TerminalTab[];
   activeTabId: string | null;
   createTab: (sessionId: string) => string;
+  createLocalTab: () => string;
   closeTab: (tabId: string) => Promise<void>;
   setActiveTab: (tabId: string) => void;
   updateTabStatus: (tabId: string, status: TerminalTab['status'], channelId?: string) => void;
+  updateTabLatency: (tabId: string, latencyMs: number) => void;
+  updateTabActivity: (tabId: string, stats: ChannelStats) => void;
+  toggleTabBroadcast: (tabId: string) => void;
+  setTabLogPath: (tabId: string, logPath?: string) => void;
+  setTabShell: (tabId: string, shell: string) => void;
+  setTabRecordingPath: (tabId: string, recordingPath?: string) => void;
+  setTabPasteProgress: (tabId: string, pasteProgress?: number) => void;
+  setTabShellPrompt: (tabId: string, shellPrompt?: string) => void;
 }
 
 export interface UISlice {
@@ -211,6 +704,7 @@ export interface UISlice {
   commandModal: ModalState & { data?: { command?: SavedCommand; mode?: 'create' | 'edit' } };
   infoModal: ModalState & { data?: { session: Session } };
   settingsModal: ModalState;
+  hostKeyModal: ModalState & { data?: HostKeyChange };
   commandPaletteOpen: boolean;
   sidebarCollapsed: boolean;
   sidebarWidth: number; // expanded width in px (resizable)
@@ -228,6 +722,8 @@ export interface UISlice {
   closeInfoModal: () => void;
   openSettingsModal: () => void;
   closeSettingsModal: () => void;
+  openHostKeyModal: (data: HostKeyChange) => void;
+  closeHostKeyModal: () => void;
   toggleCommandPalette: () => void;
   closeCommandPalette: () => void;
   toggleSidebar: () => void;
@@ -0,0 +1,18 @@
+import { describe, it, expect } from 'vitest';
+import { asAppError, errorMessage } from './appError';
+
+describe('asAppError', () => {
+  it('keeps backend errors and wraps anything else', () => {
+    const backend = {
+      kind: 'hostKeyUnknown',
+      message: 'Unknown host key for srv:22: ssh-ed25519 SHA256:abc',
+      details: { host: 'srv', port: 22, keyType: 'ssh-ed25519', fingerprint: 'SHA256:abc' },
+    };
+    expect(asAppError(backend)).toBe(backend);
+    expect(asAppError('boom')).toEqual({ kind: 'failed', message: 'boom' });
+    expect(asAppError(new Error('oops'))).toEqual({ kind: 'failed', message: 'oops' });
+    expect(errorMessage({ kind: 'auth', message: 'Authentication failed: denied' })).toBe(
+      'Authentication failed: denied'
+    );
+  });
+});
@@ -0,0 +1,17 @@
+// Every Tauri command rejects with { kind, message, details? }
+// (error::AppError on the backend). Anything else that reaches a catch (a
+// JS exception, a plain string) is taken as kind 'failed'.
+import type { AppError } from '../types';
+
+export function asAppError(error: unknown): AppError {
+  if (typeof error === 'object' && error !== null && 'kind' in error && 'message' in error) {
+    return error as AppError;
+  }
+  if (error instanceof Error) return { kind: 'failed', message: error.message };
+  return { kind: 'failed', message: String(error) };
+}
+
+// The text for toasts and logs
+export function errorMessage(error: unknown): string {
+  return asAppError(error).message;
+}
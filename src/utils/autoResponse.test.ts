@@ -0,0 +1,30 @@
+import { describe, it, expect } from 'vitest';
+import { formatAutoResponses, parseAutoResponses } from './autoResponse';
+
+describe('parseAutoResponses', () => {
+  it('reads rules, once markers and comments', () => {
+    expect(
+      parseAutoResponses('# pagers\n--More-- =>  \nonce: Press any key => \\r\n\n\\[y/N\\] => y\\r')
+    ).toEqual({
+      ok: true,
+      rules: [
+        { pattern: '--More--', response: ' ' },
+        { pattern: 'Press any key', response: '\\r', once: true },
+        { pattern: '\\[y/N\\]', response: 'y\\r' },
+      ],
+    });
+    expect(parseAutoResponses('  ')).toEqual({ ok: true, rules: [] });
+  });
+
+  it('reports the first invalid line', () => {
+    expect(parseAutoResponses('ok => y\nno arrow here')).toEqual({ ok: false, line: 'no arrow here' });
+    expect(parseAutoResponses('([ => y')).toEqual({ ok: false, line: '([ => y' });
+    expect(parseAutoResponses(' => y')).toEqual({ ok: false, line: '=> y' });
+  });
+
+  it('formats back to the same text', () => {
+    const text = '--More-- => \\t\nonce: Continue\\? => yes\\r';
+    const parsed = parseAutoResponses(text);
+    expect(parsed.ok && formatAutoResponses(parsed.rules)).toBe(text);
+  });
+});
@@ -0,0 +1,43 @@
+// Text form of the auto-response rules in the session form, one rule per
+// line: "pattern => response", with "once:" in front for a rule that fires
+// only once per tab. The response keeps its \r \n \t escapes (the backend
+// expands them); lines starting with # are comments.
+import type { AutoResponseRule } from '../types';
+
+const ONCE = /^once:\s*/i;
+const ARROW = ' => ';
+
+/**
+ * Parse the text form. Returns the rules, or the first line that is not a
+ * valid rule (missing " => " or a pattern that is not a regex).
+ */
+export function parseAutoResponses(
+  text: string
+): { ok: true; rules: AutoResponseRule[] } | { ok: false; line: string } {
+  const rules: AutoResponseRule[] = [];
+  for (const raw of text.split('\n')) {
+    // The end is kept: a pager's answer is often a single space
+    const line = raw.replace(/\r$/, '').trimStart();
+    if (!line.trim() || line.startsWith('#')) continue;
+    const once = ONCE.test(line);
+    const body = line.replace(ONCE, '');
+    const arrow = body.indexOf(ARROW);
+    const pattern = arrow < 0 ? '' : body.slice(0, arrow).trim();
+    if (!pattern) {
+      return { ok: false, line };
+    }
+    try {
+      new RegExp(pattern);
+    } catch {
+      return { ok: false, line };
+    }
+    rules.push({ pattern, response: body.slice(arrow + ARROW.length), ...(once ? { once } : {}) });
+  }
+  return { ok: true, rules };
+}
+
+export function formatAutoResponses(rules: AutoResponseRule[] | undefined): string {
+  return (rules ?? [])
+    .map((rule) => `${rule.once ? 'once: ' : ''}${rule.pattern}${ARROW}${rule.response}`)
+    .join('\n');
+}
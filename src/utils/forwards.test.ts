@@ -0,0 +1,49 @@
+import { describe, it, expect } from 'vitest';
+import { formatForwards, parseForwards } from './forwards';
+
+describe('parseForwards', () => {
+  it('reads the three kinds, binds and disabled lines', () => {
+    const text = 'L 8080:db.internal:5432\n\n# r 0.0.0.0:0:localhost:3000\nD [::1]:1080';
+    expect(parseForwards(text)).toEqual({
+      ok: true,
+      forwards: [
+        {
+          kind: 'local',
+          bindHost: '',
+          bindPort: 8080,
+          targetHost: 'db.internal',
+          targetPort: 5432,
+          enabled: true,
+        },
+        {
+          kind: 'remote',
+          bindHost: '0.0.0.0',
+          bindPort: 0,
+          targetHost: 'localhost',
+          targetPort: 3000,
+          enabled: false,
+        },
+        {
+          kind: 'dynamic',
+          bindHost: '::1',
+          bindPort: 1080,
+          targetHost: null,
+          targetPort: null,
+          enabled: true,
+        },
+      ],
+    });
+  });
+
+  it('reports the first invalid line', () => {
+    expect(parseForwards('D 1080\nL 8080:db')).toEqual({ ok: false, line: 'L 8080:db' });
+    expect(parseForwards('L 8080:db:0')).toEqual({ ok: false, line: 'L 8080:db:0' });
+    expect(parseForwards('X 8080')).toEqual({ ok: false, line: 'X 8080' });
+  });
+
+  it('formats back to the same text', () => {
+    const text = 'L 127.0.0.1:8080:[::1]:80\n# R 9000:localhost:3000\nD 1080';
+    const parsed = parseForwards(text);
+    expect(parsed.ok && formatForwards(parsed.forwards)).toBe(text);
+  });
+});
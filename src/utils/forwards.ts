@@ -0,0 +1,96 @@
+// Text form of a session's saved forwards, one per line as in ssh's flags:
+// "L 8080:db.internal:5432", "R 0.0.0.0:9000:localhost:3000", "D 1080".
+// A leading '#' keeps the forward but disabled.
+import { invoke } from '@tauri-apps/api/core';
+import type { ForwardKind, ForwardRule } from '../types';
+
+type ForwardSpec = Omit<ForwardRule, 'id' | 'sessionId'>;
+
+const KINDS: Record<string, ForwardKind> = { l: 'local', r: 'remote', d: 'dynamic' };
+const LETTERS: Record<ForwardKind, string> = { local: 'L', remote: 'R', dynamic: 'D' };
+
+function port(text: string, allowZero: boolean): number | null {
+  if (!/^\d{1,5}$/.test(text)) return null;
+  const n = Number(text);
+  return n <= 65535 && (n > 0 || allowZero) ? n : null;
+}
+
+// [bind:]port for dynamic, [bind:]port:host:hostport otherwise. IPv6 goes
+// in brackets ("[::1]:8080:...")
+function parseLine(line: string): ForwardSpec | null {
+  let text = line.trim();
+  const enabled = !text.startsWith('#');
+  if (!enabled) text = text.slice(1).trim();
+  const match = /^([lrd])\s+(\S+)$/i.exec(text);
+  if (!match) return null;
+  const kind = KINDS[match[1].toLowerCase()];
+  const parts = match[2].match(/\[[^\]]*\]|[^:]+/g) ?? [];
+  const unbracket = (part: string) => part.replace(/^\[(.*)\]$/, '$1');
+  const fields = kind === 'dynamic' ? 1 : 3;
+  if (parts.length !== fields && parts.length !== fields + 1) return null;
+  const bindHost = parts.length > fields ? unbracket(parts.shift()!) : '';
+  const bindPort = port(parts[0], true);
+  if (bindPort === null) return null;
+  if (kind === 'dynamic') {
+    return { kind, bindHost, bindPort, targetHost: null, targetPort: null, enabled };
+  }
+  const targetPort = port(parts[2], false);
+  const targetHost = unbracket(parts[1]);
+  if (targetPort === null || !targetHost) return null;
+  return { kind, bindHost, bindPort, targetHost, targetPort, enabled };
+}
+
+/** Parse the text form. Returns the forwards, or the first invalid line. */
+export function parseForwards(
+  text: string
+): { ok: true; forwards: ForwardSpec[] } | { ok: false; line: string } {
+  const forwards: ForwardSpec[] = [];
+  for (const line of text.split('\n').filter((l) => l.trim())) {
+    const spec = parseLine(line);
+    if (!spec) return { ok: false, line: line.trim() };
+    forwards.push(spec);
+  }
+  return { ok: true, forwards };
+}
+
+function host(text: string): string {
+  return text.includes(':') ? `[${text}]` : text;
+}
+
+export function formatForward(rule: ForwardSpec): string {
+  const bind = `${rule.bindHost ? `${host(rule.bindHost)}:` : ''}${rule.bindPort}`;
+  const target =
+    rule.kind === 'dynamic' ? '' : `:${host(rule.targetHost ?? '')}:${rule.targetPort ?? 0}`;
+  return `${rule.enabled ? '' : '# '}${LETTERS[rule.kind]} ${bind}${target}`;
+}
+
+export function formatForwards(rules: ForwardSpec[]): string {
+  return rules.map(formatForward).join('\n');
+}
+
+export async function fetchForwards(sessionId: string): Promise<ForwardRule[]> {
+  return invoke<ForwardRule[]>('get_forwards', { sessionId });
+}
+
+/**
+ * Make the saved forwards of a session `specs`. Unchanged lines keep their
+ * rule (and id); the rest are saved anew and the ones gone are deleted.
+ */
+export async function replaceForwards(
+  sessionId: string,
+  current: ForwardRule[],
+  specs: ForwardSpec[]
+): Promise<void> {
+  const unused = [...current];
+  for (const spec of specs) {
+    const at = unused.findIndex((rule) => formatForward(rule) === formatForward(spec));
+    if (at >= 0) {
+      unused.splice(at, 1);
+      continue;
+    }
+    await invoke('save_forward', { forward: { ...spec, id: crypto.randomUUID(), sessionId } });
+  }
+  for (const rule of unused) {
+    await invoke('delete_forward', { id: rule.id });
+  }
+}
@@ -0,0 +1,27 @@
+import { describe, it, expect } from 'vitest';
+import { formatKnockSequence, parseKnockSequence } from './knock';
+
+describe('parseKnockSequence', () => {
+  it('reads ports, protocols and delays', () => {
+    expect(parseKnockSequence('7000, 8000/udp@500 9000/TCP')).toEqual({
+      ok: true,
+      steps: [
+        { port: 7000, protocol: 'tcp' },
+        { port: 8000, protocol: 'udp', delayMs: 500 },
+        { port: 9000, protocol: 'tcp' },
+      ],
+    });
+    expect(parseKnockSequence('  ')).toEqual({ ok: true, steps: [] });
+  });
+
+  it('reports the first invalid token', () => {
+    expect(parseKnockSequence('7000 70000 8000')).toEqual({ ok: false, token: '70000' });
+    expect(parseKnockSequence('7000/icmp')).toEqual({ ok: false, token: '7000/icmp' });
+  });
+
+  it('formats back to the same text', () => {
+    const text = '7000 8000/udp@500 9000@1000';
+    const parsed = parseKnockSequence(text);
+    expect(parsed.ok && formatKnockSequence(parsed.steps)).toBe(text);
+  });
+});
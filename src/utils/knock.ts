@@ -0,0 +1,39 @@
+// Text form of a port knocking sequence, as typed in the session form and
+// in CSV imports: "7000 8000/udp@500 9000" = TCP 7000, UDP 8000 then wait
+// 500 ms, TCP 9000. Commas work as separators too.
+import type { KnockProtocol, KnockStep } from '../types';
+
+const STEP = /^(\d{1,5})(?:\/(tcp|udp))?(?:@(\d+))?$/i;
+
+/**
+ * Parse the text form. Returns the steps, or the first token that is not a
+ * valid knock (port 1-65535, optional /tcp|/udp, optional @delayMs).
+ */
+export function parseKnockSequence(
+  text: string
+): { ok: true; steps: KnockStep[] } | { ok: false; token: string } {
+  const steps: KnockStep[] = [];
+  for (const token of text.split(/[\s,]+/).filter(Boolean)) {
+    const match = STEP.exec(token);
+    const port = match ? Number(match[1]) : 0;
+    if (!match || port < 1 || port > 65535) {
+      return { ok: false, token };
+    }
+    const delayMs = match[3] ? Number(match[3]) : 0;
+    steps.push({
+      port,
+      protocol: (match[2]?.toLowerCase() ?? 'tcp') as KnockProtocol,
+      ...(delayMs ? { delayMs } : {}),
+    });
+  }
+  return { ok: true, steps };
+}
+
+export function formatKnockSequence(steps: KnockStep[] | undefined): string {
+  return (steps ?? [])
+    .map(
+      (step) =>
+        `${step.port}${step.protocol === 'udp' ? '/udp' : ''}${step.delayMs ? `@${step.delayMs}` : ''}`
+    )
+    .join(' ');
+}
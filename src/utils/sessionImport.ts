@@ -1,4 +1,5 @@
-import type { AuthMethod, JumpHop, Session, SessionColor } from '../types';
+import type { AuthMethod, JumpHop, KnockStep, ProxyConfig, ProxyType, Session, SessionColor } from '../types';
+import { parseKnockSequence } from './knock';
 
 export type ImportedSession = Omit<Session, 'id' | 'createdAt'> & {
   groupName?: string;
@@ -23,13 +24,21 @@ const VALID_COLORS: SessionColor[] = [
 // Values include legacy flat jump columns (jumpHost...) that are folded
 // into the jumpHops array during normalization
 const HEADER_ALIASES: Record<string, string> = {
+  agent_forwarding: 'agentForwarding',
+  agentforwarding: 'agentForwarding',
   auth: 'authMethod',
   auth_method: 'authMethod',
   authmethod: 'authMethod',
+  charset: 'encoding',
   color: 'color',
+  compression: 'compression',
+  encoding: 'encoding',
   group: 'groupName',
   groupname: 'groupName',
   host: 'host',
+  idle_timeout: 'idleTimeoutSecs',
+  idle_timeout_secs: 'idleTimeoutSecs',
+  idletimeoutsecs: 'idleTimeoutSecs',
   ip: 'host',
   jump_host: 'jumpHost',
   jumphost: 'jumpHost',
@@ -44,23 +53,46 @@ const HEADER_ALIASES: Record<string, string> = {
   jumpport: 'jumpPort',
   jump_username: 'jumpUsername',
   jumpusername: 'jumpUsername',
+  keepalive_interval: 'keepaliveInterval',
+  keepaliveinterval: 'keepaliveInterval',
+  knock_sequence: 'knockSequence',
+  knocksequence: 'knockSequence',
   name: 'name',
   nombre: 'name',
   pass: 'password',
   password: 'password',
   port: 'port',
+  private_key_data: 'privateKeyData',
   private_key_passphrase: 'privateKeyPassphrase',
   private_key_path: 'privateKeyPath',
+  privatekeydata: 'privateKeyData',
   privatekeypassphrase: 'privateKeyPassphrase',
   privatekeypath: 'privateKeyPath',
+  proxy_host: 'proxyHost',
+  proxy_password: 'proxyPassword',
+  proxy_port: 'proxyPort',
+  proxy_type: 'proxyType',
+  proxy_username: 'proxyUsername',
+  proxyhost: 'proxyHost',
+  proxypassword: 'proxyPassword',
+  proxyport: 'proxyPort',
+  proxytype: 'proxyType',
+  proxyusername: 'proxyUsername',
   puerto: 'port',
   server: 'host',
   session: 'name',
+  term: 'term',
+  totp_secret: 'totpSecret',
+  totpsecret: 'totpSecret',
   user: 'username',
   username: 'username',
   usuario: 'username',
   usableasjump: 'usableAsJump',
   usable_as_jump: 'usableAsJump',
+  wol_broadcast_address: 'wolBroadcastAddress',
+  wol_mac_address: 'wolMacAddress',
+  wolbroadcastaddress: 'wolBroadcastAddress',
+  wolmacaddress: 'wolMacAddress',
 };
 
 export function parseSessionsFile(fileName: string, content: string): SessionImportResult {
@@ -211,9 +243,17 @@ function normalizeSession(row: Record<string, unknown>):
   }
 
   const privateKeyPath = readText(row, 'privateKeyPath');
-  const authMethod = normalizeAuthMethod(readText(row, 'authMethod'), privateKeyPath);
-  if (authMethod === 'key' && !privateKeyPath) {
-    return { ok: false, error: 'privateKeyPath es obligatorio cuando authMethod es key' };
+  // Pasted key text (as exported); it takes precedence over the path
+  const privateKeyData = readText(row, 'privateKeyData');
+  const authMethod = normalizeAuthMethod(
+    readText(row, 'authMethod'),
+    privateKeyPath || privateKeyData
+  );
+  if (authMethod === 'key' && !privateKeyPath && !privateKeyData) {
+    return {
+      ok: false,
+      error: 'privateKeyPath o privateKeyData es obligatorio cuando authMethod es key',
+    };
   }
 
   const jumpResult = normalizeJumpHops(row);
@@ -221,6 +261,16 @@ function normalizeSession(row: Record<string, unknown>):
     return jumpResult;
   }
 
+  const proxyResult = normalizeProxy(row);
+  if (!proxyResult.ok) {
+    return proxyResult;
+  }
+
+  const knockResult = normalizeKnockSequence(row);
+  if (!knockResult.ok) {
+    return knockResult;
+  }
+
   return {
     ok: true,
     session: {
@@ -230,10 +280,22 @@ function normalizeSession(row: Record<string, unknown>):
       username,
       authMethod,
       password: authMethod === 'password' ? readText(row, 'password') : undefined,
+      totpSecret: authMethod === 'password' ? readText(row, 'totpSecret') : undefined,
       privateKeyPath: authMethod === 'key' ? privateKeyPath : undefined,
       privateKeyPassphrase: authMethod === 'key' ? readText(row, 'privateKeyPassphrase') : undefined,
+      privateKeyData: authMethod === 'key' ? privateKeyData : undefined,
       jumpHops: jumpResult.hops,
       usableAsJump: row.usableAsJump === true || readText(row, 'usableAsJump') === 'true',
+      agentForwarding: row.agentForwarding === true || readText(row, 'agentForwarding') === 'true',
+      compression: row.compression === true || readText(row, 'compression') === 'true',
+      keepaliveInterval: Math.max(0, readNumber(row, 'keepaliveInterval', 30)),
+      proxy: proxyResult.proxy,
+      knockSequence: knockResult.steps,
+      wolMacAddress: readText(row, 'wolMacAddress') ?? null,
+      wolBroadcastAddress: readText(row, 'wolBroadcastAddress') ?? null,
+      term: readText(row, 'term'),
+      encoding: readText(row, 'encoding'),
+      idleTimeoutSecs: Math.max(0, readNumber(row, 'idleTimeoutSecs', 0)),
       color: normalizeColor(readText(row, 'color')),
       groupName: readText(row, 'groupName'),
     },
@@ -295,6 +357,68 @@ function normalizeJumpHops(
   };
 }
 
+/// Reads the proxy from a proxy object (JSON export) or the flat
+/// proxyType/proxyHost/proxyPort/proxyUsername/proxyPassword columns (CSV)
+function normalizeProxy(
+  row: Record<string, unknown>
+): { ok: true; proxy: ProxyConfig | null } | { ok: false; error: string } {
+  const source = isRecord(row.proxy)
+    ? normalizeInputRow(row.proxy)
+    : {
+        proxyType: row.proxyType,
+        host: row.proxyHost,
+        port: row.proxyPort,
+        username: row.proxyUsername,
+        password: row.proxyPassword,
+      };
+  const host = readText(source, 'host');
+  if (!host) {
+    return { ok: true, proxy: null };
+  }
+  const type = readText(source, 'proxyType')?.toLowerCase();
+  const proxyType: ProxyType | undefined =
+    type === 'http' || type === 'socks5' ? type : type === 'socks' ? 'socks5' : undefined;
+  if (!proxyType) {
+    return { ok: false, error: 'proxyType debe ser http o socks5' };
+  }
+  const port = readNumber(source, 'port', proxyType === 'http' ? 8080 : 1080);
+  if (port < 1 || port > 65535) {
+    return { ok: false, error: 'proxyPort debe estar entre 1 y 65535' };
+  }
+  return {
+    ok: true,
+    proxy: {
+      proxyType,
+      host,
+      port,
+      username: readText(source, 'username') ?? null,
+      password: readText(source, 'password') ?? null,
+    },
+  };
+}
+
+/// Knock steps from a knockSequence array (JSON export) or its text form
+/// "7000 8000/udp@500" (CSV)
+function normalizeKnockSequence(
+  row: Record<string, unknown>
+): { ok: true; steps: KnockStep[] } | { ok: false; error: string } {
+  const text = Array.isArray(row.knockSequence)
+    ? row.knockSequence
+        .map((raw) =>
+          isRecord(raw)
+            ? `${readText(raw, 'port') ?? ''}${readText(raw, 'protocol') === 'udp' ? '/udp' : ''}${
+                readText(raw, 'delayMs') ? `@${readText(raw, 'delayMs')}` : ''
+              }`
+            : String(raw)
+        )
+        .join(' ')
+    : readText(row, 'knockSequence') ?? '';
+  const parsed = parseKnockSequence(text);
+  return parsed.ok
+    ? parsed
+    : { ok: false, error: `knockSequence contiene un knock no valido: ${parsed.token}` };
+}
+
 function normalizeHeader(header: string) {
   const key = header.trim().toLowerCase().replace(/[\s-]+/g, '_');
   return HEADER_ALIASES[key] ?? key;
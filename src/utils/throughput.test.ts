@@ -0,0 +1,25 @@
+import { describe, it, expect } from 'vitest';
+import { ACTIVITY_HISTORY, formatBytes, formatRate, pushActivity, sparklinePoints } from './throughput';
+
+describe('throughput', () => {
+  it('keeps only the last samples', () => {
+    let history: number[] | undefined;
+    for (let i = 0; i < ACTIVITY_HISTORY + 5; i++) history = pushActivity(history, i);
+    expect(history).toHaveLength(ACTIVITY_HISTORY);
+    expect(history?.[0]).toBe(5);
+  });
+
+  it('formats rates', () => {
+    expect(formatRate(820.4)).toBe('820 B/s');
+    expect(formatRate(12.5 * 1024)).toBe('12.5 KB/s');
+    expect(formatRate(3.1 * 1024 * 1024)).toBe('3.1 MB/s');
+    expect(formatBytes(2.5 * 1024 * 1024)).toBe('2.5 MB');
+  });
+
+  it('scales the sparkline to the peak', () => {
+    expect(sparklinePoints([5], 20, 10)).toBe('');
+    expect(sparklinePoints([0, 10, 5], 20, 10)).toBe('0.0,10.0 10.0,0.0 20.0,5.0');
+    // All idle: flat line at the bottom
+    expect(sparklinePoints([0, 0], 20, 10)).toBe('0.0,10.0 20.0,10.0');
+  });
+});
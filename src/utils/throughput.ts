@@ -0,0 +1,32 @@
+// Tab activity sparkline: history of ssh_get_channel_stats rates
+
+// Rates kept per tab (one per poll)
+export const ACTIVITY_HISTORY = 30;
+
+export function pushActivity(history: number[] | undefined, rate: number): number[] {
+  const next = [...(history ?? []), rate];
+  return next.length > ACTIVITY_HISTORY ? next.slice(next.length - ACTIVITY_HISTORY) : next;
+}
+
+// "820 B", "12.5 KB", "3.1 MB"
+export function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${Math.round(bytes)} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+// "820 B/s", "12.5 KB/s", "3.1 MB/s"
+export function formatRate(bytesPerSec: number): string {
+  return `${formatBytes(bytesPerSec)}/s`;
+}
+
+
+// SVG polyline points, scaled to the highest rate of the history
+export function sparklinePoints(history: number[], width: number, height: number): string {
+  if (history.length < 2) return '';
+  const max = Math.max(...history, 1);
+  const step = width / (history.length - 1);
+  return history
+    .map((rate, i) => `${(i * step).toFixed(1)},${(height - (rate / max) * height).toFixed(1)}`)
+    .join(' ');
+}